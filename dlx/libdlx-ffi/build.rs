@@ -0,0 +1,28 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Generates the C header for the functions in src/lib.rs into OUT_DIR and copies it to include/dlx.h,
+// which is committed so that C code can use it without building the crate. The copy is only
+// written when the header changed, so that it does not touch the source tree on every build.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let generated = out_dir.join("dlx.h");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate the C header.")
+        .write_to_file(&generated);
+
+    let header = fs::read(&generated).unwrap();
+    let committed = crate_dir.join("include").join("dlx.h");
+    if fs::read(&committed).ok().as_ref() != Some(&header) {
+        fs::write(&committed, header).expect("Unable to copy the C header to include/dlx.h.");
+    }
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
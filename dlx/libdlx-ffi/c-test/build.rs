@@ -0,0 +1,12 @@
+// Compiles the C test program against the generated header.
+// It is linked into the binary of this crate, together with libdlx-ffi.
+fn main() {
+    cc::Build::new()
+        .file("dlx_test.c")
+        .include("../include")
+        .warnings(true)
+        .compile("dlx_test");
+
+    println!("cargo:rerun-if-changed=dlx_test.c");
+    println!("cargo:rerun-if-changed=../include/dlx.h");
+}
@@ -0,0 +1,194 @@
+/*
+ * Exercises the C interface of libdlx through dlx.h.
+ * Build and run it with cargo run -p libdlx-ffi-c-test, it prints ok or the failed check.
+ */
+#include <stdio.h>
+#include "dlx.h"
+
+#define CHECK(condition) \
+    do { \
+        if (!(condition)) { \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+            return 1; \
+        } \
+    } while (0)
+
+#define CHECK_OK(call) \
+    do { \
+        DlxStatus status = (call); \
+        if (status != DLX_STATUS_OK) { \
+            fprintf(stderr, "%s:%d: %s: %s\n", __FILE__, __LINE__, #call, dlx_status_message(status)); \
+            return 1; \
+        } \
+    } while (0)
+
+/* Knuth's example with the items a to g, whose only solution is c e, a d f and b g. */
+static DlxProblem *knuth_example(void) {
+    static const uint32_t options[6][3] = {
+        {2, 4}, {0, 3, 6}, {1, 2, 5}, {0, 3, 5}, {1, 6}, {3, 4, 6}
+    };
+    static const size_t lengths[6] = {2, 3, 3, 3, 2, 3};
+    DlxProblem *problem = dlx_problem_new(7, 0, 0);
+    for (size_t i = 0; i < 6; i++) {
+        if (dlx_problem_add_option(problem, options[i], lengths[i]) != DLX_STATUS_OK) {
+            dlx_problem_free(problem);
+            return NULL;
+        }
+    }
+    return problem;
+}
+
+static int test_exact_cover(void) {
+    DlxProblem *problem = knuth_example();
+    CHECK(problem != NULL);
+    CHECK(dlx_problem_option_count(problem) == 6);
+
+    DlxSolutions *solutions = NULL;
+    CHECK_OK(dlx_solve_first(problem, 0, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 1);
+    size_t len = 0;
+    const size_t *options = dlx_solution_options(solutions, 0, &len);
+    CHECK(options != NULL);
+    CHECK(len == 3);
+    CHECK(options[0] == 0 && options[1] == 3 && options[2] == 4);
+    CHECK(dlx_solution_options(solutions, 1, &len) == NULL);
+    dlx_solutions_free(solutions);
+
+    CHECK_OK(dlx_solve_all(problem, 0, 1000, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 1);
+    CHECK(dlx_solutions_complete(solutions));
+    dlx_solutions_free(solutions);
+
+    uint64_t count = 0;
+    bool complete = false;
+    CHECK_OK(dlx_solve_count(problem, 0, &count, &complete));
+    CHECK(count == 1);
+    CHECK(complete);
+
+    dlx_problem_free(problem);
+    return 0;
+}
+
+static int test_colors(void) {
+    /* items 0 and 1 are primary, item 2 is secondary, the colors are 1 and 2 */
+    DlxProblem *problem = dlx_problem_new(2, 1, 2);
+    const uint32_t first[2] = {0, 2};
+    const uint32_t second[2] = {1, 2};
+    const uint32_t red[2] = {0, 1};
+    const uint32_t blue[2] = {0, 2};
+    CHECK_OK(dlx_problem_add_colored_option(problem, first, red, 2));
+    CHECK_OK(dlx_problem_add_colored_option(problem, second, blue, 2));
+    CHECK_OK(dlx_problem_add_colored_option(problem, second, red, 2));
+
+    DlxSolutions *solutions = NULL;
+    CHECK_OK(dlx_solve_all(problem, 0, 0, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 1);
+    size_t len = 0;
+    const size_t *options = dlx_solution_options(solutions, 0, &len);
+    CHECK(len == 2);
+    CHECK(options[0] == 0 && options[1] == 2);
+    dlx_solutions_free(solutions);
+
+    dlx_problem_free(problem);
+    return 0;
+}
+
+static int test_min_cost(void) {
+    DlxProblem *problem = dlx_problem_new(2, 0, 0);
+    const uint32_t both[2] = {0, 1};
+    CHECK_OK(dlx_problem_add_option(problem, both, 1));
+    CHECK_OK(dlx_problem_add_option(problem, both + 1, 1));
+    CHECK_OK(dlx_problem_add_option(problem, both, 2));
+    CHECK_OK(dlx_problem_set_cost(problem, 0, 1));
+    CHECK_OK(dlx_problem_set_cost(problem, 1, 1));
+    CHECK_OK(dlx_problem_set_cost(problem, 2, 3));
+
+    DlxSolutions *solutions = NULL;
+    CHECK_OK(dlx_solve_min_cost(problem, 0, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 1);
+    CHECK(dlx_solutions_complete(solutions));
+    CHECK(dlx_solution_cost(solutions, 0) == 2);
+    size_t len = 0;
+    const size_t *options = dlx_solution_options(solutions, 0, &len);
+    CHECK(len == 2);
+    CHECK(options[0] == 0 && options[1] == 1);
+    dlx_solutions_free(solutions);
+
+    CHECK_OK(dlx_problem_set_cost(problem, 2, 1));
+    CHECK_OK(dlx_solve_min_cost(problem, 0, &solutions));
+    CHECK(dlx_solution_cost(solutions, 0) == 1);
+    options = dlx_solution_options(solutions, 0, &len);
+    CHECK(len == 1);
+    CHECK(options[0] == 2);
+    dlx_solutions_free(solutions);
+
+    dlx_problem_free(problem);
+    return 0;
+}
+
+static int test_identical_options(void) {
+    /* the options 0 and 1 cover the same item, they are told apart by their index and cost */
+    DlxProblem *problem = dlx_problem_new(1, 0, 0);
+    const uint32_t item[1] = {0};
+    CHECK_OK(dlx_problem_add_option(problem, item, 1));
+    CHECK_OK(dlx_problem_add_option(problem, item, 1));
+    CHECK_OK(dlx_problem_set_cost(problem, 0, 5));
+    CHECK_OK(dlx_problem_set_cost(problem, 1, 2));
+
+    DlxSolutions *solutions = NULL;
+    CHECK_OK(dlx_solve_all(problem, 0, 0, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 2);
+    size_t len = 0;
+    const size_t *first = dlx_solution_options(solutions, 0, &len);
+    const size_t *second = dlx_solution_options(solutions, 1, &len);
+    CHECK(first[0] + second[0] == 1);
+    dlx_solutions_free(solutions);
+
+    CHECK_OK(dlx_solve_min_cost(problem, 0, &solutions));
+    CHECK(dlx_solution_cost(solutions, 0) == 2);
+    const size_t *options = dlx_solution_options(solutions, 0, &len);
+    CHECK(len == 1);
+    CHECK(options[0] == 1);
+    dlx_solutions_free(solutions);
+
+    dlx_problem_free(problem);
+    return 0;
+}
+
+static int test_errors(void) {
+    DlxProblem *problem = dlx_problem_new(2, 1, 1);
+    const uint32_t missing[1] = {3};
+    const uint32_t twice[2] = {1, 1};
+    const uint32_t items[2] = {0, 2};
+    const uint32_t colors[2] = {0, 2};
+    CHECK(dlx_problem_add_option(problem, missing, 1) == DLX_STATUS_INVALID_ITEM);
+    CHECK(dlx_problem_add_option(problem, twice, 2) == DLX_STATUS_DUPLICATE_ITEM);
+    CHECK(dlx_problem_add_colored_option(problem, items, colors, 2) == DLX_STATUS_INVALID_COLOR);
+    CHECK(dlx_problem_add_option(problem, NULL, 2) == DLX_STATUS_NULL_POINTER);
+    CHECK(dlx_problem_add_option(NULL, items, 2) == DLX_STATUS_NULL_POINTER);
+    CHECK(dlx_problem_option_count(problem) == 0);
+    CHECK(dlx_problem_set_cost(problem, 0, 1) == DLX_STATUS_INVALID_OPTION);
+    CHECK(dlx_solve_first(problem, 0, NULL) == DLX_STATUS_NULL_POINTER);
+    CHECK(dlx_solve_count(problem, 0, NULL, NULL) == DLX_STATUS_NULL_POINTER);
+    CHECK(dlx_status_message(DLX_STATUS_INVALID_ITEM) != NULL);
+
+    /* no option covers the items, so there is no solution */
+    DlxSolutions *solutions = NULL;
+    CHECK_OK(dlx_solve_first(problem, 0, &solutions));
+    CHECK(dlx_solutions_len(solutions) == 0);
+    CHECK(dlx_solutions_complete(solutions));
+    dlx_solutions_free(solutions);
+
+    dlx_problem_free(problem);
+    dlx_problem_free(NULL);
+    dlx_solutions_free(NULL);
+    return 0;
+}
+
+int dlx_test_main(void) {
+    if (test_exact_cover() || test_colors() || test_min_cost() || test_identical_options() || test_errors()) {
+        return 1;
+    }
+    printf("ok\n");
+    return 0;
+}
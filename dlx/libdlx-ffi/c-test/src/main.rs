@@ -0,0 +1,14 @@
+use std::os::raw::c_int;
+use std::process;
+
+// the C functions must be linked from libdlx-ffi
+extern crate dlx_ffi;
+
+extern "C" {
+    fn dlx_test_main() -> c_int;
+}
+
+// Runs the C test program in dlx_test.c, run it with cargo run -p libdlx-ffi-c-test.
+fn main() {
+    process::exit(unsafe { dlx_test_main() });
+}
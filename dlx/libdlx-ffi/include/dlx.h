@@ -0,0 +1,175 @@
+/*
+ * The C interface of libdlx.
+ *
+ * Items are numbered from 0, the primary items first and then the secondary ones.
+ * Colors are numbered from 1, with 0 for no color. Options are numbered in the order
+ * they were added. Identical options cannot be told apart in solutions, they are
+ * reported as the first of them.
+ *
+ * Problems and solutions are opaque handles owned by the caller, who frees them with
+ * dlx_problem_free and dlx_solutions_free. Pointers passed in must be NULL or valid
+ * for the given lengths. The functions that can fail return a DlxStatus.
+ */
+
+#ifndef DLX_H
+#define DLX_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of the functions that can fail.
+ */
+typedef enum DlxStatus {
+  DLX_STATUS_OK = 0,
+  /**
+   * A pointer that must not be NULL is.
+   */
+  DLX_STATUS_NULL_POINTER,
+  /**
+   * An item is not below the number of items of the problem.
+   */
+  DLX_STATUS_INVALID_ITEM,
+  /**
+   * A color is above the number of colors of the problem.
+   */
+  DLX_STATUS_INVALID_COLOR,
+  /**
+   * An option has the same item twice.
+   */
+  DLX_STATUS_DUPLICATE_ITEM,
+  /**
+   * An option index is not below the number of options.
+   */
+  DLX_STATUS_INVALID_OPTION,
+  /**
+   * libdlx panicked, which is a bug. The message is printed to stderr.
+   */
+  DLX_STATUS_PANIC,
+} DlxStatus;
+
+/**
+ * A problem that options are added to before it is solved.
+ */
+typedef struct DlxProblem DlxProblem;
+
+/**
+ * The solutions that a solve function found.
+ */
+typedef struct DlxSolutions DlxSolutions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * A problem with no options. Items 0 to primary_count - 1 are primary,
+ * the next secondary_count items secondary. The colors are 1 to color_count.
+ */
+struct DlxProblem *dlx_problem_new(uint32_t primary_count,
+                                   uint32_t secondary_count,
+                                   uint32_t color_count);
+
+void dlx_problem_free(struct DlxProblem *problem);
+
+/**
+ * Adds an option with the len items.
+ */
+enum DlxStatus dlx_problem_add_option(struct DlxProblem *problem,
+                                      const uint32_t *items,
+                                      size_t len);
+
+/**
+ * Adds an option with the len items, which the option gives the colors.
+ * colors can be NULL for an option without colors, and 0 leaves an item without a color.
+ * A primary item with a color is covered and given the color.
+ */
+enum DlxStatus dlx_problem_add_colored_option(struct DlxProblem *problem,
+                                              const uint32_t *items,
+                                              const uint32_t *colors,
+                                              size_t len);
+
+/**
+ * Sets the cost of an option for dlx_solve_min_cost, options cost 0 otherwise.
+ */
+enum DlxStatus dlx_problem_set_cost(struct DlxProblem *problem, size_t option, uint64_t cost);
+
+/**
+ * The number of options added so far, 0 for NULL.
+ */
+size_t dlx_problem_option_count(const struct DlxProblem *problem);
+
+/**
+ * The first solution, or none if there is none or the time limit is hit first.
+ * A time limit of 0 means no limit.
+ */
+enum DlxStatus dlx_solve_first(const struct DlxProblem *problem,
+                               uint64_t time_limit_ms,
+                               struct DlxSolutions **out);
+
+/**
+ * Up to max_solutions solutions, or all of them if it is 0, found within the time limit.
+ * A time limit of 0 means no limit.
+ */
+enum DlxStatus dlx_solve_all(const struct DlxProblem *problem,
+                             size_t max_solutions,
+                             uint64_t time_limit_ms,
+                             struct DlxSolutions **out);
+
+/**
+ * Counts the solutions found within the time limit. complete tells whether that is all of them
+ * and can be NULL. A time limit of 0 means no limit.
+ */
+enum DlxStatus dlx_solve_count(const struct DlxProblem *problem,
+                               uint64_t time_limit_ms,
+                               uint64_t *count,
+                               bool *complete);
+
+/**
+ * The cheapest solution, or the cheapest one found before the time limit, with
+ * dlx_solutions_complete telling which. A time limit of 0 means no limit.
+ */
+enum DlxStatus dlx_solve_min_cost(const struct DlxProblem *problem,
+                                  uint64_t time_limit_ms,
+                                  struct DlxSolutions **out);
+
+void dlx_solutions_free(struct DlxSolutions *solutions);
+
+/**
+ * The number of solutions, 0 for NULL.
+ */
+size_t dlx_solutions_len(const struct DlxSolutions *solutions);
+
+/**
+ * Whether the search was exhausted, so there are no solutions besides these.
+ */
+bool dlx_solutions_complete(const struct DlxSolutions *solutions);
+
+/**
+ * The options of a solution in increasing order, with their number written to len.
+ * NULL if the solution does not exist. The options live as long as the solutions.
+ */
+const size_t *dlx_solution_options(const struct DlxSolutions *solutions,
+                                   size_t solution,
+                                   size_t *len);
+
+/**
+ * The total cost of the options of a solution, 0 if it does not exist.
+ */
+uint64_t dlx_solution_cost(const struct DlxSolutions *solutions, size_t solution);
+
+/**
+ * A description of the status, valid for the lifetime of the program.
+ */
+const char *dlx_status_message(enum DlxStatus status);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* DLX_H */
@@ -0,0 +1,379 @@
+// A C interface to libdlx over integer items, see include/dlx.h, which build.rs generates
+// from this file. Only the comments starting with three slashes end up in the header.
+// Pointers from C are checked for NULL and panics are caught, so that they do not unwind into C.
+#![allow(clippy::missing_safety_doc)]
+
+use std::os::raw::c_char;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+use std::time::Instant;
+use libdlx::dlxc::Item;
+use libdlx::dlxc::State;
+use libdlx::dlxc::dlxc_iter;
+use libdlx::min_cost_dlxc::min_cost_dlxc_iter;
+
+/// The result of the functions that can fail.
+#[repr(C)]
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum DlxStatus {
+    Ok = 0,
+    /// A pointer that must not be NULL is.
+    NullPointer,
+    /// An item is not below the number of items of the problem.
+    InvalidItem,
+    /// A color is above the number of colors of the problem.
+    InvalidColor,
+    /// An option has the same item twice.
+    DuplicateItem,
+    /// An option index is not below the number of options.
+    InvalidOption,
+    /// libdlx panicked, which is a bug. The message is printed to stderr.
+    Panic
+}
+
+/// A problem that options are added to before it is solved.
+pub struct DlxProblem {
+    primary_count: u32,
+    secondary_count: u32,
+    color_count: u32,
+    // the items of every option with their colors, 0 for none
+    options: Vec<Vec<(u32, u32)>>,
+    costs: Vec<u64>
+}
+
+/// The solutions that a solve function found.
+pub struct DlxSolutions {
+    // the sorted options of every solution, with its cost
+    solutions: Vec<(Vec<usize>, u64)>,
+    complete: bool
+}
+
+type Sets = Vec<Vec<Item<u32, u32, u32>>>;
+
+impl DlxProblem {
+    fn add_option(&mut self, items: &[u32], colors: &[u32]) -> Result<(), DlxStatus> {
+        let item_count = self.primary_count as u64 + self.secondary_count as u64;
+        for (i, &item) in items.iter().enumerate() {
+            if item as u64 >= item_count {
+                return Err(DlxStatus::InvalidItem)
+            }
+            if items[..i].contains(&item) {
+                return Err(DlxStatus::DuplicateItem)
+            }
+        }
+        if colors.iter().any(|&color| color > self.color_count) {
+            return Err(DlxStatus::InvalidColor)
+        }
+
+        self.options.push(items.iter().cloned().zip(colors.iter().cloned()).collect());
+        self.costs.push(0);
+        Ok(())
+    }
+
+    fn item(&self, (item, color): (u32, u32)) -> Item<u32, u32, u32> {
+        match (item < self.primary_count, color) {
+            (true, 0) => Item::Primary(item),
+            (true, color) => Item::ColoredPrimary(item, color),
+            (false, 0) => Item::Secondary(item),
+            (false, color) => Item::ColoredSecondary(item, color)
+        }
+    }
+
+    // Every option gets a secondary item of its own after the items of the problem, which tells the
+    // decoder which option a row of a solution is, even if other options have the same items and colors.
+    fn tag(&self, option: usize) -> u32 {
+        self.primary_count + self.secondary_count + option as u32
+    }
+
+    fn sets(&self) -> Sets {
+        self.options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| option
+                .iter()
+                .map(|&item| self.item(item))
+                .chain(std::iter::once(Item::Secondary(self.tag(i))))
+                .collect())
+            .collect()
+    }
+
+    fn primary_items(&self) -> Vec<u32> {
+        (0..self.primary_count).collect()
+    }
+
+    fn secondary_items(&self) -> Vec<u32> {
+        (self.primary_count..self.tag(self.options.len())).collect()
+    }
+
+    fn colors(&self) -> Vec<u32> {
+        (1..=self.color_count).collect()
+    }
+
+    // The solvers give the options of a solution as their items, among them the tag of the option.
+    fn decoder(&self) -> impl Fn(&Sets) -> (Vec<usize>, u64) + '_ {
+        let first_tag = self.tag(0);
+        move |rows| {
+            let mut options: Vec<usize> = rows
+                .iter()
+                .map(|row| row
+                    .iter()
+                    .find_map(|&item| match item {
+                        Item::Secondary(item) if item >= first_tag => Some((item - first_tag) as usize),
+                        _ => None
+                    })
+                    .unwrap_or_else(|| panic!("{:?} is not an option.", row)))
+                .collect();
+            options.sort_unstable();
+            let cost = options
+                .iter()
+                .fold(0u64, |cost, &option| cost.saturating_add(self.costs[option]));
+            (options, cost)
+        }
+    }
+}
+
+// 0 stands for no time limit.
+fn time_limit(time_limit_ms: u64) -> Duration {
+    if time_limit_ms == 0 {
+        Duration::MAX
+    }
+    else {
+        Duration::from_millis(time_limit_ms)
+    }
+}
+
+fn guard(body: impl FnOnce() -> Result<(), DlxStatus>) -> DlxStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => DlxStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => DlxStatus::Panic
+    }
+}
+
+unsafe fn slice_from<'a, T>(pointer: *const T, len: usize) -> Result<&'a [T], DlxStatus> {
+    if len == 0 {
+        Ok(&[])
+    }
+    else if pointer.is_null() {
+        Err(DlxStatus::NullPointer)
+    }
+    else {
+        Ok(slice::from_raw_parts(pointer, len))
+    }
+}
+
+unsafe fn write_solutions(out: *mut *mut DlxSolutions, solutions: DlxSolutions) {
+    *out = Box::into_raw(Box::new(solutions));
+}
+
+/// A problem with no options. Items 0 to primary_count - 1 are primary,
+/// the next secondary_count items secondary. The colors are 1 to color_count.
+#[no_mangle]
+pub extern "C" fn dlx_problem_new(primary_count: u32, secondary_count: u32, color_count: u32) -> *mut DlxProblem {
+    Box::into_raw(Box::new(DlxProblem {
+        primary_count,
+        secondary_count,
+        color_count,
+        options: Vec::new(),
+        costs: Vec::new()
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dlx_problem_free(problem: *mut DlxProblem) {
+    if !problem.is_null() {
+        drop(Box::from_raw(problem));
+    }
+}
+
+/// Adds an option with the len items.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_problem_add_option(problem: *mut DlxProblem, items: *const u32, len: usize) -> DlxStatus {
+    dlx_problem_add_colored_option(problem, items, ptr::null(), len)
+}
+
+/// Adds an option with the len items, which the option gives the colors.
+/// colors can be NULL for an option without colors, and 0 leaves an item without a color.
+/// A primary item with a color is covered and given the color.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_problem_add_colored_option(problem: *mut DlxProblem, items: *const u32, colors: *const u32,
+                                                        len: usize) -> DlxStatus {
+    guard(|| {
+        let problem = problem.as_mut().ok_or(DlxStatus::NullPointer)?;
+        let items = slice_from(items, len)?;
+        let colors = if colors.is_null() {
+            vec![0; len]
+        }
+        else {
+            slice_from(colors, len)?.to_vec()
+        };
+        problem.add_option(items, &colors)
+    })
+}
+
+/// Sets the cost of an option for dlx_solve_min_cost, options cost 0 otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_problem_set_cost(problem: *mut DlxProblem, option: usize, cost: u64) -> DlxStatus {
+    guard(|| {
+        let problem = problem.as_mut().ok_or(DlxStatus::NullPointer)?;
+        let option_cost = problem.costs.get_mut(option).ok_or(DlxStatus::InvalidOption)?;
+        *option_cost = cost;
+        Ok(())
+    })
+}
+
+/// The number of options added so far, 0 for NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_problem_option_count(problem: *const DlxProblem) -> usize {
+    problem.as_ref().map_or(0, |problem| problem.options.len())
+}
+
+/// The first solution, or none if there is none or the time limit is hit first.
+/// A time limit of 0 means no limit.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solve_first(problem: *const DlxProblem, time_limit_ms: u64,
+                                         out: *mut *mut DlxSolutions) -> DlxStatus {
+    dlx_solve_all(problem, 1, time_limit_ms, out)
+}
+
+/// Up to max_solutions solutions, or all of them if it is 0, found within the time limit.
+/// A time limit of 0 means no limit.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solve_all(problem: *const DlxProblem, max_solutions: usize, time_limit_ms: u64,
+                                       out: *mut *mut DlxSolutions) -> DlxStatus {
+    guard(|| {
+        let problem = problem.as_ref().ok_or(DlxStatus::NullPointer)?;
+        if out.is_null() {
+            return Err(DlxStatus::NullPointer)
+        }
+
+        let max_solutions = if max_solutions == 0 {
+            usize::MAX
+        }
+        else {
+            max_solutions
+        };
+        let time_limit = time_limit(time_limit_ms);
+        let decode = problem.decoder();
+        let mut iter = dlxc_iter(problem.sets(), problem.primary_items(), problem.secondary_items(), problem.colors());
+        let start = Instant::now();
+        let mut solutions = Vec::new();
+        while solutions.len() < max_solutions && start.elapsed() < time_limit {
+            match iter.next() {
+                Some((State::FoundSolution, Some((rows, _)))) => solutions.push(decode(&rows)),
+                Some(_) => {},
+                None => break
+            }
+        }
+
+        write_solutions(out, DlxSolutions { solutions, complete: iter.is_exhausted() });
+        Ok(())
+    })
+}
+
+/// Counts the solutions found within the time limit. complete tells whether that is all of them
+/// and can be NULL. A time limit of 0 means no limit.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solve_count(problem: *const DlxProblem, time_limit_ms: u64, count: *mut u64,
+                                         complete: *mut bool) -> DlxStatus {
+    guard(|| {
+        let problem = problem.as_ref().ok_or(DlxStatus::NullPointer)?;
+        let count = count.as_mut().ok_or(DlxStatus::NullPointer)?;
+        let mut iter = dlxc_iter(problem.sets(), problem.primary_items(), problem.secondary_items(), problem.colors());
+        *count = iter.count_solutions(time_limit(time_limit_ms)) as u64;
+        if let Some(complete) = complete.as_mut() {
+            *complete = iter.is_exhausted();
+        }
+        Ok(())
+    })
+}
+
+/// The cheapest solution, or the cheapest one found before the time limit, with
+/// dlx_solutions_complete telling which. A time limit of 0 means no limit.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solve_min_cost(problem: *const DlxProblem, time_limit_ms: u64,
+                                            out: *mut *mut DlxSolutions) -> DlxStatus {
+    guard(|| {
+        let problem = problem.as_ref().ok_or(DlxStatus::NullPointer)?;
+        if out.is_null() {
+            return Err(DlxStatus::NullPointer)
+        }
+
+        let decode = problem.decoder();
+        let sets = problem.sets()
+            .into_iter()
+            .zip(problem.costs.iter().cloned())
+            .collect();
+        let mut iter = min_cost_dlxc_iter(sets, problem.primary_items(), problem.secondary_items(), problem.colors());
+        let complete = iter.run(time_limit(time_limit_ms));
+        let solutions = iter.incumbent()
+            .map(|solution| decode(&solution.rows))
+            .into_iter()
+            .collect();
+
+        write_solutions(out, DlxSolutions { solutions, complete });
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solutions_free(solutions: *mut DlxSolutions) {
+    if !solutions.is_null() {
+        drop(Box::from_raw(solutions));
+    }
+}
+
+/// The number of solutions, 0 for NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solutions_len(solutions: *const DlxSolutions) -> usize {
+    solutions.as_ref().map_or(0, |solutions| solutions.solutions.len())
+}
+
+/// Whether the search was exhausted, so there are no solutions besides these.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solutions_complete(solutions: *const DlxSolutions) -> bool {
+    solutions.as_ref().is_some_and(|solutions| solutions.complete)
+}
+
+/// The options of a solution in increasing order, with their number written to len.
+/// NULL if the solution does not exist. The options live as long as the solutions.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solution_options(solutions: *const DlxSolutions, solution: usize,
+                                              len: *mut usize) -> *const usize {
+    match solutions.as_ref().and_then(|solutions| solutions.solutions.get(solution)) {
+        Some((options, _)) => {
+            if let Some(len) = len.as_mut() {
+                *len = options.len();
+            }
+            options.as_ptr()
+        },
+        None => ptr::null()
+    }
+}
+
+/// The total cost of the options of a solution, 0 if it does not exist.
+#[no_mangle]
+pub unsafe extern "C" fn dlx_solution_cost(solutions: *const DlxSolutions, solution: usize) -> u64 {
+    solutions
+        .as_ref()
+        .and_then(|solutions| solutions.solutions.get(solution))
+        .map_or(0, |&(_, cost)| cost)
+}
+
+/// A description of the status, valid for the lifetime of the program.
+#[no_mangle]
+pub extern "C" fn dlx_status_message(status: DlxStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        DlxStatus::Ok => b"ok\0",
+        DlxStatus::NullPointer => b"a pointer is NULL\0",
+        DlxStatus::InvalidItem => b"an item does not exist\0",
+        DlxStatus::InvalidColor => b"a color does not exist\0",
+        DlxStatus::DuplicateItem => b"an option has the same item twice\0",
+        DlxStatus::InvalidOption => b"an option does not exist\0",
+        DlxStatus::Panic => b"libdlx panicked\0"
+    };
+    message.as_ptr() as *const c_char
+}
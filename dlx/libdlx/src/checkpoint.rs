@@ -0,0 +1,338 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::dlxc::State;
+
+// A snapshot of a running search, to save a long run to disk and resume it where it stopped.
+// The levels refer to the nodes of the table, so a checkpoint only fits the problem that it was
+// taken from, which is checked with a fingerprint of the table. Restoring rebuilds the links
+// by covering the columns and rows on the stack again.
+// The text format has one line per field and one line per level, and ends with a line end,
+// so that a file that was cut off while it was written is not taken for a shallower search.
+#[derive(Clone,PartialEq,Debug)]
+pub struct Checkpoint<W = usize> {
+    pub(crate) fingerprint: u64,
+    pub(crate) state: State,
+    // the column and the current row of every level
+    pub(crate) levels: Vec<(usize, usize)>,
+    pub(crate) costs: Option<SearchCosts<W>>
+}
+
+// what was spent, the cost of the limit and whether it is inclusive
+pub(crate) type SavedThreshold<W> = (W, W, bool);
+
+// What min_cost_dlxc keeps besides the stack. Limits are stored as the cost and
+// whether it is inclusive, thresholds as what was spent and their limit.
+#[derive(Clone,PartialEq,Debug)]
+pub(crate) struct SearchCosts<W> {
+    pub(crate) current_cost: W,
+    pub(crate) limit: (W, bool),
+    // what was spent above every level and its hiding and covering thresholds
+    pub(crate) levels: Vec<(W, SavedThreshold<W>, SavedThreshold<W>)>,
+    // the cost and row nodes of the best solution so far
+    pub(crate) incumbent: Option<(W, Vec<usize>)>
+}
+
+// Why a checkpoint cannot be read or does not fit the search it is restored into.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub enum CheckpointError {
+    // a line that cannot be read
+    Syntax { line: usize, message: String },
+    // a field or the end is missing, for example because the file was cut off
+    Incomplete(String),
+    // the checkpoint was taken from a different problem
+    OtherProblem { fingerprint: u64, expected: u64 },
+    // the checkpoint was taken from another kind of search, or its levels do not fit the table
+    Mismatch(String)
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            CheckpointError::Incomplete(message) => write!(f, "incomplete checkpoint: {}", message),
+            CheckpointError::OtherProblem { fingerprint, expected } =>
+                write!(f, "the checkpoint was taken from a different problem, its fingerprint is {:016x} instead of {:016x}",
+                       fingerprint, expected),
+            CheckpointError::Mismatch(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+impl<W> Checkpoint<W> {
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    // True if the search had already explored everything when the checkpoint was taken.
+    pub fn is_exhausted(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    // The number of levels of the search.
+    pub fn depth(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+// Whether the row of the last level is covered, which depends on what the search does next.
+pub(crate) fn last_row_covered(state: State) -> bool {
+    matches!(state, State::CoveringColumn | State::BacktrackingRow | State::FoundSolution)
+}
+
+pub(crate) fn check_fingerprint<W>(checkpoint: &Checkpoint<W>, fingerprint: u64) -> Result<(), CheckpointError> {
+    if checkpoint.fingerprint != fingerprint {
+        return Err(CheckpointError::OtherProblem { fingerprint: checkpoint.fingerprint, expected: fingerprint })
+    }
+    Ok(())
+}
+
+// Checks the levels against the table before they are replayed.
+// is_level tells whether a row node can be the current row of a column.
+pub(crate) fn check_levels<W>(checkpoint: &Checkpoint<W>, is_level: impl Fn(usize, usize) -> bool) -> Result<(), CheckpointError> {
+    match checkpoint.levels.iter().position(|&(column, row_node)| !is_level(column, row_node)) {
+        Some(level) => Err(CheckpointError::Mismatch(format!("level {} of the checkpoint does not fit the table", level + 1))),
+        None => Ok(())
+    }
+}
+
+// FNV-1a over the links of a freshly built table, together with the names of its items.
+pub(crate) struct Fingerprint(u64);
+
+impl Fingerprint {
+    pub(crate) fn new() -> Self {
+        Fingerprint(0xcbf29ce484222325)
+    }
+
+    fn add_byte(&mut self, byte: u8) {
+        self.0 ^= byte as u64;
+        self.0 = self.0.wrapping_mul(0x100000001b3);
+    }
+
+    pub(crate) fn add(&mut self, value: usize) {
+        for &byte in (value as u64).to_le_bytes().iter() {
+            self.add_byte(byte);
+        }
+    }
+
+    pub(crate) fn add_debug(&mut self, value: &impl fmt::Debug) {
+        fmt::Write::write_fmt(self, format_args!("{:?}", value)).unwrap();
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Write for Fingerprint {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.add_byte(byte);
+        }
+        Ok(())
+    }
+}
+
+fn state_name(state: State) -> &'static str {
+    match state {
+        State::CoveringColumn => "covering_column",
+        State::CoveringRow => "covering_row",
+        State::BacktrackingColumn => "backtracking_column",
+        State::BacktrackingRow => "backtracking_row",
+        State::FoundSolution => "found_solution",
+    }
+}
+
+fn limit_name(inclusive: bool) -> &'static str {
+    if inclusive {
+        "at_most"
+    }
+    else {
+        "below"
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for Checkpoint<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "fingerprint {:016x}", self.fingerprint)?;
+        writeln!(f, "state {}", state_name(self.state))?;
+        if let Some(costs) = &self.costs {
+            writeln!(f, "cost {}", costs.current_cost)?;
+            writeln!(f, "limit {} {}", costs.limit.0, limit_name(costs.limit.1))?;
+            if let Some((cost, nodes)) = &costs.incumbent {
+                write!(f, "incumbent {}", cost)?;
+                for node in nodes {
+                    write!(f, " {}", node)?;
+                }
+                writeln!(f)?;
+            }
+            for (&(column, row_node), (spent, hiding, covering)) in self.levels.iter().zip(&costs.levels) {
+                writeln!(f, "level {} {} {} {} {} {} {} {} {}", column, row_node, spent,
+                         hiding.0, hiding.1, limit_name(hiding.2), covering.0, covering.1, limit_name(covering.2))?;
+            }
+        }
+        else {
+            for (column, row_node) in &self.levels {
+                writeln!(f, "level {} {}", column, row_node)?;
+            }
+        }
+        writeln!(f, "end")
+    }
+}
+
+fn syntax_error<T>(line: usize, message: String) -> Result<T, CheckpointError> {
+    Err(CheckpointError::Syntax { line, message })
+}
+
+fn parse_field<T: FromStr>(token: Option<&str>, line_number: usize) -> Result<T, CheckpointError> {
+    match token.map(|token| token.parse()) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) => syntax_error(line_number, format!("{} is not a valid value", token.unwrap())),
+        None => syntax_error(line_number, "a value is missing".to_string())
+    }
+}
+
+fn parse_limit(token: Option<&str>, line_number: usize) -> Result<bool, CheckpointError> {
+    match token {
+        Some("at_most") => Ok(true),
+        Some("below") => Ok(false),
+        _ => syntax_error(line_number, "a limit should be at_most or below".to_string())
+    }
+}
+
+impl<W: FromStr> Checkpoint<W> {
+    pub fn parse(text: &str) -> Result<Self, CheckpointError> {
+        let mut fingerprint = None;
+        let mut state = None;
+        let mut levels = Vec::new();
+        let mut current_cost = None;
+        let mut limit = None;
+        let mut incumbent = None;
+        let mut level_costs = Vec::new();
+        let mut complete = false;
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line_number = i + 1;
+            if complete {
+                return syntax_error(line_number, "the checkpoint continues after its end".to_string())
+            }
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("fingerprint") => {
+                    let token = tokens.next().unwrap_or("");
+                    match u64::from_str_radix(token, 16) {
+                        Ok(value) => fingerprint = Some(value),
+                        Err(_) => return syntax_error(line_number, format!("invalid fingerprint {}", token))
+                    }
+                },
+                Some("state") => {
+                    state = Some(match tokens.next() {
+                        Some("covering_column") => State::CoveringColumn,
+                        Some("covering_row") => State::CoveringRow,
+                        Some("backtracking_column") => State::BacktrackingColumn,
+                        Some("backtracking_row") => State::BacktrackingRow,
+                        Some("found_solution") => State::FoundSolution,
+                        Some(other) => return syntax_error(line_number, format!("unknown state {}", other)),
+                        None => return syntax_error(line_number, "the state is missing".to_string())
+                    });
+                },
+                Some("cost") => {
+                    current_cost = Some(parse_field(tokens.next(), line_number)?);
+                },
+                Some("limit") => {
+                    limit = Some((parse_field(tokens.next(), line_number)?, parse_limit(tokens.next(), line_number)?));
+                },
+                Some("incumbent") => {
+                    let cost = parse_field(tokens.next(), line_number)?;
+                    let nodes = tokens
+                        .map(|token| parse_field(Some(token), line_number))
+                        .collect::<Result<_, _>>()?;
+                    incumbent = Some((cost, nodes));
+                },
+                Some("level") => {
+                    levels.push((parse_field(tokens.next(), line_number)?, parse_field(tokens.next(), line_number)?));
+                    if let Some(spent) = tokens.next() {
+                        let spent = parse_field(Some(spent), line_number)?;
+                        let mut threshold = || Ok((
+                            parse_field(tokens.next(), line_number)?,
+                            parse_field(tokens.next(), line_number)?,
+                            parse_limit(tokens.next(), line_number)?
+                        ));
+                        let hiding = threshold()?;
+                        let covering = threshold()?;
+                        level_costs.push((spent, hiding, covering));
+                    }
+                },
+                Some("end") => complete = true,
+                Some(other) => return syntax_error(line_number, format!("unknown checkpoint field {}", other)),
+                None => {}
+            }
+        }
+
+        let incomplete = |message: &str| Err(CheckpointError::Incomplete(message.to_string()));
+        if !complete {
+            return incomplete("the checkpoint has no end")
+        }
+        let costs = match (current_cost, limit) {
+            (Some(current_cost), Some(limit)) => {
+                if level_costs.len() != levels.len() {
+                    return incomplete("every level of a min_cost_dlxc checkpoint needs its costs")
+                }
+                Some(SearchCosts { current_cost, limit, levels: level_costs, incumbent })
+            },
+            (None, None) => None,
+            _ => return incomplete("a checkpoint with costs needs both its cost and its limit")
+        };
+
+        match (fingerprint, state) {
+            (Some(fingerprint), Some(state)) => Ok(Checkpoint { fingerprint, state, levels, costs }),
+            (None, _) => incomplete("the checkpoint has no fingerprint"),
+            (_, None) => incomplete("the checkpoint has no state")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dlx::dlx_iter;
+    use super::Checkpoint;
+    use super::CheckpointError;
+
+    // Every way to split six items into parts of one or two items.
+    fn sets() -> Vec<Vec<usize>> {
+        let mut sets: Vec<Vec<usize>> = (0..6).map(|i| vec![i]).collect();
+        for i in 0..6 {
+            for j in i + 1..6 {
+                sets.push(vec![i, j]);
+            }
+        }
+        sets
+    }
+
+    #[test]
+    fn restored_search_finds_the_remaining_solutions() {
+        let all: Vec<_> = dlx_iter(sets(), (0..6).collect(), vec![]).collect();
+        for skip in 0..=all.len() {
+            let mut iter = dlx_iter(sets(), (0..6).collect(), vec![]);
+            for _ in 0..skip {
+                iter.next();
+            }
+            let checkpoint = Checkpoint::parse(&iter.checkpoint().to_string()).unwrap();
+            let restored = dlx_iter(sets(), (0..6).collect(), vec![]).restore(&checkpoint).unwrap();
+            assert_eq!(restored.collect::<Vec<_>>(), all[skip..]);
+        }
+    }
+
+    #[test]
+    fn rejects_cut_off_and_foreign_checkpoints() {
+        let mut iter = dlx_iter(sets(), (0..6).collect(), vec![]);
+        iter.next();
+        iter.next();
+        let text = iter.checkpoint().to_string();
+        for end in 0..text.trim_end().len() {
+            assert!(Checkpoint::<usize>::parse(&text[..end]).is_err(), "{:?}", &text[..end]);
+        }
+
+        let checkpoint = Checkpoint::parse(&text).unwrap();
+        let other = dlx_iter(sets()[1..].to_vec(), (0..6).collect(), vec![]);
+        assert!(matches!(other.restore(&checkpoint), Err(CheckpointError::OtherProblem { .. })));
+    }
+}
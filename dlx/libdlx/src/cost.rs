@@ -0,0 +1,88 @@
+// Costs of the options in min_cost_dlxc. The search only needs to add costs up
+// and compare them, so besides the integers this covers floating point weights
+// and tuples, which are compared lexicographically and added componentwise.
+// Costs must not be negative: the search relies on partial solutions only
+// getting more expensive as options are added. So the signed integers are not
+// costs, and the table rejects negative floats and tuples when it is built.
+pub trait Cost: Copy + PartialOrd + std::fmt::Debug {
+    fn zero() -> Self;
+    // at least the cost of every solution, used when there is no bound yet
+    fn max_value() -> Self;
+    // saturates at max_value
+    fn plus(self, other: Self) -> Self;
+}
+
+macro_rules! impl_integer_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                #[inline]
+                fn zero() -> Self {
+                    0
+                }
+
+                #[inline]
+                fn max_value() -> Self {
+                    <$t>::MAX
+                }
+
+                #[inline]
+                fn plus(self, other: Self) -> Self {
+                    self.saturating_add(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_cost!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_float_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                #[inline]
+                fn zero() -> Self {
+                    0.0
+                }
+
+                #[inline]
+                fn max_value() -> Self {
+                    <$t>::INFINITY
+                }
+
+                #[inline]
+                fn plus(self, other: Self) -> Self {
+                    self + other
+                }
+            }
+        )*
+    };
+}
+
+impl_float_cost!(f32, f64);
+
+macro_rules! impl_tuple_cost {
+    ($(($($t:ident $i:tt),*)),*) => {
+        $(
+            impl<$($t: Cost),*> Cost for ($($t,)*) {
+                #[inline]
+                fn zero() -> Self {
+                    ($($t::zero(),)*)
+                }
+
+                #[inline]
+                fn max_value() -> Self {
+                    ($($t::max_value(),)*)
+                }
+
+                #[inline]
+                fn plus(self, other: Self) -> Self {
+                    ($(self.$i.plus(other.$i),)*)
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple_cost!((A 0, B 1), (A 0, B 1, C 2), (A 0, B 1, C 2, D 3));
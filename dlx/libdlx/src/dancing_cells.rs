@@ -0,0 +1,623 @@
+use std::time::Instant;
+use std::time::Duration;
+use rand::seq::SliceRandom;
+use crate::random;
+use crate::dlxc::Item;
+use crate::dlxc::Solution;
+use crate::dlxc::DLXCIter;
+pub use crate::dlxc::State;
+
+// Changes to the sparse sets, undone in reverse order when backtracking.
+#[derive(PartialEq,Eq,Clone,Copy,Debug)]
+enum Change {
+    Size(usize, usize),
+    Active(usize)
+}
+
+// An XCC problem stored in sparse sets instead of linked lists,
+// after Knuth's dancing cells.
+// Every item has a set of the nodes of its options, of which the first
+// sizes[item] are active. Removing a node swaps it behind the active ones,
+// so undoing a removal only needs the old size.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct DCTable<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    names: Vec<Item<P, S, C>>,
+    color_names: Vec<Option<C>>,
+    primary_count: usize,
+    // the active items come first
+    items: Vec<usize>,
+    item_locations: Vec<usize>,
+    active: usize,
+    set_starts: Vec<usize>,
+    sizes: Vec<usize>,
+    sets: Vec<usize>,
+    node_items: Vec<usize>,
+    node_colors: Vec<usize>,
+    node_locations: Vec<usize>,
+    node_options: Vec<usize>,
+    option_starts: Vec<usize>,
+    trail: Vec<Change>
+}
+
+fn has_name<P, S, C>(item: Item<P, S, C>, name: Item<P, S, C>) -> bool
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    match (name, item) {
+        (Item::Primary(n), Item::Primary(i)) => i == n,
+        (Item::Secondary(n), Item::Secondary(i)) => i == n,
+        (Item::Secondary(n), Item::ColoredSecondary(i, _)) => i == n,
+        _ => false
+    }
+}
+
+impl<P, S, C> DCTable<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub fn new(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+        let primary_count = primary_items.len();
+        let mut names = Vec::with_capacity(primary_items.len() + secondary_items.len());
+        for item in primary_items {
+            names.push(Item::Primary(item));
+        }
+
+        for item in secondary_items {
+            names.push(Item::Secondary(item));
+        }
+
+        let mut color_names = Vec::with_capacity(colors.len() + 1);
+        color_names.push(None);
+        for color in colors {
+            color_names.push(Some(color));
+        }
+
+        let item_count = names.len();
+        let mut node_items = Vec::new();
+        let mut node_colors = Vec::new();
+        let mut node_options = Vec::new();
+        let mut option_starts = vec![0];
+        let mut sizes = vec![0; item_count];
+        for set in sets.into_iter().filter(|set| !set.is_empty()) {
+            // colored primary items and options with several colors for an item need the dancing links tables
+            let several_colors = set
+                .iter()
+                .enumerate()
+                .any(|(i, &item)| matches!(item, Item::ColoredSecondary(s, c) if set[..i]
+                    .iter()
+                    .any(|&other| matches!(other, Item::ColoredSecondary(t, d) if t == s && d != c))));
+            if several_colors || set.iter().any(|item| matches!(item, Item::ColoredPrimary(_, _) | Item::PrimaryColor(_, _))) {
+                panic!("{:?} is only supported by the dancing links solvers", set);
+            }
+            for item in set {
+                let index = names
+                    .iter()
+                    .position(|&name| has_name(item, name))
+                    .unwrap_or_else(|| panic!("{:?} not present", item));
+                let color = if let Item::ColoredSecondary(_, c) = item {
+                    color_names
+                        .iter()
+                        .position(|&color| color == Some(c))
+                        .unwrap_or_else(|| panic!("color {:?} not present", c))
+                }
+                else {
+                    0
+                };
+
+                sizes[index] += 1;
+                node_items.push(index);
+                node_colors.push(color);
+                node_options.push(option_starts.len() - 1);
+            }
+            option_starts.push(node_items.len());
+        }
+
+        let mut set_starts = Vec::with_capacity(item_count + 1);
+        let mut start = 0;
+        for &size in &sizes {
+            set_starts.push(start);
+            start += size;
+        }
+        set_starts.push(start);
+
+        let mut filled = vec![0; item_count];
+        let mut node_sets = vec![0; node_items.len()];
+        let mut node_locations = vec![0; node_items.len()];
+        for (node, &item) in node_items.iter().enumerate() {
+            node_sets[set_starts[item] + filled[item]] = node;
+            node_locations[node] = filled[item];
+            filled[item] += 1;
+        }
+
+        DCTable {
+            names,
+            color_names,
+            primary_count,
+            items: (0..item_count).collect(),
+            item_locations: (0..item_count).collect(),
+            active: item_count,
+            set_starts,
+            sizes,
+            sets: node_sets,
+            node_items,
+            node_colors,
+            node_locations,
+            node_options,
+            option_starts,
+            trail: Vec::new()
+        }
+    }
+
+    fn is_active(&self, item: usize) -> bool {
+        self.item_locations[item] < self.active
+    }
+
+    fn node_at(&self, item: usize, location: usize) -> usize {
+        self.sets[self.set_starts[item] + location]
+    }
+
+    fn deactivate(&mut self, item: usize) {
+        let location = self.item_locations[item];
+        let last = self.items[self.active - 1];
+        self.items.swap(location, self.active - 1);
+        self.item_locations[last] = location;
+        self.item_locations[item] = self.active - 1;
+        self.trail.push(Change::Active(self.active));
+        self.active -= 1;
+    }
+
+    // removes the option from the sets of all active items
+    fn hide(&mut self, option: usize) {
+        for node in self.option_starts[option]..self.option_starts[option + 1] {
+            let item = self.node_items[node];
+            if self.is_active(item) {
+                let size = self.sizes[item];
+                let location = self.node_locations[node];
+                debug_assert!(location < size, "hiding an inactive node of {:?}", self.names[item]);
+                let last = self.node_at(item, size - 1);
+                let start = self.set_starts[item];
+                self.sets.swap(start + location, start + size - 1);
+                self.node_locations[last] = location;
+                self.node_locations[node] = size - 1;
+                self.trail.push(Change::Size(item, size));
+                self.sizes[item] = size - 1;
+            }
+        }
+    }
+
+    // hides the options of the item that do not have the given color,
+    // color 0 hides all of them
+    fn commit(&mut self, item: usize, color: usize, chosen_option: usize) {
+        self.deactivate(item);
+        for location in 0..self.sizes[item] {
+            let node = self.node_at(item, location);
+            let option = self.node_options[node];
+            if option != chosen_option && (color == 0 || self.node_colors[node] != color) {
+                self.hide(option);
+            }
+        }
+    }
+
+    fn cover(&mut self, item: usize) {
+        self.commit(item, 0, usize::MAX);
+    }
+
+    fn cover_option(&mut self, chosen_node: usize) {
+        let option = self.node_options[chosen_node];
+        for node in self.option_starts[option]..self.option_starts[option + 1] {
+            let item = self.node_items[node];
+            if node != chosen_node && self.is_active(item) {
+                self.commit(item, self.node_colors[node], option);
+            }
+        }
+    }
+
+    fn undo(&mut self, trail_length: usize) {
+        while self.trail.len() > trail_length {
+            match self.trail.pop().unwrap() {
+                Change::Size(item, size) => self.sizes[item] = size,
+                Change::Active(active) => self.active = active
+            }
+        }
+    }
+
+    fn get_item(&self, node: usize, colors: &[usize]) -> Item<P, S, C> {
+        let item = self.node_items[node];
+        match self.names[item] {
+            Item::Secondary(name) => {
+                if let Some(color) = self.color_names[colors[item]] {
+                    Item::ColoredSecondary(name, color)
+                }
+                else {
+                    Item::Secondary(name)
+                }
+            },
+            name => name
+        }
+    }
+
+    // the chosen options give the colors of the secondary items
+    fn get_colors(&self, chosen_nodes: &[usize]) -> Vec<usize> {
+        let mut colors = vec![0; self.names.len()];
+        for &chosen_node in chosen_nodes {
+            let option = self.node_options[chosen_node];
+            for node in self.option_starts[option]..self.option_starts[option + 1] {
+                if self.node_colors[node] != 0 {
+                    colors[self.node_items[node]] = self.node_colors[node];
+                }
+            }
+        }
+        colors
+    }
+
+    // the row starts at the chosen node, like the rows of dlxc
+    fn get_row(&self, chosen_node: usize, colors: &[usize]) -> Vec<Item<P, S, C>> {
+        let option = self.node_options[chosen_node];
+        let start = self.option_starts[option];
+        let end = self.option_starts[option + 1];
+        (chosen_node..end)
+            .chain(start..chosen_node)
+            .map(|node| self.get_item(node, colors))
+            .collect()
+    }
+
+    fn get_solution(&self, chosen_nodes: &[usize]) -> Solution<P, S, C> {
+        let colors = self.get_colors(chosen_nodes);
+        let rows = chosen_nodes
+            .iter()
+            .map(|&node| self.get_row(node, &colors))
+            .collect();
+        let assignments = self.names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| match name {
+                Item::Secondary(item) => Some((*item, self.color_names[colors[i]])),
+                _ => None
+            })
+            .collect();
+        (rows, assignments)
+    }
+}
+
+fn min_length_item<P, S, C>(table: &DCTable<P, S, C>) -> Option<usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    table.items[..table.active]
+        .iter()
+        .filter(|&&item| item < table.primary_count)
+        .min_by_key(|&&item| (table.sizes[item], item))
+        .cloned()
+}
+
+fn min_length_item_randomized<P, S, C>(table: &DCTable<P, S, C>) -> Option<usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    let mut size = usize::MAX;
+    let mut items = Vec::new();
+    for &item in table.items[..table.active].iter().filter(|&&item| item < table.primary_count) {
+        if table.sizes[item] < size {
+            items = Vec::from([item]);
+            size = table.sizes[item];
+        }
+        else if table.sizes[item] == size {
+            items.push(item);
+        }
+    }
+    random::with_rng(|rng| items.choose(rng).cloned())
+}
+
+#[derive(PartialEq,Eq,Clone,Copy,Debug)]
+struct LevelState {
+    item: usize,
+    location: usize,
+    // trail lengths before covering the item and before covering the current option
+    item_trail: usize,
+    option_trail: usize
+}
+
+pub struct DCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    table: DCTable<P, S, C>,
+    stack: Vec<LevelState>,
+    state: State,
+    choose_item: fn(&DCTable<P, S, C>) -> Option<usize>
+}
+
+impl<P, S, C> DCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub fn new(sets: Vec<Vec<Item<P, S, C>>>, choose_item: fn(&DCTable<P, S, C>) -> Option<usize>,
+               primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+        let table = DCTable::new(sets, primary_items, secondary_items, colors);
+        let stack = Vec::new();
+        let state = State::CoveringColumn;
+        let mut this = DCIter { table, stack, state, choose_item };
+        this.cover_column();
+        this
+    }
+
+    fn cover_column(&mut self) {
+        if let Some(item) = (self.choose_item)(&self.table) {
+            let item_trail = self.table.trail.len();
+            self.table.cover(item);
+            self.stack.push(LevelState {
+                item,
+                location: 0,
+                item_trail,
+                option_trail: self.table.trail.len()
+            });
+
+            if self.table.sizes[item] == 0 {
+                // the item has no options left
+                // set up to return to the previous level
+                self.state = State::BacktrackingColumn;
+            }
+            else {
+                // cover the first option
+                self.state = State::CoveringRow;
+            }
+        }
+        else {
+            // all primary items are covered
+            self.state = State::FoundSolution;
+        }
+    }
+
+    fn cover_row(&mut self) {
+        // cover the current option and set up for the next level
+        let level = self.stack.last().unwrap();
+        let node = self.table.node_at(level.item, level.location);
+        self.table.cover_option(node);
+        self.state = State::CoveringColumn;
+    }
+
+    fn backtrack_column(&mut self) {
+        // uncover the last covered item
+        // and set up to continue
+        let level = self.stack.pop().unwrap();
+        self.table.undo(level.item_trail);
+        self.state = State::BacktrackingRow;
+    }
+
+    fn backtrack_row(&mut self) {
+        let level = self.stack.last_mut().unwrap();
+        self.table.undo(level.option_trail);
+        level.location += 1;
+        if level.location == self.table.sizes[level.item] {
+            // we tried the last option
+            // set up to return to the previous level
+            self.state = State::BacktrackingColumn;
+        }
+        else {
+            // cover the next option
+            self.state = State::CoveringRow;
+        }
+    }
+
+    fn step(&mut self) {
+        match self.state {
+            State::FoundSolution => {
+                self.state = State::BacktrackingRow;
+            },
+            State::CoveringColumn => {
+                self.cover_column();
+            },
+            State::CoveringRow => {
+                self.cover_row();
+            },
+            State::BacktrackingRow => {
+                self.backtrack_row();
+            }
+            State::BacktrackingColumn => {
+                self.backtrack_column();
+            },
+        }
+    }
+
+    // True when the whole search tree has been explored,
+    // as opposed to a search that was stopped by its time limit.
+    pub fn is_exhausted(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn get_solution(&self) -> Option<Solution<P, S, C>> {
+        if let State::FoundSolution = self.state {
+            let chosen_nodes: Vec<usize> = self.stack
+                .iter()
+                .map(|level| self.table.node_at(level.item, level.location))
+                .collect();
+            Some(self.table.get_solution(&chosen_nodes))
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn first_solution(&mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
+        let start = Instant::now();
+        while !self.stack.is_empty() {
+            if start.elapsed() >= time_limit {
+                return None
+            }
+            if self.state == State::FoundSolution {
+                return self.get_solution()
+            }
+            self.step();
+        }
+        None
+    }
+
+    pub fn all_solutions(&mut self, time_limit: Duration) -> Vec<Solution<P, S, C>> {
+        let start = Instant::now();
+        let mut solutions = Vec::new();
+        while !self.stack.is_empty() {
+            if start.elapsed() >= time_limit {
+                break;
+            }
+            if let Some(solution) = self.get_solution() {
+                solutions.push(solution);
+            }
+            self.step();
+        }
+        solutions
+    }
+
+    pub fn count_solutions(&mut self, time_limit: Duration) -> usize {
+        let start = Instant::now();
+        let mut count = 0;
+        while !self.stack.is_empty() {
+            if start.elapsed() >= time_limit {
+                break;
+            }
+            if self.state == State::FoundSolution {
+                count += 1;
+            }
+            self.step();
+        }
+        count
+    }
+}
+
+impl<P, S, C> Iterator for DCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    type Item = (State, Option<Solution<P, S, C>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.stack.is_empty() {
+            self.step();
+            Some((self.state, self.get_solution()))
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[derive(PartialEq,Eq,Clone,Copy,Debug)]
+pub enum Backend {
+    DancingLinks,
+    DancingCells
+}
+
+// An XCC search on either backend, with the API of dlxc::DLXCIter.
+pub enum XCCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    Links(Box<DLXCIter<P, S, C>>),
+    Cells(Box<DCIter<P, S, C>>)
+}
+
+impl<P, S, C> XCCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub fn is_exhausted(&self) -> bool {
+        match self {
+            XCCIter::Links(iter) => iter.is_exhausted(),
+            XCCIter::Cells(iter) => iter.is_exhausted()
+        }
+    }
+
+    pub fn first_solution(&mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
+        match self {
+            XCCIter::Links(iter) => iter.first_solution(time_limit),
+            XCCIter::Cells(iter) => iter.first_solution(time_limit)
+        }
+    }
+
+    pub fn all_solutions(&mut self, time_limit: Duration) -> Vec<Solution<P, S, C>> {
+        match self {
+            XCCIter::Links(iter) => iter.all_solutions(time_limit),
+            XCCIter::Cells(iter) => iter.all_solutions(time_limit)
+        }
+    }
+
+    pub fn count_solutions(&mut self, time_limit: Duration) -> usize {
+        match self {
+            XCCIter::Links(iter) => iter.count_solutions(time_limit),
+            XCCIter::Cells(iter) => iter.count_solutions(time_limit)
+        }
+    }
+}
+
+impl<P, S, C> Iterator for XCCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    type Item = (State, Option<Solution<P, S, C>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            XCCIter::Links(iter) => iter.next(),
+            XCCIter::Cells(iter) => iter.next()
+        }
+    }
+}
+
+pub fn dancing_cells_iter<P, S, C>(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> DCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    DCIter::new(sets, min_length_item, primary_items, secondary_items, colors)
+}
+
+pub fn dancing_cells_iter_randomized<P, S, C>(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> DCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    DCIter::new(sets, min_length_item_randomized, primary_items, secondary_items, colors)
+}
+
+pub fn xcc_iter<P, S, C>(backend: Backend, sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>,
+                         secondary_items: Vec<S>, colors: Vec<C>) -> XCCIter<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    match backend {
+        Backend::DancingLinks => XCCIter::Links(Box::new(crate::dlxc::dlxc_iter(sets, primary_items, secondary_items, colors))),
+        Backend::DancingCells => XCCIter::Cells(Box::new(dancing_cells_iter(sets, primary_items, secondary_items, colors)))
+    }
+}
+
+pub fn xcc_first<P, S, C>(backend: Backend, sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>,
+                          colors: Vec<C>, time_limit: Duration) -> Option<Solution<P, S, C>>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    xcc_iter(backend, sets, primary_items, secondary_items, colors).first_solution(time_limit)
+}
+
+pub fn xcc_count<P, S, C>(backend: Backend, sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>,
+                          colors: Vec<C>, time_limit: Duration) -> usize
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    xcc_iter(backend, sets, primary_items, secondary_items, colors).count_solutions(time_limit)
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+use crate::dlxc::dlxc_iter;
+use crate::dlxc::Item;
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum Feasibility {
+    Feasible,
+    Infeasible,
+    // the run was stopped by its time limit
+    Unknown
+}
+
+// A subset of the primary items that cannot be covered exactly once
+// even when all other primary items are dropped from the problem.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct InfeasibleCore<P>
+where
+P: Eq + Copy + std::fmt::Debug {
+    pub items: Vec<P>,
+    // indices of the options that touch the core items
+    pub options: Vec<usize>,
+    // false if some run timed out, so an item might have been kept needlessly
+    pub minimal: bool,
+    pub runs: usize
+}
+
+pub fn check_feasibility<P, S, C>(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>,
+                                  colors: Vec<C>, time_limit: Duration) -> Feasibility
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    if primary_items.is_empty() {
+        // the empty set of options is a solution
+        return Feasibility::Feasible
+    }
+
+    let mut iter = dlxc_iter(sets, primary_items, secondary_items, colors);
+    if iter.first_solution(time_limit).is_some() {
+        Feasibility::Feasible
+    }
+    else if iter.is_exhausted() {
+        Feasibility::Infeasible
+    }
+    else {
+        Feasibility::Unknown
+    }
+}
+
+fn touches<P, S, C>(set: &[Item<P, S, C>], items: &[P]) -> bool
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    set.iter().any(|item| match item {
+        Item::Primary(p) | Item::ColoredPrimary(p, _) => items.contains(p),
+        _ => false
+    })
+}
+
+// Keeps only the options that touch the given primary items
+// and drops all other primary items from them.
+fn restrict<P, S, C>(sets: &[Vec<Item<P, S, C>>], items: &[P]) -> Vec<Vec<Item<P, S, C>>>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    sets.iter()
+        .filter(|set| touches(set, items))
+        .map(|set| set
+            .iter()
+            .filter(|item| match item {
+                Item::Primary(p) | Item::ColoredPrimary(p, _) | Item::PrimaryColor(p, _) => items.contains(p),
+                _ => true
+            })
+            .cloned()
+            .collect())
+        .collect()
+}
+
+// Finds a small infeasible subset of the primary items by deletion:
+// every item is dropped in turn and stays dropped if the rest is still infeasible.
+// Each run of the solver gets at most run_time_limit.
+// Returns None if the problem has a solution or if that could not be decided.
+pub fn infeasible_core<P, S, C>(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>,
+                                colors: Vec<C>, run_time_limit: Duration) -> Option<InfeasibleCore<P>>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    let options_touching = |items: &[P]| -> Vec<usize> {
+        sets.iter()
+            .enumerate()
+            .filter(|(_, set)| touches(set, items))
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    // an item without options is a core on its own
+    if let Some(&item) = primary_items.iter().find(|&&p| !touches_any(&sets, p)) {
+        return Some(InfeasibleCore {
+            items: vec![item],
+            options: Vec::new(),
+            minimal: true,
+            runs: 0
+        })
+    }
+
+    let is_feasible = |items: &[P]| check_feasibility(
+        restrict(&sets, items),
+        items.to_vec(),
+        secondary_items.clone(),
+        colors.clone(),
+        run_time_limit
+    );
+
+    let mut runs = 1;
+    if is_feasible(&primary_items) != Feasibility::Infeasible {
+        return None
+    }
+
+    let mut core = primary_items;
+    let mut minimal = true;
+    let mut i = 0;
+    while i < core.len() {
+        let mut candidate = core.clone();
+        candidate.remove(i);
+
+        runs += 1;
+        match is_feasible(&candidate) {
+            Feasibility::Infeasible => {
+                core = candidate;
+            },
+            Feasibility::Feasible => {
+                i += 1;
+            },
+            Feasibility::Unknown => {
+                minimal = false;
+                i += 1;
+            }
+        }
+    }
+
+    Some(InfeasibleCore {
+        options: options_touching(&core),
+        items: core,
+        minimal,
+        runs
+    })
+}
+
+fn touches_any<P, S, C>(sets: &[Vec<Item<P, S, C>>], item: P) -> bool
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    sets.iter().any(|set| set
+        .iter()
+        .any(|&other| matches!(other, Item::Primary(p) | Item::ColoredPrimary(p, _) if p == item)))
+}
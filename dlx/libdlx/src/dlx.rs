@@ -2,19 +2,44 @@ use std::time::Instant;
 use std::time::Duration;
 use std::mem::take;
 use rand::seq::SliceRandom;
+use crate::random;
+use crate::index::Index;
+use crate::index::Header;
+use crate::index::check_capacity;
+use crate::checkpoint::Checkpoint;
+use crate::checkpoint::CheckpointError;
+use crate::checkpoint::check_levels;
+use crate::checkpoint::Fingerprint;
+use crate::checkpoint::check_fingerprint;
+use crate::checkpoint::last_row_covered;
+use crate::dlxc::State as CheckpointState;
+use crate::symmetry::Symmetry;
+use crate::symmetry::Choice;
+use crate::trace::Trace;
+use crate::inspect;
+use crate::inspect::Links;
 
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Default)]
+struct Node<I: Index> {
+    up: I,
+    down: I,
+    header: I
+}
+
+// The first nodes double as the item headers, the rest are the options,
+// each followed by a spacer node.
+// A table can be edited while no search runs on it: items and options are added
+// in place and options can be retired. Options are numbered in the order they were added.
 #[derive(Clone,PartialEq,Eq,Debug)]
-pub struct DLXTable<T: Eq + Copy + std::fmt::Debug> {
+pub struct DLXTable<T: Eq + Copy + std::fmt::Debug, I: Index = u32> {
     names: Vec<Option<T>>,
-    left_links: Vec<usize>,
-    right_links: Vec<usize>,
-    lengths: Vec<usize>,
-    up_links: Vec<usize>,
-    down_links: Vec<usize>,
-    header_links: Vec<usize>
+    headers: Vec<Header<I>>,
+    nodes: Vec<Node<I>>,
+    // the first node of every option, None for empty and retired ones
+    options: Vec<Option<usize>>
 }
 
-impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> DLXTable<T, I> {
     pub fn new(sets: Vec<Vec<T>>, primary_items: Vec<T>, secondary_items: Vec<T>) -> Self {
         let primary_count = primary_items.len();
         let mut names = Vec::with_capacity(1 + primary_items.len() + secondary_items.len());
@@ -37,112 +62,346 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
                 .sum::<usize>())
             .sum::<usize>();
 
+        check_capacity::<I>(node_count);
         let mut table = DLXTable {
             names,
-            left_links: vec![0; names_count],
-            right_links: vec![0; names_count],
-            lengths: vec![0; names_count],
-            up_links: vec![0; node_count],
-            down_links: vec![0; node_count],
-            header_links: vec![0; node_count]
+            headers: vec![Header::default(); names_count],
+            nodes: Vec::with_capacity(node_count),
+            options: Vec::with_capacity(sets.len())
         };
+        // the headers and the first spacer
+        table.nodes.resize(names_count + 1, Node::default());
 
         // header setup
-        table.left_links[0] = primary_count;
+        table.set_left(0, primary_count);
         for i in 0..primary_count {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
+            table.set_left(i+1, i);
+            table.set_right(i, i+1);
+            table.set_up(i+1, i+1);
+            table.set_down(i+1, i+1);
         }
 
-        table.left_links[primary_count + 1] = names_count - 1;
+        // the secondary items form their own circular list, if there are any
+        if names_count > primary_count + 1 {
+            table.set_left(primary_count + 1, names_count - 1);
+
+            table.set_up(primary_count + 1, primary_count + 1);
+            table.set_down(primary_count + 1, primary_count + 1);
+            for i in primary_count+1..names_count-1 {
+                table.set_left(i+1, i);
+                table.set_right(i, i+1);
+                table.set_up(i+1, i+1);
+                table.set_down(i+1, i+1);
+            }
 
-        table.up_links[primary_count + 1] = primary_count + 1;
-        table.down_links[primary_count + 1] = primary_count + 1;
-        for i in primary_count+1..names_count-1 {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
+            table.set_right(names_count - 1, primary_count + 1);
         }
 
-        table.right_links[names_count - 1] = primary_count + 1;
+        table.add_options(sets);
+        table
+    }
 
-        let mut prev_spacer = names_count;
-        
-        let mut current_index = names_count + 1;
+    // Appends the options after the ones the table already has.
+    pub fn add_options(&mut self, sets: Vec<Vec<T>>) {
+        let node_count = self.nodes.len() + sets.len() + sets
+            .iter()
+            .map(|set| set.len())
+            .sum::<usize>();
+        check_capacity::<I>(node_count);
+        self.nodes.reserve(node_count - self.nodes.len());
+        self.options.reserve(sets.len());
         for set in sets {
-            if set.len() > 0 {
-                for item in set {
-                    let header_index = table.names
-                        .iter()
-                        .position(|name_opt| name_opt.is_some() && item == name_opt.unwrap())
-                        .unwrap();
-                    table.lengths[header_index] += 1;
-                    
-                    // node setup
-                    table.up_links[current_index] = table.up_links[header_index];
-                    table.down_links[current_index] = header_index;
-                    table.header_links[current_index] = header_index;
-
-                    // uplink setup
-                    table.down_links[table.up_links[current_index]] = current_index;
-                    
-                    // header setup
-                    if table.down_links[header_index] == header_index {
-                        table.down_links[header_index] = current_index;
-                    }
-                    table.up_links[header_index] = current_index;
-                    
-                    current_index += 1;
+            self.add_option(set);
+        }
+    }
+
+    fn add_option(&mut self, set: Vec<T>) {
+        if set.is_empty() {
+            self.options.push(None);
+            return
+        }
+
+        let prev_spacer = self.nodes.len() - 1;
+        let first_index = self.nodes.len();
+        for item in set {
+            let header_index = self.column(item);
+            let current_index = self.nodes.len();
+            self.nodes.push(Node::default());
+            self.inc_length(header_index);
+            
+            // node setup
+            self.set_up(current_index, self.up(header_index));
+            self.set_down(current_index, header_index);
+            self.set_header(current_index, header_index);
+
+            // uplink setup
+            self.set_down(self.up(current_index), current_index);
+            
+            // header setup
+            if self.down(header_index) == header_index {
+                self.set_down(header_index, current_index);
+            }
+            self.set_up(header_index, current_index);
+        }
+
+        // spacer
+        let spacer = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.set_up(spacer, first_index);
+        self.set_down(prev_spacer, spacer - 1);
+        self.options.push(Some(first_index));
+    }
+
+    fn column(&self, item: T) -> usize {
+        self.names
+            .iter()
+            .position(|name_opt| name_opt.is_some() && item == name_opt.unwrap())
+            .unwrap_or_else(|| panic!("{:?} is not an item of the table.", item))
+    }
+
+    // Adding items moves every option node to make room for the new headers, so a call takes
+    // time linear in the size of the table. Items added together share that cost, see add_items.
+    pub fn add_primary_items(&mut self, items: Vec<T>) {
+        self.add_items(items, Vec::new());
+    }
+
+    pub fn add_secondary_items(&mut self, items: Vec<T>) {
+        self.add_items(Vec::new(), items);
+    }
+
+    // Adds primary and secondary items with a single move of the option nodes.
+    pub fn add_items(&mut self, primary_items: Vec<T>, secondary_items: Vec<T>) {
+        let mut list = self.secondary_column();
+        let items: Vec<T> = primary_items.iter().chain(secondary_items.iter()).copied().collect();
+        let first_column = self.insert_headers(&items);
+        let first_secondary = first_column + primary_items.len();
+        for column in first_column..first_secondary {
+            // insert before the root, at the end of the list
+            self.set_left(column, self.left(0));
+            self.set_right(column, 0);
+            self.set_right(self.left(0), column);
+            self.set_left(0, column);
+        }
+        for column in first_secondary..first_secondary + secondary_items.len() {
+            match list {
+                Some(other) => {
+                    self.set_left(column, self.left(other));
+                    self.set_right(column, other);
+                    self.set_right(self.left(other), column);
+                    self.set_left(other, column);
+                },
+                None => {
+                    self.set_left(column, column);
+                    self.set_right(column, column);
+                    list = Some(column);
                 }
-    
-                // spacer
-                table.up_links[current_index] = prev_spacer + 1;
-                table.down_links[prev_spacer] = current_index - 1;
-                prev_spacer = current_index;
-                current_index += 1;
             }
         }
+    }
 
-        table
+    // Any column of the secondary list, found as the columns that are not primary.
+    fn secondary_column(&self) -> Option<usize> {
+        let mut primary = vec![false; self.names.len()];
+        let mut i = self.right(0);
+        while i != 0 {
+            primary[i] = true;
+            i = self.right(i);
+        }
+        (1..self.names.len()).find(|&column| !primary[column])
+    }
+
+    // The new header nodes go right after the existing ones,
+    // so the nodes of the options move up by one for each.
+    // This touches every node once however many items there are.
+    // Returns the column of the first new item.
+    fn insert_headers(&mut self, items: &[T]) -> usize {
+        for &item in items {
+            if self.names.contains(&Some(item)) || items.iter().filter(|&&other| other == item).count() > 1 {
+                panic!("{:?} is already an item of the table.", item);
+            }
+        }
+
+        let first_column = self.names.len();
+        let count = items.len();
+        check_capacity::<I>(self.nodes.len() + count);
+        for node in 0..self.nodes.len() {
+            if self.up(node) >= first_column {
+                self.set_up(node, self.up(node) + count);
+            }
+            if self.down(node) >= first_column {
+                self.set_down(node, self.down(node) + count);
+            }
+        }
+        self.nodes.splice(first_column..first_column, vec![Node::default(); count]);
+        for first_node in self.options.iter_mut().flatten() {
+            *first_node += count;
+        }
+
+        for (column, &item) in (first_column..).zip(items) {
+            self.names.push(Some(item));
+            self.headers.push(Header::default());
+            self.set_up(column, column);
+            self.set_down(column, column);
+        }
+        first_column
+    }
+
+    // Removes an option for good. Its nodes stay in the table, but no column links to them anymore.
+    pub fn retire_option(&mut self, option: usize) {
+        let first_node = self.options
+            .get_mut(option)
+            .and_then(|first_node| first_node.take())
+            .unwrap_or_else(|| panic!("Option {} does not exist, is empty or was already retired.", option));
+
+        let mut i = first_node;
+        while self.header(i) != 0 {
+            let header = self.header(i);
+            self.set_up(self.down(i), self.up(i));
+            self.set_down(self.up(i), self.down(i));
+            self.dec_length(header);
+            i += 1;
+        }
+    }
+
+    // The number of options, including the empty and the retired ones.
+    pub fn option_count(&self) -> usize {
+        self.options.len()
+    }
+
+    // Only meaningful while no columns are covered.
+    fn is_retired(&self, row_node: usize) -> bool {
+        self.down(self.up(row_node)) != row_node
+    }
+
+    // The option of every node, usize::MAX for the headers and spacers.
+    fn node_options(&self) -> Vec<usize> {
+        let mut node_options = vec![usize::MAX; self.nodes.len()];
+        for (option, &first_node) in self.options.iter().enumerate() {
+            if let Some(first_node) = first_node {
+                let mut k = first_node;
+                while self.header(k) != 0 {
+                    node_options[k] = option;
+                    k += 1;
+                }
+            }
+        }
+        node_options
+    }
+
+    // Whether an option that is not chosen can still be chosen, while the rows of all levels are covered.
+    // The other nodes of a row are hidden when one of its columns is covered,
+    // rows with a single node are excluded when their column is covered.
+    fn is_open(&self, option: usize) -> bool {
+        match self.options[option] {
+            Some(first_node) => {
+                let mut k = first_node;
+                while self.header(k) != 0 {
+                    if self.down(self.up(k)) != k {
+                        return false
+                    }
+                    k += 1;
+                }
+                let column = self.header(first_node);
+                k > first_node + 1 || self.right(self.left(column)) == column
+            },
+            None => false
+        }
+    }
+
+    fn left(&self, header: usize) -> usize {
+        self.headers[header].left.to_usize()
+    }
+
+    fn set_left(&mut self, header: usize, value: usize) {
+        self.headers[header].left = I::from_usize(value);
+    }
+
+    fn right(&self, header: usize) -> usize {
+        self.headers[header].right.to_usize()
+    }
+
+    fn set_right(&mut self, header: usize, value: usize) {
+        self.headers[header].right = I::from_usize(value);
+    }
+
+    fn length(&self, header: usize) -> usize {
+        self.headers[header].length.to_usize()
+    }
+
+    fn inc_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) + 1);
+    }
+
+    fn dec_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) - 1);
+    }
+
+    fn up(&self, node: usize) -> usize {
+        self.nodes[node].up.to_usize()
+    }
+
+    fn set_up(&mut self, node: usize, value: usize) {
+        self.nodes[node].up = I::from_usize(value);
+    }
+
+    fn down(&self, node: usize) -> usize {
+        self.nodes[node].down.to_usize()
+    }
+
+    fn set_down(&mut self, node: usize, value: usize) {
+        self.nodes[node].down = I::from_usize(value);
+    }
+
+    fn header(&self, node: usize) -> usize {
+        self.nodes[node].header.to_usize()
+    }
+
+    fn set_header(&mut self, node: usize, value: usize) {
+        self.nodes[node].header = I::from_usize(value);
     }
 
     fn cover(&mut self, column: usize) {
-        self.left_links[self.right_links[column]] = self.left_links[column];
-        self.right_links[self.left_links[column]] = self.right_links[column];
+        self.set_left(self.right(column), self.left(column));
+        self.set_right(self.left(column), self.right(column));
 
-        let mut i = self.down_links[column];
+        let mut i = self.down(column);
         while i != column {
             self.hide(i);
-            i = self.down_links[i];
+            i = self.down(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
     fn uncover(&mut self, column: usize) {
-        let mut i = self.up_links[column];
+        let mut i = self.up(column);
         while i != column {
             self.unhide(i);
-            i = self.up_links[i];
+            i = self.up(i);
         }
 
-        self.left_links[self.right_links[column]] = column;
-        self.right_links[self.left_links[column]] = column;
+        self.set_left(self.right(column), column);
+        self.set_right(self.left(column), column);
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
+        }
     }
     
     fn hide(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             // spacer
             if header == 0 {
-                i = self.up_links[i];
+                i = self.up(i);
             }
             else {
-                self.up_links[self.down_links[i]] = self.up_links[i];
-                self.down_links[self.up_links[i]] = self.down_links[i];
-                self.lengths[header] -= 1;
+                self.set_up(self.down(i), self.up(i));
+                self.set_down(self.up(i), self.down(i));
+                self.dec_length(header);
 
                 i += 1;
             }
@@ -152,15 +411,15 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
     fn unhide(&mut self, row_node: usize) {
         let mut i = row_node - 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             // spacer
             if header == 0 {
-                i = self.down_links[i];
+                i = self.down(i);
             }
             else {
-                self.lengths[header] += 1;
-                self.up_links[self.down_links[i]] = i;
-                self.down_links[self.up_links[i]] = i;
+                self.inc_length(header);
+                self.set_up(self.down(i), i);
+                self.set_down(self.up(i), i);
 
                 i -= 1;
             }
@@ -170,9 +429,9 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
     fn cover_row(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.up_links[i];
+                i = self.up(i);
             }
             else {
                 self.cover(header);
@@ -184,9 +443,9 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
     fn uncover_row(&mut self, row_node: usize) {
         let mut i = row_node - 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.down_links[i];
+                i = self.down(i);
             }
             else {
                 self.uncover(header);
@@ -195,16 +454,65 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
         }
     }
 
+    // Only identifies the problem while no columns are covered.
+    fn fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add_debug(&self.names);
+        for header in &self.headers {
+            fingerprint.add(header.left.to_usize());
+            fingerprint.add(header.right.to_usize());
+            fingerprint.add(header.length.to_usize());
+        }
+        for node in &self.nodes {
+            fingerprint.add(node.up.to_usize());
+            fingerprint.add(node.down.to_usize());
+            fingerprint.add(node.header.to_usize());
+        }
+        fingerprint.finish()
+    }
+
+    // Whether row_node can be the current row of the column at a level of the search:
+    // a node of the column, or the column itself once its rows are exhausted.
+    fn is_level(&self, column: usize, row_node: usize) -> bool {
+        column > 0 && column < self.headers.len()
+            && (row_node == column || (row_node >= self.headers.len() && row_node < self.nodes.len() && self.header(row_node) == column))
+    }
+
+    // The items that are not covered with the number of options they have left,
+    // and the options that can still be chosen, see inspect.rs.
+    pub fn dump(&self) -> String {
+        let primary = inspect::active_primary(self);
+        let secondary = inspect::active_secondary(self);
+        let options = inspect::active_options(self, &[&primary[..], &secondary[..]].concat())
+            .into_iter()
+            .map(|row_node| self.get_row(row_node)
+                .iter()
+                .map(|item| format!("{:?}", item))
+                .collect::<Vec<_>>()
+                .join(" "))
+            .collect();
+        let lengths = |columns: Vec<usize>| columns
+            .into_iter()
+            .map(|column| (Links::label(self, column), self.length(column)))
+            .collect();
+        inspect::render(lengths(primary), lengths(secondary), options)
+    }
+
+    // Panics if a link does not point back or a length is off.
+    pub fn check_links(&self) {
+        inspect::check_links(self);
+    }
+
     fn get_row(&self, row_node: usize) -> Vec<T> {
-        let mut row = vec![self.names[self.header_links[row_node]].unwrap()];
+        let mut row = vec![self.names[self.header(row_node)].unwrap()];
         let mut k = row_node + 1;
         while k != row_node {
-            let header = self.header_links[k];
+            let header = self.header(k);
             if header == 0 {
-                k = self.up_links[k];
+                k = self.up(k);
             }
             else {
-                let item = self.names[self.header_links[k]].unwrap(); 
+                let item = self.names[self.header(k)].unwrap(); 
                 row.push(item);
                 k += 1;
             }
@@ -214,50 +522,91 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXTable<T> {
     }
 }
 
-fn min_length_column<T>(table: &DLXTable<T>) -> Option<usize> 
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> Links for DLXTable<T, I> {
+    fn header_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn left(&self, header: usize) -> usize {
+        DLXTable::left(self, header)
+    }
+
+    fn right(&self, header: usize) -> usize {
+        DLXTable::right(self, header)
+    }
+
+    fn length(&self, header: usize) -> usize {
+        DLXTable::length(self, header)
+    }
+
+    fn up(&self, node: usize) -> usize {
+        DLXTable::up(self, node)
+    }
+
+    fn down(&self, node: usize) -> usize {
+        DLXTable::down(self, node)
+    }
+
+    fn header(&self, node: usize) -> usize {
+        DLXTable::header(self, node)
+    }
+
+    fn label(&self, column: usize) -> String {
+        format!("{:?}", self.names[column].unwrap())
+    }
+}
+
+pub fn min_length_column<T, I>(table: &DLXTable<T, I>) -> Option<usize> 
 where
-T: Eq + Copy + std::fmt::Debug {
-    let mut i = table.right_links[0];
+T: Eq + Copy + std::fmt::Debug,
+I: Index {
+    let mut i = table.right(0);
     let mut size = usize::MAX;
     let mut column = None;
     while i != 0 {
-        if table.lengths[i] < size {
+        if table.length(i) < size {
             column = Some(i);
-            size = table.lengths[i];
+            size = table.length(i);
         }
-        i = table.right_links[i];
+        i = table.right(i);
     }
 
     column
 }
 
-fn min_length_column_randomized<T>(table: &DLXTable<T>) -> Option<usize>
+pub fn min_length_column_randomized<T, I>(table: &DLXTable<T, I>) -> Option<usize>
 where
-T: Eq + Copy + std::fmt::Debug {
-    let mut i = table.right_links[0];
+T: Eq + Copy + std::fmt::Debug,
+I: Index {
+    let mut i = table.right(0);
     let mut size = usize::MAX;
     let mut columns = Vec::new();
     while i != 0 {
-        if table.lengths[i] < size {
+        if table.length(i) < size {
             columns = Vec::from([i]);
-            size = table.lengths[i];
+            size = table.length(i);
         }
-        else if table.lengths[i] == size {
+        else if table.length(i) == size {
             columns.push(i);
         }
-        i = table.right_links[i];
+        i = table.right(i);
     }
-    columns.choose(&mut rand::thread_rng()).cloned()
+    random::with_rng(|rng| columns.choose(rng).cloned())
 }
 
-fn search<T>(table: &mut DLXTable<T>, choose_column: fn(&DLXTable<T>) -> Option<usize>, 
-             partial_solution: &mut Vec<usize>) -> Option<Vec<usize>>
+fn search<T, I>(table: &mut DLXTable<T, I>, choose_column: fn(&DLXTable<T, I>) -> Option<usize>, 
+                partial_solution: &mut Vec<usize>) -> Option<Vec<usize>>
 where
-T: Eq + Copy + std::fmt::Debug {
+T: Eq + Copy + std::fmt::Debug,
+I: Index {
     if let Some(column) = min_length_column(table) {
         table.cover(column);
 
-        let mut row_node = table.down_links[column];
+        let mut row_node = table.down(column);
         while row_node != column {
             table.cover_row(row_node);
 
@@ -272,7 +621,7 @@ T: Eq + Copy + std::fmt::Debug {
             
             table.uncover_row(row_node);
 
-            row_node = table.down_links[row_node];
+            row_node = table.down(row_node);
         }
 
         table.uncover(column);
@@ -291,25 +640,39 @@ enum State {
     BacktrackingRow
 }
 
+fn checkpoint_state(state: State) -> CheckpointState {
+    match state {
+        State::CoveringColumn => CheckpointState::CoveringColumn,
+        State::CoveringRow => CheckpointState::CoveringRow,
+        State::BacktrackingColumn => CheckpointState::BacktrackingColumn,
+        State::BacktrackingRow => CheckpointState::BacktrackingRow
+    }
+}
+
 #[derive(PartialEq,Eq,Clone,Copy,Debug)]
 struct LevelState {
     column: usize,
     row_node: usize
 }
 
-pub struct DLXIter<T: Eq + Copy + std::fmt::Debug> {
-    table: DLXTable<T>,
+pub struct DLXIter<T: Eq + Copy + std::fmt::Debug, I: Index = u32> {
+    table: DLXTable<T, I>,
     stack: Vec<LevelState>,
     state: State,
-    choose_column: fn(&DLXTable<T>) -> Option<usize>
+    choose_column: fn(&DLXTable<T, I>) -> Option<usize>,
+    fingerprint: u64,
+    // the symmetry and the option of every node
+    symmetry: Option<(Symmetry, Vec<usize>)>,
+    trace: Option<Trace>
 }
 
-impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
-    pub fn from_table(mut table: DLXTable<T>, choose_column: fn(&DLXTable<T>) -> Option<usize>) -> Self {
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> DLXIter<T, I> {
+    pub fn from_table(mut table: DLXTable<T, I>, choose_column: fn(&DLXTable<T, I>) -> Option<usize>) -> Self {
+        let fingerprint = table.fingerprint();
         let mut stack = Vec::new();
         let state = State::CoveringRow;
         if let Some(column) = choose_column(&table) {
-            let row_node = table.down_links[column];
+            let row_node = table.down(column);
             stack.push(LevelState {
                 column,
                 row_node
@@ -317,16 +680,17 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
             table.cover(column);
         }
 
-        DLXIter { table, stack, state, choose_column }
+        DLXIter { table, stack, state, choose_column, fingerprint, symmetry: None, trace: None }
     }
 
-    pub fn new(sets: Vec<Vec<T>>, choose_column: fn(&DLXTable<T>) -> Option<usize>,
+    pub fn new(sets: Vec<Vec<T>>, choose_column: fn(&DLXTable<T, I>) -> Option<usize>,
                primary_items: Vec<T>, secondary_items: Vec<T>) -> Self {
         let mut table = DLXTable::new(sets, primary_items, secondary_items);
+        let fingerprint = table.fingerprint();
         let mut stack = Vec::new();
         let state = State::CoveringRow;
         if let Some(column) = choose_column(&table) {
-            let row_node = table.down_links[column];
+            let row_node = table.down(column);
             stack.push(LevelState {
                 column,
                 row_node
@@ -334,12 +698,15 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
             table.cover(column);
         }
 
-        DLXIter { table, stack, state, choose_column }
+        DLXIter { table, stack, state, choose_column, fingerprint, symmetry: None, trace: None }
     }
 
     fn cover_column(&mut self, column: usize) {
+        if let Some(trace) = &mut self.trace {
+            trace.choose(&self.table.names[column].unwrap(), self.table.length(column));
+        }
         self.table.cover(column);
-        let row_node = self.table.down_links[column];
+        let row_node = self.table.down(column);
         self.stack.push(LevelState { column, row_node });
 
         if row_node == column {
@@ -361,10 +728,205 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
             .collect()
     }
 
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            fingerprint: self.fingerprint,
+            state: checkpoint_state(self.state),
+            levels: self.stack
+                .iter()
+                .map(|level| (level.column, level.row_node))
+                .collect(),
+            costs: None
+        }
+    }
+
+    // Continues from a checkpoint of the same problem instead of where this search stands.
+    pub fn restore(mut self, checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        check_fingerprint(checkpoint, self.fingerprint)?;
+        let state = match checkpoint.state {
+            CheckpointState::CoveringColumn => State::CoveringColumn,
+            CheckpointState::CoveringRow => State::CoveringRow,
+            CheckpointState::BacktrackingColumn => State::BacktrackingColumn,
+            CheckpointState::BacktrackingRow => State::BacktrackingRow,
+            CheckpointState::FoundSolution =>
+                return Err(CheckpointError::Mismatch("dlx checkpoints never stop at a solution".to_string()))
+        };
+        if checkpoint.costs.is_some() {
+            return Err(CheckpointError::Mismatch("the checkpoint was taken from min_cost_dlxc".to_string()))
+        }
+        check_levels(checkpoint, |column, row_node| self.table.is_level(column, row_node))?;
+
+        self.unwind();
+        self.replay(&checkpoint.levels, state);
+        Ok(self)
+    }
+
+    // Only enumerates the smallest solution of every orbit of the symmetry.
+    // The symmetry acts on the option indices of the table.
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        if symmetry.option_count() != self.table.option_count() {
+            panic!("The symmetry acts on {} options, but the table has {}.",
+                   symmetry.option_count(), self.table.option_count());
+        }
+        let node_options = self.table.node_options();
+        self.symmetry = Some((symmetry, node_options));
+        self
+    }
+
+    // Records the search tree from here on, see trace.rs.
+    // The levels that are already on the stack are recorded as if they were just chosen.
+    pub fn with_trace(mut self) -> Self {
+        let mut trace = Trace::new();
+        for (i, level) in self.stack.iter().enumerate() {
+            trace.choose(&self.table.names[level.column].unwrap(), self.table.length(level.column));
+            if i + 1 < self.stack.len() || last_row_covered(checkpoint_state(self.state)) {
+                trace.try_option(&self.table.get_row(level.row_node));
+            }
+        }
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    fn may_lead(&self) -> bool {
+        match &self.symmetry {
+            Some((symmetry, node_options)) => symmetry.may_lead(|option| {
+                if self.stack.iter().any(|level| node_options[level.row_node] == option) {
+                    Choice::Chosen
+                }
+                else if self.table.is_open(option) {
+                    Choice::Open
+                }
+                else {
+                    Choice::Excluded
+                }
+            }),
+            None => true
+        }
+    }
+
+    // The number of solutions that the current one stands for, None if it is not the smallest of its orbit.
+    fn orbit_size(&self) -> Option<usize> {
+        match &self.symmetry {
+            Some((symmetry, node_options)) => {
+                let mut chosen = vec![false; symmetry.option_count()];
+                for level in &self.stack {
+                    chosen[node_options[level.row_node]] = true;
+                }
+                symmetry.orbit_size(&chosen)
+            },
+            None => Some(1)
+        }
+    }
+
+    // The table as the search left it, with the columns and rows of its levels covered.
+    pub fn table(&self) -> &DLXTable<T, I> {
+        &self.table
+    }
+
+    // Gives the table back with all columns uncovered, to edit it or to start another search on it.
+    pub fn into_table(mut self) -> DLXTable<T, I> {
+        self.unwind();
+        self.table
+    }
+
+    // Edits the table and continues the search where it stands. The parts of the search
+    // that are already explored are not revisited with the new items and options.
+    // If the row of a level was retired, the search goes on with the next row of that level.
+    pub fn edit(mut self, edit: impl FnOnce(&mut DLXTable<T, I>)) -> Self {
+        let state = self.state;
+        let header_count = self.table.names.len();
+        let levels = self.unwind();
+        edit(&mut self.table);
+        self.fingerprint = self.table.fingerprint();
+        if let Some((symmetry, _)) = self.symmetry.take() {
+            self = self.with_symmetry(symmetry);
+        }
+
+        // the option nodes moved up by the number of new items
+        let shift = self.table.names.len() - header_count;
+        let levels: Vec<_> = levels
+            .into_iter()
+            .map(|(column, row_node)| if row_node >= header_count {
+                (column, row_node + shift)
+            }
+            else {
+                (column, row_node)
+            })
+            .collect();
+
+        let retired = levels
+            .iter()
+            .position(|&(column, row_node)| row_node != column && self.table.is_retired(row_node));
+        match retired {
+            Some(level) => {
+                self.replay(&levels[..level], State::CoveringColumn);
+                let (column, retired_node) = levels[level];
+                self.table.cover(column);
+                // the column lists are sorted, so the rows that are left come after the retired one
+                let mut row_node = self.table.down(column);
+                while row_node != column && row_node < retired_node {
+                    row_node = self.table.down(row_node);
+                }
+                self.stack.push(LevelState { column, row_node });
+                self.state = if row_node == column {
+                    State::BacktrackingColumn
+                }
+                else {
+                    State::CoveringRow
+                };
+            },
+            None => {
+                self.replay(&levels, state);
+            }
+        }
+        self
+    }
+
+    // Undoes the search so far and returns its levels.
+    fn unwind(&mut self) -> Vec<(usize, usize)> {
+        let levels = self.stack
+            .iter()
+            .map(|level| (level.column, level.row_node))
+            .collect();
+        // the rows below the last level are all covered
+        let mut row_covered = last_row_covered(checkpoint_state(self.state));
+        while let Some(level) = self.stack.pop() {
+            if row_covered {
+                self.table.uncover_row(level.row_node);
+            }
+            self.table.uncover(level.column);
+            row_covered = true;
+        }
+        levels
+    }
+
+    // Covers the columns and rows of the levels again, on a table where nothing is covered.
+    fn replay(&mut self, levels: &[(usize, usize)], state: State) {
+        for (i, &(column, row_node)) in levels.iter().enumerate() {
+            self.table.cover(column);
+            if i + 1 < levels.len() || last_row_covered(checkpoint_state(state)) {
+                self.table.cover_row(row_node);
+            }
+            self.stack.push(LevelState { column, row_node });
+        }
+        self.state = state;
+    }
+
     fn backtrack_row(&mut self) {
         let mut level = self.stack.pop().unwrap();
         self.table.uncover_row(level.row_node);
-        level.row_node = self.table.down_links[level.row_node];
+        if let Some(trace) = &mut self.trace {
+            trace.leave();
+        }
+        level.row_node = self.table.down(level.row_node);
         self.stack.push(level);
         if level.row_node == level.column {
             // we tried the last row
@@ -378,10 +940,10 @@ impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
     }
 }
 
-impl<T: Eq + Copy + std::fmt::Debug> Iterator for DLXIter<T> {
-    type Item = Vec<Vec<T>>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> DLXIter<T, I> {
+    // The next solution together with the number of solutions it stands for,
+    // which is more than one only with a symmetry.
+    pub fn next_representative(&mut self) -> Option<(Vec<Vec<T>>, usize)> {
         while !self.stack.is_empty() {
             match self.state {
                 State::CoveringColumn => {
@@ -392,14 +954,31 @@ impl<T: Eq + Copy + std::fmt::Debug> Iterator for DLXIter<T> {
                     else {
                         // all columns are covered
                         self.state = State::BacktrackingRow;
-                        return Some(self.get_solution())
+                        if let Some(orbit_size) = self.orbit_size() {
+                            if let Some(trace) = &mut self.trace {
+                                trace.solution();
+                            }
+                            return Some((self.get_solution(), orbit_size))
+                        }
                     }
                 },
                 State::CoveringRow => {
-                    // cover the current row and set up for the next level 
+                    // cover the current row and set up for the next level,
+                    // unless a symmetric part of the search covers it
                     let level = self.stack.last().unwrap();
                     self.table.cover_row(level.row_node);
-                    self.state = State::CoveringColumn;
+                    if let Some(trace) = &mut self.trace {
+                        trace.try_option(&self.table.get_row(level.row_node));
+                    }
+                    self.state = if self.may_lead() {
+                        State::CoveringColumn
+                    }
+                    else {
+                        if let Some(trace) = &mut self.trace {
+                            trace.prune();
+                        }
+                        State::BacktrackingRow
+                    };
                 },
                 State::BacktrackingRow => {
                     // uncover the current row and set up to cover the next one
@@ -418,7 +997,15 @@ impl<T: Eq + Copy + std::fmt::Debug> Iterator for DLXIter<T> {
     }
 }
 
-impl<T: Eq + Copy + std::fmt::Debug> DLXIter<T> {
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> Iterator for DLXIter<T, I> {
+    type Item = Vec<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_representative().map(|(solution, _)| solution)
+    }
+}
+
+impl<T: Eq + Copy + std::fmt::Debug, I: Index> DLXIter<T, I> {
     fn first_solution(&mut self, time_limit: Duration) -> Option<Vec<Vec<T>>> {
         let start = Instant::now();
         while !self.stack.is_empty() {
@@ -471,11 +1058,100 @@ pub fn dlx_iter_randomized<T: Eq + Copy + std::fmt::Debug>(sets: Vec<Vec<T>>, pr
 pub fn dlx_first<T>(sets: Vec<Vec<T>>, primary_items: Vec<T>, 
                     secondary_items: Vec<T>, time_limit: Duration) -> Option<Vec<Vec<T>>>
 where T: Eq + Copy + std::fmt::Debug {
-    DLXIter::new(sets, min_length_column, primary_items, secondary_items).first_solution(time_limit)
+    dlx_iter(sets, primary_items, secondary_items).first_solution(time_limit)
 }
 
 pub fn dlx_first_randomized<T>(sets: Vec<Vec<T>>, primary_items: Vec<T>, 
                                secondary_items: Vec<T>, time_limit: Duration) -> Option<Vec<Vec<T>>>
 where T: Eq + Copy + std::fmt::Debug {
-    DLXIter::new(sets, min_length_column_randomized, primary_items, secondary_items).first_solution(time_limit)
+    dlx_iter_randomized(sets, primary_items, secondary_items).first_solution(time_limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use super::DLXIter;
+    use super::DLXTable;
+    use super::min_length_column;
+
+    fn random_sets(rng: &mut StdRng, items: &[usize]) -> Vec<Vec<usize>> {
+        (0..rng.gen_range(0..10))
+            .map(|_| items.iter().copied().filter(|_| rng.gen_bool(0.35)).collect::<Vec<_>>())
+            .filter(|set| !set.is_empty())
+            .collect()
+    }
+
+    // The solutions with their rows and items sorted, so that they do not depend on the layout of the table.
+    fn normalized(solutions: impl Iterator<Item = Vec<Vec<usize>>>) -> Vec<Vec<Vec<usize>>> {
+        let mut solutions: Vec<_> = solutions
+            .map(|mut solution| {
+                for row in solution.iter_mut() {
+                    row.sort();
+                }
+                solution.sort();
+                solution
+            })
+            .collect();
+        solutions.sort();
+        solutions
+    }
+
+    #[test]
+    fn edited_table_matches_fresh_table() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let mut ran = 0;
+        while ran < 1000 {
+            let primary_items: Vec<usize> = (0..rng.gen_range(1..6)).collect();
+            let secondary_items: Vec<usize> = (100..100 + rng.gen_range(0..4)).collect();
+            let new_primary_items: Vec<usize> = (10..10 + rng.gen_range(0..4)).collect();
+            let new_secondary_items: Vec<usize> = (200..200 + rng.gen_range(0..3)).collect();
+            let old_items: Vec<usize> = primary_items.iter().chain(&secondary_items).copied().collect();
+            let all_items: Vec<usize> = old_items.iter().chain(&new_primary_items).chain(&new_secondary_items).copied().collect();
+            let first = random_sets(&mut rng, &old_items);
+            let second = random_sets(&mut rng, &all_items);
+            let sets: Vec<Vec<usize>> = first.iter().chain(&second).cloned().collect();
+            let retired: Vec<usize> = (0..sets.len()).filter(|_| rng.gen_bool(0.2)).collect();
+            let kept: Vec<Vec<usize>> = (0..sets.len())
+                .filter(|option| !retired.contains(option))
+                .map(|option| sets[option].clone())
+                .collect();
+
+            // the search does not end while a primary item has no options
+            let all_primary_items: Vec<usize> = primary_items.iter().chain(&new_primary_items).copied().collect();
+            if all_primary_items.iter().any(|item| !kept.iter().any(|set| set.contains(item))) {
+                continue
+            }
+            ran += 1;
+
+            let all_secondary_items: Vec<usize> = secondary_items.iter().chain(&new_secondary_items).copied().collect();
+            let fresh = DLXIter::<usize>::new(kept.clone(), min_length_column, all_primary_items.clone(), all_secondary_items.clone());
+            let expected = normalized(fresh);
+
+            let mut table = DLXTable::<usize>::new(first.clone(), primary_items.clone(), secondary_items.clone());
+            table.add_items(new_primary_items.clone(), new_secondary_items.clone());
+            table.add_options(second.clone());
+            for &option in retired.iter() {
+                table.retire_option(option);
+            }
+            assert_eq!(normalized(DLXIter::from_table(table, min_length_column)), expected, "{:?} retiring {:?}", sets, retired);
+
+            // the same edits on the table of a search that has not started,
+            // which has already chosen its first column, so that column must not be empty
+            if primary_items.iter().any(|item| !first.iter().any(|set| set.contains(item))) {
+                continue
+            }
+            let iter = DLXIter::<usize>::new(first.clone(), min_length_column, primary_items.clone(), secondary_items.clone())
+                .edit(|table| {
+                    table.add_secondary_items(new_secondary_items.clone());
+                    table.add_primary_items(new_primary_items.clone());
+                    table.add_options(second.clone());
+                    for &option in retired.iter() {
+                        table.retire_option(option);
+                    }
+                });
+            assert_eq!(normalized(iter), expected, "{:?} retiring {:?}", sets, retired);
+        }
+    }
 }
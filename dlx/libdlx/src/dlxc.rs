@@ -1,8 +1,22 @@
 use std::time::Instant;
 use std::time::Duration;
 use rand::seq::SliceRandom;
+use crate::random;
+use crate::index::Index;
+use crate::index::Header;
+use crate::index::check_capacity;
+use crate::checkpoint::Checkpoint;
+use crate::checkpoint::CheckpointError;
+use crate::checkpoint::check_levels;
+use crate::checkpoint::Fingerprint;
+use crate::checkpoint::check_fingerprint;
+use crate::checkpoint::last_row_covered;
+use crate::trace::Trace;
+use crate::inspect;
+use crate::inspect::Links;
 
 #[derive(Clone,Copy,PartialEq,Eq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Item<P, S, C> 
 where
 P: Eq + Copy + std::fmt::Debug,
@@ -11,153 +25,438 @@ C: Eq + Copy + std::fmt::Debug {
     Primary(P),
     Secondary(S),
     ColoredSecondary(S, C),
+    // covers the primary item and gives it the color
+    ColoredPrimary(P, C),
+    // does not cover the primary item, but the option that covers it must give it the color
+    PrimaryColor(P, C),
 }
 
+// The columns of a table and the colors its nodes can have, shared by dlxc and min_cost_dlxc.
+// A primary item that is colored in some option gets a second, secondary column for its color,
+// which options covering the item without a color claim exclusively.
+// An option can give an item several colors. It gets a single node whose color stands
+// for the set of them, so that options only agree on the item if they give it the same colors.
 #[derive(Clone,PartialEq,Eq,Debug)]
-pub struct DLXCTable<P, S, C> 
-where 
+pub(crate) struct Columns<P, S, C>
+where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug  {
-    names: Vec<Option<Item<P, S, C>>>,
-    color_names: Vec<Option<C>>,
-    left_links: Vec<usize>,
-    right_links: Vec<usize>,
-    lengths: Vec<usize>,
-    up_links: Vec<usize>,
-    down_links: Vec<usize>,
-    header_links: Vec<usize>,
-    colors: Vec<usize>,
+C: Eq + Copy + std::fmt::Debug {
+    // the item of every column, with None for the root and Primary for the color columns
+    pub(crate) names: Vec<Option<Item<P, S, C>>>,
+    pub(crate) primary_count: usize,
+    // the color column of a primary column and the other way around, 0 if there is none
+    partners: Vec<usize>,
+    // color 0 is no color, then come the given colors and the sets used by options
+    colors: Vec<Vec<C>>,
+    given_colors: usize
+}
+
+pub(crate) fn same_colors<C: Eq>(a: &[C], b: &[C]) -> bool {
+    a.iter().all(|c| b.contains(c)) && b.iter().all(|c| a.contains(c))
+}
+
+// Adds the color to the colors listed for the key, e.g. the node of an option in a column.
+pub(crate) fn add_color<C: Eq>(entries: &mut Vec<(usize, Vec<C>)>, key: usize, color: Option<C>) {
+    if let Some((_, colors)) = entries.iter_mut().find(|(other, _)| *other == key) {
+        if let Some(color) = color {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+    }
+    else {
+        entries.push((key, color.into_iter().collect()));
+    }
 }
 
-fn has_name<P, S, C>(item: Item<P, S, C>, name: Option<Item<P, S, C>>) -> bool
+impl<P, S, C> Columns<P, S, C>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
 C: Eq + Copy + std::fmt::Debug {
-    match (name, item) {
-        (Some(Item::Primary(n)), Item::Primary(i)) => i == n,
-        (Some(Item::Secondary(n)), Item::Secondary(i)) => i == n,
-        (Some(Item::Secondary(n)), Item::ColoredSecondary(i, _)) => i == n,
-        _ => false
+    pub(crate) fn new<'a>(sets: impl Iterator<Item = &'a Vec<Item<P, S, C>>>, primary_items: Vec<P>,
+                          secondary_items: Vec<S>, colors: Vec<C>) -> Self
+    where
+    P: 'a,
+    S: 'a,
+    C: 'a {
+        let mut colored_primaries = Vec::new();
+        for item in sets.flatten() {
+            if let Item::ColoredPrimary(p, _) | Item::PrimaryColor(p, _) = *item {
+                if !colored_primaries.contains(&p) {
+                    colored_primaries.push(p);
+                }
+            }
+        }
+
+        let primary_count = primary_items.len();
+        let mut names = Vec::with_capacity(1 + primary_count + secondary_items.len() + colored_primaries.len());
+        names.push(None);
+        for item in primary_items {
+            names.push(Some(Item::Primary(item)));
+        }
+
+        for item in secondary_items {
+            names.push(Some(Item::Secondary(item)));
+        }
+
+        let mut partners = vec![0; names.len()];
+        for p in colored_primaries {
+            let column = names
+                .iter()
+                .position(|&name| name == Some(Item::Primary(p)))
+                .unwrap_or_else(|| panic!("{:?} not present", Item::<P, S, C>::Primary(p)));
+            partners[column] = names.len();
+            partners.push(column);
+            names.push(Some(Item::Primary(p)));
+        }
+
+        let given_colors = colors.len();
+        let mut color_sets = Vec::with_capacity(colors.len() + 1);
+        color_sets.push(Vec::new());
+        for color in colors {
+            color_sets.push(vec![color]);
+        }
+
+        Columns {
+            names,
+            primary_count,
+            partners,
+            colors: color_sets,
+            given_colors
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub(crate) fn is_primary(&self, column: usize) -> bool {
+        column > 0 && column <= self.primary_count
+    }
+
+    fn column(&self, item: Item<P, S, C>) -> usize {
+        let position = match item {
+            Item::Primary(p) | Item::ColoredPrimary(p, _) => self.names[1..=self.primary_count]
+                .iter()
+                .position(|&name| name == Some(Item::Primary(p))),
+            Item::Secondary(s) | Item::ColoredSecondary(s, _) => self.names[self.primary_count + 1..]
+                .iter()
+                .position(|&name| name == Some(Item::Secondary(s)))
+                .map(|i| i + self.primary_count),
+            Item::PrimaryColor(_, _) => None
+        };
+        position
+            .map(|i| i + 1)
+            .unwrap_or_else(|| panic!("{:?} not present", item))
+    }
+
+    fn color_index(&mut self, colors: Vec<C>) -> usize {
+        if colors.is_empty() {
+            return 0
+        }
+
+        for color in &colors {
+            if !self.colors[1..=self.given_colors].iter().any(|given| given[0] == *color) {
+                panic!("color {:?} not present", color);
+            }
+        }
+
+        if let Some(index) = self.colors.iter().position(|set| same_colors(set, &colors)) {
+            index
+        }
+        else {
+            self.colors.push(colors);
+            self.colors.len() - 1
+        }
+    }
+
+    // The columns and colors of the nodes of an option, in the order of its items.
+    // Colored primary items get a node in their color column as well.
+    pub(crate) fn nodes(&mut self, set: &[Item<P, S, C>]) -> Vec<(usize, usize)> {
+        let mut nodes = Vec::with_capacity(set.len());
+        for &item in set {
+            match item {
+                Item::Primary(p) => {
+                    if set.iter().any(|&other| matches!(other, Item::PrimaryColor(q, _) if q == p)) {
+                        panic!("{:?} covers {:?} and gives it a color, use ColoredPrimary instead", set, p);
+                    }
+                    let column = self.column(item);
+                    add_color(&mut nodes, column, None);
+                    // covering a primary item without a color claims its color column
+                    if self.partners[column] != 0 {
+                        add_color(&mut nodes, self.partners[column], None);
+                    }
+                },
+                Item::Secondary(_) => add_color(&mut nodes, self.column(item), None),
+                Item::ColoredSecondary(_, c) => add_color(&mut nodes, self.column(item), Some(c)),
+                Item::ColoredPrimary(_, c) => {
+                    let column = self.column(item);
+                    add_color(&mut nodes, column, None);
+                    add_color(&mut nodes, self.partners[column], Some(c));
+                },
+                Item::PrimaryColor(p, c) => {
+                    let column = self.column(Item::Primary(p));
+                    add_color(&mut nodes, self.partners[column], Some(c));
+                }
+            }
+        }
+
+        nodes.into_iter()
+            .map(|(column, colors)| (column, self.color_index(colors)))
+            .collect()
+    }
+
+    // The items that a row stands for, given the columns and colors of its nodes.
+    // Secondary items get one item per color and color columns show up with their primary item.
+    pub(crate) fn items(&self, nodes: &[(usize, usize)]) -> Vec<Item<P, S, C>> {
+        let mut items = Vec::with_capacity(nodes.len());
+        for &(column, color) in nodes {
+            let partner = self.partners[column];
+            match self.names[column] {
+                Some(Item::Primary(p)) if self.is_primary(column) => {
+                    let colors = nodes
+                        .iter()
+                        .find(|&&(other, _)| partner != 0 && other == partner)
+                        .map(|&(_, color)| &self.colors[color][..])
+                        .unwrap_or(&[]);
+                    if colors.is_empty() {
+                        items.push(Item::Primary(p));
+                    }
+                    else {
+                        items.extend(colors.iter().map(|&c| Item::ColoredPrimary(p, c)));
+                    }
+                },
+                Some(Item::Primary(p)) => {
+                    if !nodes.iter().any(|&(other, _)| other == partner) {
+                        items.extend(self.colors[color].iter().map(|&c| Item::PrimaryColor(p, c)));
+                    }
+                },
+                Some(Item::Secondary(s)) => {
+                    if self.colors[color].is_empty() {
+                        items.push(Item::Secondary(s));
+                    }
+                    else {
+                        items.extend(self.colors[color].iter().map(|&c| Item::ColoredSecondary(s, c)));
+                    }
+                },
+                _ => panic!("Node in column {} has no item. Something went horribly wrong.", column)
+            }
+        }
+        items
+    }
+
+    // The item of a column as the dumps show it, color columns with /color after their item.
+    pub(crate) fn label(&self, column: usize) -> String {
+        match self.names[column] {
+            Some(Item::Primary(p)) if !self.is_primary(column) => format!("{:?}/color", p),
+            Some(Item::Primary(p)) => format!("{:?}", p),
+            Some(Item::Secondary(s)) => format!("{:?}", s),
+            _ => String::from("the root")
+        }
+    }
+
+    fn color_label(&self, color: usize) -> String {
+        self.colors[color]
+            .iter()
+            .map(|c| format!("{:?}", c))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    // The dump of a table with these columns, see inspect.rs. The options show the colors of their
+    // nodes and the secondary items the color that the chosen options gave them.
+    // Options that conflict with that color are left out, min_cost_dlxc does not hide them all.
+    pub(crate) fn dump(&self, table: &impl Links, color: impl Fn(usize) -> usize, cost: impl Fn(usize) -> Option<String>) -> String {
+        // purified nodes have the color of their column
+        let node_color = |node| match color(node) {
+            usize::MAX => color(table.header(node)),
+            node_color => node_color
+        };
+        let primary = inspect::active_primary(table);
+        let secondary = inspect::active_secondary(table);
+        let options = inspect::active_options(table, &[&primary[..], &secondary[..]].concat())
+            .into_iter()
+            // options with another color than the one their column was given
+            .filter(|&first_node| (first_node..)
+                .take_while(|&k| table.header(k) != 0)
+                .all(|k| matches!(color(table.header(k)), 0 | usize::MAX) || node_color(k) == color(table.header(k))))
+            .map(|first_node| {
+                let mut nodes = Vec::new();
+                let mut k = first_node;
+                while table.header(k) != 0 {
+                    nodes.push((table.header(k), node_color(k)));
+                    k += 1;
+                }
+                let items = self.items(&nodes)
+                    .iter()
+                    .map(item_label)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                match cost(first_node) {
+                    Some(cost) => format!("{}  (cost {})", items, cost),
+                    None => items
+                }
+            })
+            .collect();
+        let primary = primary
+            .into_iter()
+            .map(|column| (self.label(column), table.length(column)))
+            .collect();
+        let secondary = secondary
+            .into_iter()
+            .map(|column| match color(column) {
+                0 => (self.label(column), table.length(column)),
+                color => (format!("{}:{}", self.label(column), self.color_label(color)), table.length(column))
+            })
+            .collect();
+        inspect::render(primary, secondary, options)
+    }
+
+    // The colors of the secondary items, given the colors of their columns.
+    pub(crate) fn assignments(&self, color: impl Fn(usize) -> usize) -> Vec<(S, Option<C>)> {
+        let mut assignments = Vec::new();
+        for (i, name) in self.names.iter().enumerate() {
+            if let Some(Item::Secondary(item)) = *name {
+                let colors = &self.colors[color(i)];
+                if colors.is_empty() {
+                    assignments.push((item, None));
+                }
+                else {
+                    assignments.extend(colors.iter().map(|&c| (item, Some(c))));
+                }
+            }
+        }
+        assignments
     }
 }
 
-fn add_node<P, S, C>(table: &mut DLXCTable<P, S, C>, index: usize, item: Item<P, S, C>) 
+// An item as the dumps show it, with its colors after a colon.
+fn item_label<P, S, C>(item: &Item<P, S, C>) -> String
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
 C: Eq + Copy + std::fmt::Debug {
-    let header_index = table.names
-        .iter()
-        .position(|&name| has_name(item, name))
-        .expect(&format!("{:?} not present", item));
-    table.lengths[header_index] += 1;
+    match item {
+        Item::Primary(p) => format!("{:?}", p),
+        Item::Secondary(s) => format!("{:?}", s),
+        Item::ColoredSecondary(s, c) => format!("{:?}:{:?}", s, c),
+        Item::ColoredPrimary(p, c) => format!("{:?}:{:?}", p, c),
+        Item::PrimaryColor(p, c) => format!("{:?}/color:{:?}", p, c)
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Default)]
+struct Node<I: Index> {
+    up: I,
+    down: I,
+    header: I,
+    color: I
+}
+
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct DLXCTable<P, S, C, I = u32> 
+where 
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    columns: Columns<P, S, C>,
+    headers: Vec<Header<I>>,
+    nodes: Vec<Node<I>>
+}
+
+fn add_node<P, S, C, I>(table: &mut DLXCTable<P, S, C, I>, index: usize, header_index: usize, color: usize) 
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    table.inc_length(header_index);
     
     // node setup
-    table.up_links[index] = table.up_links[header_index];
-    table.down_links[index] = header_index;
-    table.header_links[index] = header_index;
+    table.set_up(index, table.up(header_index));
+    table.set_down(index, header_index);
+    table.set_header(index, header_index);
 
     // uplink setup
-    table.down_links[table.up_links[index]] = index;
+    table.set_down(table.up(index), index);
     
     // header setup
-    if table.down_links[header_index] == header_index {
-        table.down_links[header_index] = index;
+    if table.down(header_index) == header_index {
+        table.set_down(header_index, index);
     }
-    table.up_links[header_index] = index;
+    table.set_up(header_index, index);
 
-    if let Item::ColoredSecondary(_, c) = item {
-        let color_index = table.color_names
-            .iter()
-            .position(|color| color.is_some() && c == color.unwrap())
-            .unwrap();
-        table.colors[index] = color_index;
-    }
+    table.set_color(index, color);
 }
 
-impl<P, S, C> DLXCTable<P, S, C> 
+impl<P, S, C, I> DLXCTable<P, S, C, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
     pub fn new(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
-        let primary_count = primary_items.len();
-        let mut names = Vec::with_capacity(1 + primary_items.len() + secondary_items.len());
-        names.push(None);
-        for item in primary_items {
-            names.push(Some(Item::Primary(item)));
-        }
-
-        for item in secondary_items {
-            names.push(Some(Item::Secondary(item)));
-        }
-
-        let names_count = names.len();
-        
-        
-        let mut color_names = Vec::with_capacity(colors.len() + 1);
-        color_names.push(None);
-        for color in colors {
-            color_names.push(Some(color));
-        }
+        let mut columns = Columns::new(sets.iter(), primary_items, secondary_items, colors);
+        let primary_count = columns.primary_count;
+        let names_count = columns.len();
+        let options: Vec<_> = sets
+            .iter()
+            .map(|set| columns.nodes(set))
+            .collect();
 
-        let node_count = 1 + names_count + sets.len() + sets
+        let node_count = 1 + names_count + options.len() + options
             .iter()
-            .map(|set| set
-                .iter()
-                .map(|_| 1)
-                .sum::<usize>())
+            .map(|option| option.len())
             .sum::<usize>();
 
+        check_capacity::<I>(node_count);
         let mut table = DLXCTable {
-            names,
-            color_names,
-            left_links: vec![0; names_count],
-            right_links: vec![0; names_count],
-            lengths: vec![0; names_count],
-            up_links: vec![0; node_count],
-            down_links: vec![0; node_count],
-            header_links: vec![0; node_count],
-            colors: vec![0; node_count]
+            columns,
+            headers: vec![Header::default(); names_count],
+            nodes: vec![Node::default(); node_count]
         };
 
         // header setup
-        table.left_links[0] = primary_count;
+        table.set_left(0, primary_count);
         for i in 0..primary_count {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
+            table.set_left(i+1, i);
+            table.set_right(i, i+1);
+            table.set_up(i+1, i+1);
+            table.set_down(i+1, i+1);
         }
 
-        table.left_links[primary_count + 1] = names_count - 1;
+        // the secondary items form their own circular list, if there are any
+        if names_count > primary_count + 1 {
+            table.set_left(primary_count + 1, names_count - 1);
 
-        table.up_links[primary_count + 1] = primary_count + 1;
-        table.down_links[primary_count + 1] = primary_count + 1;
-        for i in primary_count+1..names_count-1 {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
-        }
+            table.set_up(primary_count + 1, primary_count + 1);
+            table.set_down(primary_count + 1, primary_count + 1);
+            for i in primary_count+1..names_count-1 {
+                table.set_left(i+1, i);
+                table.set_right(i, i+1);
+                table.set_up(i+1, i+1);
+                table.set_down(i+1, i+1);
+            }
 
-        table.right_links[names_count - 1] = primary_count + 1;
+            table.set_right(names_count - 1, primary_count + 1);
+        }
 
         let mut prev_spacer = names_count;
         
         let mut current_index = names_count + 1;
-        for set in sets {
-            if set.len() > 0 {
-                for item in set {
-                    add_node(&mut table, current_index, item);
+        for option in options {
+            if !option.is_empty() {
+                for (column, color) in option {
+                    add_node(&mut table, current_index, column, color);
                     current_index += 1;
                 }
     
                 // spacer
-                table.up_links[current_index] = prev_spacer + 1;
-                table.down_links[prev_spacer] = current_index - 1;
+                table.set_up(current_index, prev_spacer + 1);
+                table.set_down(prev_spacer, current_index - 1);
                 prev_spacer = current_index;
                 current_index += 1;
             }
@@ -166,102 +465,190 @@ C: Eq + Copy + std::fmt::Debug {
         table
     }
 
+    fn left(&self, header: usize) -> usize {
+        self.headers[header].left.to_usize()
+    }
+
+    fn set_left(&mut self, header: usize, value: usize) {
+        self.headers[header].left = I::from_usize(value);
+    }
+
+    fn right(&self, header: usize) -> usize {
+        self.headers[header].right.to_usize()
+    }
+
+    fn set_right(&mut self, header: usize, value: usize) {
+        self.headers[header].right = I::from_usize(value);
+    }
+
+    fn length(&self, header: usize) -> usize {
+        self.headers[header].length.to_usize()
+    }
+
+    fn inc_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) + 1);
+    }
+
+    fn dec_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) - 1);
+    }
+
+    fn up(&self, node: usize) -> usize {
+        self.nodes[node].up.to_usize()
+    }
+
+    fn set_up(&mut self, node: usize, value: usize) {
+        self.nodes[node].up = I::from_usize(value);
+    }
+
+    fn down(&self, node: usize) -> usize {
+        self.nodes[node].down.to_usize()
+    }
+
+    fn set_down(&mut self, node: usize, value: usize) {
+        self.nodes[node].down = I::from_usize(value);
+    }
+
+    fn header(&self, node: usize) -> usize {
+        self.nodes[node].header.to_usize()
+    }
+
+    fn set_header(&mut self, node: usize, value: usize) {
+        self.nodes[node].header = I::from_usize(value);
+    }
+
+    // Purified nodes are marked with usize::MAX, which is stored as I::MAX.
+    fn color(&self, node: usize) -> usize {
+        let color = self.nodes[node].color;
+        if color == I::MAX {
+            usize::MAX
+        }
+        else {
+            color.to_usize()
+        }
+    }
+
+    fn set_color(&mut self, node: usize, value: usize) {
+        self.nodes[node].color = if value == usize::MAX {
+            I::MAX
+        }
+        else {
+            I::from_usize(value)
+        };
+    }
+
     fn commit(&mut self, row_node: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         if color == 0 {
             self.cover(header);
         }
         else if color != usize::MAX {
             self.purify(row_node);
-            self.colors[header] = color;
+            self.set_color(header, color);
         }
     }
 
     fn uncommit(&mut self, row_node: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         if color == 0 {
             self.uncover(header);
         }
         else if color != usize::MAX {
             self.unpurify(row_node);
-            self.colors[header] = 0;
+            self.set_color(header, 0);
         }
     }
 
     fn purify(&mut self, row_node: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         
-        let mut i = self.down_links[header];
+        let mut i = self.down(header);
         while i != header {
-            if self.colors[i] == color {
-                self.colors[i] = usize::MAX;
+            if self.color(i) == color {
+                self.set_color(i, usize::MAX);
             }
             else {
                 self.hide(i);
             }
 
-            i = self.down_links[i];
+            i = self.down(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
     fn unpurify(&mut self, row_node: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         
-        let mut i = self.up_links[header];
+        let mut i = self.up(header);
         while i != header {
-            if self.colors[i] == usize::MAX {
-                self.colors[i] = color;
+            if self.color(i) == usize::MAX {
+                self.set_color(i, color);
             }
             else {
                 self.unhide(i);
             }
 
-            i = self.up_links[i];
+            i = self.up(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
     fn cover(&mut self, column: usize) {
-        self.left_links[self.right_links[column]] = self.left_links[column];
-        self.right_links[self.left_links[column]] = self.right_links[column];
+        self.set_left(self.right(column), self.left(column));
+        self.set_right(self.left(column), self.right(column));
 
-        let mut i = self.down_links[column];
+        let mut i = self.down(column);
         while i != column {
             self.hide(i);
-            i = self.down_links[i];
+            i = self.down(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
     fn uncover(&mut self, column: usize) {
-        let mut i = self.up_links[column];
+        let mut i = self.up(column);
         while i != column {
             self.unhide(i);
-            i = self.up_links[i];
+            i = self.up(i);
         }
 
-        self.left_links[self.right_links[column]] = column;
-        self.right_links[self.left_links[column]] = column;
+        self.set_left(self.right(column), column);
+        self.set_right(self.left(column), column);
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
+        }
     }
     
     fn hide(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            if self.colors[i] != usize::MAX {
-                let header = self.header_links[i];
+            if self.color(i) != usize::MAX {
+                let header = self.header(i);
                 if header == 0 {
-                    i = self.up_links[i];
+                    i = self.up(i);
                 }
                 else {
-                    self.up_links[self.down_links[i]] = self.up_links[i];
-                    self.down_links[self.up_links[i]] = self.down_links[i];
-                    if self.lengths[header] == 0 {
-                        panic!("underflowing header {:?}", self.names[header]);
+                    self.set_up(self.down(i), self.up(i));
+                    self.set_down(self.up(i), self.down(i));
+                    if self.length(header) == 0 {
+                        panic!("underflowing header {:?}", self.columns.names[header]);
                     }
                     else {
-                        self.lengths[header] -= 1;
+                        self.dec_length(header);
                     }
     
                     i += 1;
@@ -276,15 +663,15 @@ C: Eq + Copy + std::fmt::Debug {
     fn unhide(&mut self, row_node: usize) {
         let mut i = row_node - 1;
         while i != row_node {
-            if self.colors[i] != usize::MAX {
-                let header = self.header_links[i];
+            if self.color(i) != usize::MAX {
+                let header = self.header(i);
                 if header == 0 {
-                    i = self.down_links[i];
+                    i = self.down(i);
                 }
                 else {
-                    self.lengths[header] += 1;
-                    self.up_links[self.down_links[i]] = i;
-                    self.down_links[self.up_links[i]] = i;
+                    self.inc_length(header);
+                    self.set_up(self.down(i), i);
+                    self.set_down(self.up(i), i);
     
                     i -= 1;
                 }
@@ -298,9 +685,9 @@ C: Eq + Copy + std::fmt::Debug {
     fn cover_row(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.up_links[i];
+                i = self.up(i);
             }
             else {
                 self.commit(i);
@@ -312,9 +699,9 @@ C: Eq + Copy + std::fmt::Debug {
     fn uncover_row(&mut self, row_node: usize) {
         let mut i = row_node - 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.down_links[i];
+                i = self.down(i);
             }
             else {
                 self.uncommit(i);
@@ -323,105 +710,255 @@ C: Eq + Copy + std::fmt::Debug {
         }
     }
 
-    fn get_item(&self, row_node: usize) -> Item<P, S, C> {
-        let header = self.header_links[row_node];
-        match self.names[header] {
-            Some(Item::Primary(item)) => Item::Primary(item),
-            Some(Item::Secondary(item)) => {
-                if let Some(color) = self.color_names[self.colors[header]] {
-                    Item::ColoredSecondary(item, color)
-                }
-                else {
-                    Item::Secondary(item)
-                }
-            },
-            _ => panic!("None or ColoredSecondary in table headers. Something went horribly wrong.")
+    // Only identifies the problem while no columns are covered.
+    fn fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add_debug(&self.columns);
+        for header in &self.headers {
+            fingerprint.add(header.left.to_usize());
+            fingerprint.add(header.right.to_usize());
+            fingerprint.add(header.length.to_usize());
         }
+        for i in 0..self.nodes.len() {
+            fingerprint.add(self.up(i));
+            fingerprint.add(self.down(i));
+            fingerprint.add(self.header(i));
+            fingerprint.add(self.color(i));
+        }
+        fingerprint.finish()
+    }
+
+    // Whether row_node can be the current row of the column at a level of the search:
+    // a node of the column, or the column itself once its rows are exhausted.
+    fn is_level(&self, column: usize, row_node: usize) -> bool {
+        column > 0 && column < self.headers.len()
+            && (row_node == column || (row_node >= self.headers.len() && row_node < self.nodes.len() && self.header(row_node) == column))
     }
 
     fn get_row(&self, row_node: usize) -> Vec<Item<P, S, C>> {
-        let mut row = vec![self.get_item(row_node)];
+        let header = self.header(row_node);
+        let mut nodes = vec![(header, self.color(header))];
         let mut k = row_node + 1;
         while k != row_node {
-            let header = self.header_links[k];
+            let header = self.header(k);
             if header == 0 {
-                k = self.up_links[k];
+                k = self.up(k);
             }
             else {
-                let item = self.get_item(k);
-                row.push(item);
+                nodes.push((header, self.color(header)));
                 k += 1;
             }
         }
     
-        row
+        self.columns.items(&nodes)
     }
 
     fn get_colors(&self) -> Vec<(S, Option<C>)> {
-        let mut assignments = Vec::new();
-        for (i, name) in self.names.iter().enumerate() {
-            if let Some(Item::Secondary(item)) = *name {
-                let color = self.color_names[self.colors[i]]; 
-                assignments.push((item, color));
-            }
-        }
+        self.columns.assignments(|column| self.color(column))
+    }
 
-        assignments
+    // The items that are not covered with the number of options they have left,
+    // and the options that can still be chosen, see inspect.rs.
+    pub fn dump(&self) -> String {
+        self.columns.dump(self, |node| self.color(node), |_| None)
+    }
+
+    // Panics if a link does not point back or a length is off.
+    pub fn check_links(&self) {
+        inspect::check_links(self);
     }
 }
 
-fn min_length_column<P, S, C>(table: &DLXCTable<P, S, C>) -> Option<usize> 
+impl<P, S, C, I> Links for DLXCTable<P, S, C, I>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    let mut i = table.right_links[0];
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    fn header_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn left(&self, header: usize) -> usize {
+        DLXCTable::left(self, header)
+    }
+
+    fn right(&self, header: usize) -> usize {
+        DLXCTable::right(self, header)
+    }
+
+    fn length(&self, header: usize) -> usize {
+        DLXCTable::length(self, header)
+    }
+
+    fn up(&self, node: usize) -> usize {
+        DLXCTable::up(self, node)
+    }
+
+    fn down(&self, node: usize) -> usize {
+        DLXCTable::down(self, node)
+    }
+
+    fn header(&self, node: usize) -> usize {
+        DLXCTable::header(self, node)
+    }
+
+    fn label(&self, column: usize) -> String {
+        self.columns.label(column)
+    }
+}
+
+pub fn min_length_column<P, S, C, I>(table: &DLXCTable<P, S, C, I>) -> Option<usize> 
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    let mut i = table.right(0);
     let mut size = usize::MAX;
     let mut column = None;
     while i != 0 {
-        if table.lengths[i] < size {
+        if table.length(i) < size {
             column = Some(i);
-            size = table.lengths[i];
+            size = table.length(i);
         }
-        i = table.right_links[i];
+        i = table.right(i);
     }
 
     column
 }
 
-fn min_length_column_randomized<P, S, C>(table: &DLXCTable<P, S, C>) -> Option<usize>
+pub fn min_length_column_randomized<P, S, C, I>(table: &DLXCTable<P, S, C, I>) -> Option<usize>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    let mut i = table.right_links[0];
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    let mut i = table.right(0);
     let mut size = usize::MAX;
     let mut columns = Vec::new();
     while i != 0 {
-        if table.lengths[i] < size {
+        if table.length(i) < size {
             columns = Vec::from([i]);
-            size = table.lengths[i];
+            size = table.length(i);
         }
-        else if table.lengths[i] == size {
+        else if table.length(i) == size {
             columns.push(i);
         }
-        i = table.right_links[i];
+        i = table.right(i);
     }
-    columns.choose(&mut rand::thread_rng()).cloned()
+    random::with_rng(|rng| columns.choose(rng).cloned())
 }
 
-type Solution<P, S, C> = (Vec<Vec<Item<P, S, C>>>, Vec<(S, Option<C>)>);
+pub type Solution<P, S, C> = (Vec<Vec<Item<P, S, C>>>, Vec<(S, Option<C>)>);
 
-fn search<P, S, C>(table: &mut DLXCTable<P, S, C>, choose_column: fn(&DLXCTable<P, S, C>) -> Option<usize>, 
-                   partial_solution: &mut Vec<usize>) -> Option<Solution<P, S, C>>
+// The colors that a solution gives its secondary items, for decoding the solutions of XCC encodings.
+// Items that the options give several colors have several of them.
+#[derive(Clone,PartialEq,Eq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Assignment<S, C>
+where
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    colors: Vec<(S, Option<C>)>
+}
+
+impl<S, C> Assignment<S, C>
+where
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub fn new(colors: Vec<(S, Option<C>)>) -> Self {
+        Assignment { colors }
+    }
+
+    // The first color of the item, None if it is uncolored or not part of the assignment.
+    pub fn color(&self, item: S) -> Option<C> {
+        self.colors
+            .iter()
+            .find(|&&(other, _)| other == item)
+            .and_then(|&(_, color)| color)
+    }
+
+    pub fn colors_of(&self, item: S) -> Vec<C> {
+        self.colors
+            .iter()
+            .filter(|&&(other, _)| other == item)
+            .filter_map(|&(_, color)| color)
+            .collect()
+    }
+
+    pub fn is_colored(&self, item: S) -> bool {
+        self.color(item).is_some()
+    }
+
+    pub fn colored(&self) -> impl Iterator<Item = (S, C)> + '_ {
+        self.colors
+            .iter()
+            .filter_map(|&(item, color)| color.map(|color| (item, color)))
+    }
+
+    pub fn uncolored(&self) -> impl Iterator<Item = S> + '_ {
+        self.colors
+            .iter()
+            .filter(|(_, color)| color.is_none())
+            .map(|&(item, _)| item)
+    }
+
+    pub fn items_with(&self, color: C) -> impl Iterator<Item = S> + '_ {
+        self.colored()
+            .filter(move |&(_, other)| other == color)
+            .map(|(item, _)| item)
+    }
+
+    // The items of every color, with the colors in the order they first appear.
+    pub fn by_color(&self) -> Vec<(C, Vec<S>)> {
+        let mut groups: Vec<(C, Vec<S>)> = Vec::new();
+        for (item, color) in self.colored() {
+            match groups.iter_mut().find(|(other, _)| *other == color) {
+                Some((_, items)) => items.push(item),
+                None => groups.push((color, vec![item]))
+            }
+        }
+        groups
+    }
+
+    pub fn without_uncolored(mut self) -> Self {
+        self.colors.retain(|(_, color)| color.is_some());
+        self
+    }
+
+    pub fn as_slice(&self) -> &[(S, Option<C>)] {
+        &self.colors
+    }
+
+    pub fn into_vec(self) -> Vec<(S, Option<C>)> {
+        self.colors
+    }
+}
+
+pub fn assignment<P, S, C>(solution: &Solution<P, S, C>) -> Assignment<S, C>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
 C: Eq + Copy + std::fmt::Debug {
+    Assignment::new(solution.1.clone())
+}
+
+fn search<P, S, C, I>(table: &mut DLXCTable<P, S, C, I>, choose_column: fn(&DLXCTable<P, S, C, I>) -> Option<usize>, 
+                   partial_solution: &mut Vec<usize>) -> Option<Solution<P, S, C>>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
     if let Some(column) = choose_column(table) {
         table.cover(column);
 
-        let mut row_node = table.down_links[column];
+        let mut row_node = table.down(column);
         while row_node != column {
             table.cover_row(row_node);
 
@@ -437,7 +974,7 @@ C: Eq + Copy + std::fmt::Debug {
             
             table.uncover_row(row_node);
 
-            row_node = table.down_links[row_node];
+            row_node = table.down(row_node);
         }
 
         table.uncover(column);
@@ -468,36 +1005,73 @@ struct LevelState {
     row_node: usize
 }
 
-pub struct DLXCIter<P, S, C> 
+pub struct DLXCIter<P, S, C, I = u32> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    table: DLXCTable<P, S, C>,
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    table: DLXCTable<P, S, C, I>,
     stack: Vec<LevelState>,
     state: State,
-    choose_column: fn(&DLXCTable<P, S, C>) -> Option<usize>
+    choose_column: fn(&DLXCTable<P, S, C, I>) -> Option<usize>,
+    omit_uncolored: bool,
+    fingerprint: u64,
+    trace: Option<Trace>
 }
 
-impl<P, S, C> DLXCIter<P, S, C>
+impl<P, S, C, I> DLXCIter<P, S, C, I>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    pub fn new(sets: Vec<Vec<Item<P, S, C>>>, choose_column: fn(&DLXCTable<P, S, C>) -> Option<usize>, 
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    pub fn new(sets: Vec<Vec<Item<P, S, C>>>, choose_column: fn(&DLXCTable<P, S, C, I>) -> Option<usize>, 
                primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
         let table = DLXCTable::new(sets, primary_items, secondary_items, colors);
+        let fingerprint = table.fingerprint();
         let stack = Vec::new();
         let state = State::CoveringColumn;
-        let mut this = DLXCIter { table, stack, state, choose_column };
+        let mut this = DLXCIter { table, stack, state, choose_column, omit_uncolored: false, fingerprint, trace: None };
         this.cover_column();
         this
     }
 
+    // Leaves the secondary items that no chosen option colors out of the colors of solutions.
+    pub fn omit_uncolored(mut self) -> Self {
+        self.omit_uncolored = true;
+        self
+    }
+
+    // Records the search tree from here on, see trace.rs.
+    // The levels that are already on the stack are recorded as if they were just chosen.
+    pub fn with_trace(mut self) -> Self {
+        let mut trace = Trace::new();
+        for (i, level) in self.stack.iter().enumerate() {
+            trace.choose(&self.table.columns.names[level.column].unwrap(), self.table.length(level.column));
+            if i + 1 < self.stack.len() || last_row_covered(self.state) {
+                trace.try_option(&self.table.get_row(level.row_node));
+            }
+        }
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
     fn cover_column(&mut self) {
         if let Some(column) = (self.choose_column)(&self.table) {
+            if let Some(trace) = &mut self.trace {
+                trace.choose(&self.table.columns.names[column].unwrap(), self.table.length(column));
+            }
             self.table.cover(column);
-            let row_node = self.table.down_links[column];
+            let row_node = self.table.down(column);
             self.stack.push(LevelState { column, row_node });
 
             if row_node == column {
@@ -513,6 +1087,9 @@ C: Eq + Copy + std::fmt::Debug {
         else {
             // all columns are covered
             self.state = State::FoundSolution;
+            if let Some(trace) = &mut self.trace {
+                trace.solution();
+            }
         }
     }
 
@@ -520,6 +1097,9 @@ C: Eq + Copy + std::fmt::Debug {
         // cover the current row and set up for the next level 
         let level = self.stack.last().unwrap();
         self.table.cover_row(level.row_node);
+        if let Some(trace) = &mut self.trace {
+            trace.try_option(&self.table.get_row(level.row_node));
+        }
         self.state = State::CoveringColumn;
     }
 
@@ -534,7 +1114,10 @@ C: Eq + Copy + std::fmt::Debug {
     fn backtrack_row(&mut self) {
         let mut level = self.stack.pop().unwrap();
         self.table.uncover_row(level.row_node);
-        level.row_node = self.table.down_links[level.row_node];
+        if let Some(trace) = &mut self.trace {
+            trace.leave();
+        }
+        level.row_node = self.table.down(level.row_node);
         self.stack.push(level);
         if level.row_node == level.column {
             // we tried the last row
@@ -547,6 +1130,58 @@ C: Eq + Copy + std::fmt::Debug {
         }
     }
 
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            fingerprint: self.fingerprint,
+            state: self.state,
+            levels: self.stack
+                .iter()
+                .map(|level| (level.column, level.row_node))
+                .collect(),
+            costs: None
+        }
+    }
+
+    // Continues from a checkpoint of the same problem instead of where this search stands.
+    pub fn restore(mut self, checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        check_fingerprint(checkpoint, self.fingerprint)?;
+        if checkpoint.costs.is_some() {
+            return Err(CheckpointError::Mismatch("the checkpoint was taken from min_cost_dlxc".to_string()))
+        }
+        check_levels(checkpoint, |column, row_node| self.table.is_level(column, row_node))?;
+
+        // undo the search so far, the rows below the last level are all covered
+        let mut row_covered = last_row_covered(self.state);
+        while let Some(level) = self.stack.pop() {
+            if row_covered {
+                self.table.uncover_row(level.row_node);
+            }
+            self.table.uncover(level.column);
+            row_covered = true;
+        }
+
+        for (i, &(column, row_node)) in checkpoint.levels.iter().enumerate() {
+            self.table.cover(column);
+            if i + 1 < checkpoint.levels.len() || last_row_covered(checkpoint.state) {
+                self.table.cover_row(row_node);
+            }
+            self.stack.push(LevelState { column, row_node });
+        }
+        self.state = checkpoint.state;
+        Ok(self)
+    }
+
+    // The table as the search left it, with the columns and rows of its levels covered.
+    pub fn table(&self) -> &DLXCTable<P, S, C, I> {
+        &self.table
+    }
+
+    // True when the whole search tree has been explored,
+    // as opposed to a search that was stopped by its time limit.
+    pub fn is_exhausted(&self) -> bool {
+        self.stack.is_empty()
+    }
+
     pub fn get_solution(&self) -> Option<(Vec<Vec<Item<P, S, C>>>, Vec<(S, Option<C>)>)> {
         if let State::FoundSolution = self.state {
             let solution = self.stack
@@ -554,7 +1189,11 @@ C: Eq + Copy + std::fmt::Debug {
                 .map(|level| level.row_node)
                 .map(|i| self.table.get_row(i))
                 .collect();
-            Some((solution, self.table.get_colors()))
+            let mut colors = self.table.get_colors();
+            if self.omit_uncolored {
+                colors.retain(|(_, color)| color.is_some());
+            }
+            Some((solution, colors))
         }
         else {
             None
@@ -562,12 +1201,13 @@ C: Eq + Copy + std::fmt::Debug {
     }
 }
 
-impl<P, S, C> DLXCIter<P, S, C> 
+impl<P, S, C, I> DLXCIter<P, S, C, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    fn first_solution(&mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
+    pub fn first_solution(&mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
         let start = Instant::now();
         while !self.stack.is_empty() {
             if start.elapsed() >= time_limit {
@@ -594,7 +1234,7 @@ C: Eq + Copy + std::fmt::Debug {
         None
     }
 
-    fn all_solutions(&mut self, time_limit: Duration) -> Vec<Solution<P, S, C>> {
+    pub fn all_solutions(&mut self, time_limit: Duration) -> Vec<Solution<P, S, C>> {
         let start = Instant::now();
         let mut solutions = Vec::new();
         while !self.stack.is_empty() {
@@ -603,10 +1243,10 @@ C: Eq + Copy + std::fmt::Debug {
             }
             match self.state {
                 State::FoundSolution => {
-                    self.state = State::BacktrackingRow;
                     if let Some(solution) = self.get_solution() {
                         solutions.push(solution)
                     }
+                    self.state = State::BacktrackingRow;
                 },
                 State::CoveringColumn => {
                     self.cover_column();
@@ -624,13 +1264,43 @@ C: Eq + Copy + std::fmt::Debug {
         }
         solutions
     }
+
+    pub fn count_solutions(&mut self, time_limit: Duration) -> usize {
+        let start = Instant::now();
+        let mut count = 0;
+        while !self.stack.is_empty() {
+            if start.elapsed() >= time_limit {
+                break;
+            }
+            match self.state {
+                State::FoundSolution => {
+                    self.state = State::BacktrackingRow;
+                    count += 1;
+                },
+                State::CoveringColumn => {
+                    self.cover_column();
+                },
+                State::CoveringRow => {
+                    self.cover_row();
+                },
+                State::BacktrackingRow => {
+                    self.backtrack_row();
+                }
+                State::BacktrackingColumn => {
+                    self.backtrack_column();
+                },
+            }
+        }
+        count
+    }
 }
 
-impl<P, S, C> Iterator for DLXCIter<P, S, C> 
+impl<P, S, C, I> Iterator for DLXCIter<P, S, C, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
+C: Eq + Copy + std::fmt::Debug,
+I: Index {
     type Item = (State, Option<Solution<P, S, C>>);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -682,7 +1352,7 @@ where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
 C: Eq + Copy + std::fmt::Debug {
-    DLXCIter::new(sets, min_length_column, primary_items, secondary_items, colors)
+    dlxc_iter(sets, primary_items, secondary_items, colors)
         .first_solution(time_limit)
 }
 
@@ -692,7 +1362,95 @@ where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
 C: Eq + Copy + std::fmt::Debug {
-    DLXCIter::new(sets, min_length_column_randomized, primary_items, secondary_items, colors)
+    dlxc_iter_randomized(sets, primary_items, secondary_items, colors)
         .first_solution(time_limit)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::min_cost_dlxc::min_cost_dlxc;
+    use crate::verify::Problem;
+    use crate::verify::verify_dlxc_solution;
+    use super::Item;
+    use super::dlxc_iter;
+
+    // Options with colored primary items and items with several colors.
+    // Every option covers a primary item, since the solvers never choose the others.
+    fn random_sets(rng: &mut StdRng, primary_count: usize, secondary_count: usize) -> Vec<Vec<Item<usize, usize, usize>>> {
+        let mut sets = Vec::new();
+        for _ in 0..rng.gen_range(1..12) {
+            let mut set = Vec::new();
+            for item in 0..primary_count {
+                if rng.gen_bool(0.4) {
+                    let color = rng.gen_range(0..3);
+                    match rng.gen_range(0..4) {
+                        0 => set.push(Item::Primary(item)),
+                        1 => set.push(Item::ColoredPrimary(item, color)),
+                        2 => {
+                            set.push(Item::ColoredPrimary(item, color));
+                            set.push(Item::ColoredPrimary(item, (color + 1) % 3));
+                        },
+                        _ => set.push(Item::PrimaryColor(item, color))
+                    }
+                }
+            }
+            for item in 0..secondary_count {
+                if rng.gen_bool(0.4) {
+                    let color = rng.gen_range(0..3);
+                    match rng.gen_range(0..3) {
+                        0 => set.push(Item::Secondary(item)),
+                        1 => set.push(Item::ColoredSecondary(item, color)),
+                        _ => {
+                            set.push(Item::ColoredSecondary(item, color));
+                            set.push(Item::ColoredSecondary(item, (color + 1) % 3));
+                        }
+                    }
+                }
+            }
+            if set.iter().any(|item| matches!(item, Item::Primary(_) | Item::ColoredPrimary(_, _))) {
+                sets.push(set);
+            }
+        }
+        sets
+    }
+
+    #[test]
+    fn colored_primaries_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(36);
+        for _ in 0..1000 {
+            let primary_items: Vec<usize> = (0..rng.gen_range(1..6)).collect();
+            let secondary_items: Vec<usize> = (0..rng.gen_range(0..3)).collect();
+            let sets = random_sets(&mut rng, primary_items.len(), secondary_items.len());
+            let costs: Vec<usize> = sets.iter().map(|_| rng.gen_range(0..10)).collect();
+
+            // the solutions as sets of options, found by trying every subset
+            let problem = Problem::from_dlxc(&sets, &primary_items, &secondary_items);
+            let expected: Vec<Vec<usize>> = (0..1usize << sets.len())
+                .map(|mask| (0..sets.len()).filter(|&i| mask & (1 << i) != 0).collect::<Vec<_>>())
+                .filter(|options| problem.verify_options(options, None, None).is_valid())
+                .collect();
+
+            let solutions = dlxc_iter(sets.clone(), primary_items.clone(), secondary_items.clone(), vec![0, 1, 2])
+                .all_solutions(Duration::MAX);
+            assert_eq!(solutions.len(), expected.len(), "{:?}", sets);
+            for solution in solutions.iter() {
+                let report = verify_dlxc_solution(&sets, &primary_items, &secondary_items, solution);
+                assert!(report.is_valid(), "{} in {:?}", report, sets);
+            }
+
+            // min_cost_dlxc shares the columns with dlxc
+            let costed_sets = sets.iter().cloned().zip(costs.iter().cloned()).collect();
+            let best = min_cost_dlxc(costed_sets, primary_items.clone(), secondary_items.clone(), vec![0, 1, 2], Duration::MAX);
+            let expected_best = expected
+                .iter()
+                .map(|options| options.iter().map(|&i| costs[i]).sum::<usize>())
+                .min();
+            assert_eq!(best.map(|solution| solution.cost), expected_best, "{:?} {:?}", sets, costs);
+        }
+    }
+}
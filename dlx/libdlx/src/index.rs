@@ -0,0 +1,49 @@
+// Unsigned integer types that the tables use for their links and counters.
+// Narrower types make every node smaller, so more of a big table fits into the cache,
+// but they limit the number of nodes a table can have.
+pub trait Index: Copy + Eq + Ord + std::fmt::Debug + Default {
+    const MAX: Self;
+
+    fn from_usize(value: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_index {
+    ($($t:ty),*) => {
+        $(
+            impl Index for $t {
+                const MAX: Self = <$t>::MAX;
+
+                #[inline]
+                fn from_usize(value: usize) -> Self {
+                    debug_assert!(value <= <$t>::MAX as usize, "{} does not fit into {}", value, stringify!($t));
+                    value as $t
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_index!(u16, u32, u64, usize);
+
+// The item headers, kept together because choosing a column
+// walks the right links and reads the lengths.
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Default)]
+pub(crate) struct Header<I: Index> {
+    pub(crate) left: I,
+    pub(crate) right: I,
+    pub(crate) length: I
+}
+
+// Panics when a table with node_count nodes cannot be addressed with I.
+// The largest value is kept free, since the colored tables use it as a marker.
+pub(crate) fn check_capacity<I: Index>(node_count: usize) {
+    if node_count >= I::MAX.to_usize() {
+        panic!("{} nodes do not fit into {:?}-sized indices", node_count, I::MAX);
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+// Looking into the tables while a search runs on them. The Debug output of a table
+// is its link arrays, so the tables also render the part of the matrix that is still
+// active with their dump methods, and check that their links are consistent.
+// Debug builds check the links after every cover and uncover. That takes as long as the table
+// is big, so the no-check-links feature turns it off for debug builds of large searches.
+
+pub(crate) const CHECK_LINKS: bool = cfg!(all(debug_assertions, not(feature = "no-check-links")));
+
+// The links that all tables share, over the indices of their headers and nodes.
+pub(crate) trait Links {
+    fn header_count(&self) -> usize;
+    fn node_count(&self) -> usize;
+    fn left(&self, header: usize) -> usize;
+    fn right(&self, header: usize) -> usize;
+    fn length(&self, header: usize) -> usize;
+    fn up(&self, node: usize) -> usize;
+    fn down(&self, node: usize) -> usize;
+    fn header(&self, node: usize) -> usize;
+    // the item of the column, for messages and dumps
+    fn label(&self, column: usize) -> String;
+}
+
+// Panics at the first link that does not point back or the first length that does not match its column.
+pub(crate) fn check_links(table: &impl Links) {
+    let headers = table.header_count();
+    let nodes = table.node_count();
+
+    // the list of the primary columns
+    let mut i = 0;
+    let mut steps = 0;
+    loop {
+        let next = table.right(i);
+        if next >= headers {
+            panic!("The right link of {} points to {}, which is not a column.", label(table, i), next);
+        }
+        if table.left(next) != i {
+            panic!("The right link of {} points to {}, but its left link does not point back.",
+                   label(table, i), label(table, next));
+        }
+        if next == 0 {
+            break
+        }
+        i = next;
+        steps += 1;
+        if steps > headers {
+            panic!("The list of primary items does not return to the root.");
+        }
+    }
+
+    // a covered column keeps its links, but its neighbours skip it
+    for column in 1..headers {
+        if table.left(column) >= headers || table.right(column) >= headers {
+            panic!("The links of {} do not point to columns.", label(table, column));
+        }
+        let from_left = table.right(table.left(column)) == column;
+        let from_right = table.left(table.right(column)) == column;
+        if from_left != from_right {
+            panic!("Only one of the neighbours of {} links to it.", label(table, column));
+        }
+    }
+
+    for column in 1..headers {
+        let mut count = 0;
+        let mut i = column;
+        loop {
+            let next = table.down(i);
+            if next >= nodes {
+                panic!("The down link of node {} in {} points to {}, which is not a node.",
+                       i, label(table, column), next);
+            }
+            if table.up(next) != i {
+                panic!("The down link of node {} in {} points to node {}, but its up link points to node {}.",
+                       i, label(table, column), next, table.up(next));
+            }
+            if next == column {
+                break
+            }
+            if table.header(next) != column {
+                panic!("Node {} is linked into {}, but its header is {}.", next, label(table, column), table.header(next));
+            }
+            count += 1;
+            i = next;
+            if count > nodes {
+                panic!("The list of {} does not return to its header.", label(table, column));
+            }
+        }
+        if count != table.length(column) {
+            panic!("{} has {} options linked, but its length is {}.", label(table, column), count, table.length(column));
+        }
+    }
+}
+
+fn label(table: &impl Links, column: usize) -> String {
+    if column == 0 {
+        String::from("the root")
+    }
+    else {
+        table.label(column)
+    }
+}
+
+// The primary columns that are not covered, in the order of their list.
+pub(crate) fn active_primary(table: &impl Links) -> Vec<usize> {
+    let mut columns = Vec::new();
+    let mut i = table.right(0);
+    while i != 0 {
+        columns.push(i);
+        i = table.right(i);
+    }
+    columns
+}
+
+// The other columns that their neighbours still link to. A secondary column
+// that is alone in its list cannot be told apart from a covered one and counts as active.
+pub(crate) fn active_secondary(table: &impl Links) -> Vec<usize> {
+    let mut primary = vec![false; table.header_count()];
+    for column in active_primary(table) {
+        primary[column] = true;
+    }
+    (1..table.header_count())
+        .filter(|&column| !primary[column] && table.right(table.left(column)) == column)
+        .collect()
+}
+
+// The first node of every option whose items are all among the columns, in the order
+// of the table. The chosen options stay linked into their own columns, but those are covered.
+pub(crate) fn active_options(table: &impl Links, columns: &[usize]) -> Vec<usize> {
+    let mut active = vec![false; table.header_count()];
+    for &column in columns {
+        active[column] = true;
+    }
+
+    let mut options = Vec::new();
+    for &column in columns {
+        let mut i = table.down(column);
+        while i != column {
+            // the node after the spacer in front of the option
+            let mut first = i;
+            let mut linked = true;
+            let mut k = i;
+            loop {
+                k = if table.header(k + 1) == 0 {
+                    table.up(k + 1)
+                }
+                else {
+                    k + 1
+                };
+                if k == i {
+                    break
+                }
+                first = first.min(k);
+                linked &= active[table.header(k)];
+            }
+            if linked {
+                options.push(first);
+            }
+            i = table.down(i);
+        }
+    }
+    options.sort_unstable();
+    options.dedup();
+    options
+}
+
+// Lays out a dump: the items with the number of options they have left, then the options.
+pub(crate) fn render(primary: Vec<(String, usize)>, secondary: Vec<(String, usize)>, options: Vec<String>) -> String {
+    let mut dump = String::new();
+    writeln!(dump, "{} primary items:", primary.len()).unwrap();
+    for (item, length) in primary {
+        writeln!(dump, "  {} ({})", item, length).unwrap();
+    }
+    writeln!(dump, "{} secondary items:", secondary.len()).unwrap();
+    for (item, length) in secondary {
+        writeln!(dump, "  {} ({})", item, length).unwrap();
+    }
+    writeln!(dump, "{} options:", options.len()).unwrap();
+    for option in options {
+        writeln!(dump, "  {}", option).unwrap();
+    }
+    dump
+}
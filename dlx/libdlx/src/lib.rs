@@ -3,3 +3,16 @@ extern crate core;
 pub mod dlx;
 pub mod dlxc;
 pub mod min_cost_dlxc;
+pub mod verify;
+pub mod diagnose;
+pub mod dancing_cells;
+pub mod index;
+pub mod cost;
+pub mod checkpoint;
+pub mod symmetry;
+pub mod trace;
+pub mod inspect;
+pub mod preprocess;
+pub mod random;
+#[cfg(feature = "serde")]
+pub mod serial;
@@ -1,168 +1,220 @@
 use std::time::Instant;
 use std::time::Duration;
+use std::iter::successors;
+use std::cmp::Ordering;
+use crate::index::Index;
+use crate::index::Header;
+use crate::index::check_capacity;
+use crate::verify::Problem;
+use crate::cost::Cost;
+use crate::dlxc::Columns;
+use crate::checkpoint::Checkpoint;
+use crate::checkpoint::CheckpointError;
+use crate::checkpoint::check_levels;
+use crate::checkpoint::SearchCosts;
+use crate::checkpoint::Fingerprint;
+use crate::checkpoint::check_fingerprint;
+use crate::checkpoint::last_row_covered;
+use crate::trace::Trace;
+use crate::inspect;
+use crate::inspect::Links;
 
+pub use crate::dlxc::Item;
+pub use crate::dlxc::Assignment;
+
+pub type CostedSet<P, S, C, W = usize> = (Vec<Item<P, S, C>>, W);
+
+// Costs are not bounded by the table size, so they are stored as given.
 #[derive(Clone,Copy,PartialEq,Eq,Debug)]
-pub enum Item<P, S, C> 
-where
-P: Eq + Copy + std::fmt::Debug,
-S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug { 
-    Primary(P),
-    Secondary(S),
-    ColoredSecondary(S, C),
+struct Node<I: Index, W> {
+    up: I,
+    down: I,
+    header: I,
+    color: I,
+    cost: W
 }
 
-#[derive(Clone,PartialEq,Eq,Debug)]
-pub struct DLXCTable<P, S, C> 
-where 
-P: Eq + Copy + std::fmt::Debug,
-S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug  {
-    names: Vec<Option<Item<P, S, C>>>,
-    color_names: Vec<Option<C>>,
-    left_links: Vec<usize>,
-    right_links: Vec<usize>,
-    lengths: Vec<usize>,
-    up_links: Vec<usize>,
-    down_links: Vec<usize>,
-    header_links: Vec<usize>,
-    colors: Vec<usize>,
-    costs: Vec<usize>
+// Solutions have to cost less than the limit, or may cost as much as it if it is inclusive.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+struct Limit<W> {
+    cost: W,
+    inclusive: bool
 }
 
-fn has_name<P, S, C>(item: Item<P, S, C>, name: Option<Item<P, S, C>>) -> bool
-where
+impl<W: Cost> Limit<W> {
+    fn below(cost: W) -> Self {
+        Limit { cost, inclusive: false }
+    }
+
+    fn at_most(cost: W) -> Self {
+        Limit { cost, inclusive: true }
+    }
+
+    #[inline]
+    fn admits(self, cost: W) -> bool {
+        if self.inclusive {
+            cost <= self.cost
+        }
+        else {
+            cost < self.cost
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.cost < other.cost || (self.cost == other.cost && !self.inclusive) {
+            self
+        }
+        else {
+            other
+        }
+    }
+}
+
+// Covering only unlinks the options that can still lead to a solution within the limit,
+// given what the partial solution has spent. The options are sorted by cost,
+// so these form a prefix of every column.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub struct Threshold<W> {
+    spent: W,
+    limit: Limit<W>
+}
+
+impl<W: Cost> Threshold<W> {
+    fn unbounded() -> Self {
+        Threshold { spent: W::zero(), limit: Limit::below(W::max_value()) }
+    }
+
+    #[inline]
+    pub fn admits(self, cost: W) -> bool {
+        self.limit.admits(self.spent.plus(cost))
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct DLXCTable<P, S, C, W = usize, I = u32> 
+where 
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    match (name, item) {
-        (Some(Item::Primary(n)), Item::Primary(i)) => i == n,
-        (Some(Item::Secondary(n)), Item::Secondary(i)) => i == n,
-        (Some(Item::Secondary(n)), Item::ColoredSecondary(i, _)) => i == n,
-        _ => false
-    }
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    columns: Columns<P, S, C>,
+    headers: Vec<Header<I>>,
+    nodes: Vec<Node<I, W>>
 }
 
-fn add_node<P, S, C>(table: &mut DLXCTable<P, S, C>, index: usize, item: Item<P, S, C>, cost: usize) 
+fn add_node<P, S, C, W, I>(table: &mut DLXCTable<P, S, C, W, I>, index: usize, header_index: usize, color: usize, cost: W) 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    let header_index = table.names
-        .iter()
-        .position(|&name| has_name(item, name))
-        .expect(&format!("{:?} not present", item));
-    table.lengths[header_index] += 1;
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    table.inc_length(header_index);
     
     // node setup
-    table.up_links[index] = table.up_links[header_index];
-    table.down_links[index] = header_index;
-    table.header_links[index] = header_index;
+    table.set_up(index, table.up(header_index));
+    table.set_down(index, header_index);
+    table.set_header(index, header_index);
 
     // uplink setup
-    table.down_links[table.up_links[index]] = index;
+    table.set_down(table.up(index), index);
     
     // header setup
-    if table.down_links[header_index] == header_index {
-        table.down_links[header_index] = index;
+    if table.down(header_index) == header_index {
+        table.set_down(header_index, index);
     }
-    table.up_links[header_index] = index;
+    table.set_up(header_index, index);
 
     // cost setup
-    table.costs[index] = cost;
+    table.set_cost(index, cost);
 
-    if let Item::ColoredSecondary(_, c) = item {
-        let color_index = table.color_names
-            .iter()
-            .position(|color| color.is_some() && c == color.unwrap())
-            .unwrap();
-        table.colors[index] = color_index;
-    }
+    table.set_color(index, color);
 }
 
-impl<P, S, C> DLXCTable<P, S, C> 
+impl<P, S, C, W, I> DLXCTable<P, S, C, W, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    pub fn new(mut sets: Vec<(Vec<Item<P, S, C>>, usize)>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
-        let primary_count = primary_items.len();
-        let mut names = Vec::with_capacity(1 + primary_items.len() + secondary_items.len());
-        names.push(None);
-        for item in primary_items {
-            names.push(Some(Item::Primary(item)));
-        }
-
-        for item in secondary_items {
-            names.push(Some(Item::Secondary(item)));
-        }
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    pub fn new(mut sets: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+        let mut columns = Columns::new(sets.iter().map(|(set, _)| set), primary_items, secondary_items, colors);
+        let primary_count = columns.primary_count;
+        let names_count = columns.len();
 
-        let names_count = names.len();
-        
-        
-        let mut color_names = Vec::with_capacity(colors.len() + 1);
-        color_names.push(None);
-        for color in colors {
-            color_names.push(Some(color));
+        // the search needs costs that can be compared and do not decrease when added up
+        let invalid_cost = sets
+            .iter()
+            .map(|(_, cost)| *cost)
+            .find(|cost| matches!(cost.partial_cmp(&W::zero()), None | Some(Ordering::Less)));
+        if let Some(cost) = invalid_cost {
+            panic!("Option costs must be comparable and not negative, but one is {:?}.", cost);
         }
+        sets.sort_by(|(_, cost1), (_, cost2)| cost1.partial_cmp(cost2).expect("Option costs must be comparable."));
+        let options: Vec<_> = sets
+            .iter()
+            .map(|(set, cost)| (columns.nodes(set), *cost))
+            .collect();
 
-        let node_count = 1 + names_count + sets.len() + sets
+        let node_count = 1 + names_count + options.len() + options
             .iter()
-            .map(|(set, _)| set
-                .iter()
-                .map(|_| 1)
-                .sum::<usize>())
+            .map(|(option, _)| option.len())
             .sum::<usize>();
 
+        check_capacity::<I>(node_count);
+        let empty_node = Node {
+            up: I::default(),
+            down: I::default(),
+            header: I::default(),
+            color: I::default(),
+            cost: W::zero()
+        };
         let mut table = DLXCTable {
-            names,
-            color_names,
-            left_links: vec![0; names_count],
-            right_links: vec![0; names_count],
-            lengths: vec![0; names_count],
-            up_links: vec![0; node_count],
-            down_links: vec![0; node_count],
-            header_links: vec![0; node_count],
-            colors: vec![0; node_count],
-            costs: vec![0; node_count]
+            columns,
+            headers: vec![Header::default(); names_count],
+            nodes: vec![empty_node; node_count]
         };
 
         // header setup
-        table.left_links[0] = primary_count;
+        table.set_left(0, primary_count);
         for i in 0..primary_count {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
+            table.set_left(i+1, i);
+            table.set_right(i, i+1);
+            table.set_up(i+1, i+1);
+            table.set_down(i+1, i+1);
         }
 
-        table.left_links[primary_count + 1] = names_count - 1;
+        // the secondary items form their own circular list, if there are any
+        if names_count > primary_count + 1 {
+            table.set_left(primary_count + 1, names_count - 1);
 
-        table.up_links[primary_count + 1] = primary_count + 1;
-        table.down_links[primary_count + 1] = primary_count + 1;
-        for i in primary_count+1..names_count-1 {
-            table.left_links[i+1] = i;
-            table.right_links[i] = i+1;
-            table.up_links[i+1] = i+1;
-            table.down_links[i+1] = i+1;
-        }
+            table.set_up(primary_count + 1, primary_count + 1);
+            table.set_down(primary_count + 1, primary_count + 1);
+            for i in primary_count+1..names_count-1 {
+                table.set_left(i+1, i);
+                table.set_right(i, i+1);
+                table.set_up(i+1, i+1);
+                table.set_down(i+1, i+1);
+            }
 
-        table.right_links[names_count - 1] = primary_count + 1;
+            table.set_right(names_count - 1, primary_count + 1);
+        }
 
         let mut prev_spacer = names_count;
         
         let mut current_index = names_count + 1;
-        sets.sort_by(|(_, cost1), (_, cost2)| cost1.cmp(cost2));
-        for (set, cost) in sets {
-            if set.len() > 0 {
-                for item in set {
-                    add_node(&mut table, current_index, item, cost);
+        for (option, cost) in options {
+            if !option.is_empty() {
+                for (column, color) in option {
+                    add_node(&mut table, current_index, column, color, cost);
                     current_index += 1;
                 }
     
                 // spacer
-                table.up_links[current_index] = prev_spacer + 1;
-                table.down_links[prev_spacer] = current_index - 1;
+                table.set_up(current_index, prev_spacer + 1);
+                table.set_down(prev_spacer, current_index - 1);
                 prev_spacer = current_index;
                 current_index += 1;
             }
@@ -171,102 +223,208 @@ C: Eq + Copy + std::fmt::Debug {
         table
     }
 
-    fn commit(&mut self, row_node: usize, threshold: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+    fn left(&self, header: usize) -> usize {
+        self.headers[header].left.to_usize()
+    }
+
+    fn set_left(&mut self, header: usize, value: usize) {
+        self.headers[header].left = I::from_usize(value);
+    }
+
+    fn right(&self, header: usize) -> usize {
+        self.headers[header].right.to_usize()
+    }
+
+    fn set_right(&mut self, header: usize, value: usize) {
+        self.headers[header].right = I::from_usize(value);
+    }
+
+    fn length(&self, header: usize) -> usize {
+        self.headers[header].length.to_usize()
+    }
+
+    fn inc_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) + 1);
+    }
+
+    fn dec_length(&mut self, header: usize) {
+        self.headers[header].length = I::from_usize(self.length(header) - 1);
+    }
+
+    fn up(&self, node: usize) -> usize {
+        self.nodes[node].up.to_usize()
+    }
+
+    fn set_up(&mut self, node: usize, value: usize) {
+        self.nodes[node].up = I::from_usize(value);
+    }
+
+    fn down(&self, node: usize) -> usize {
+        self.nodes[node].down.to_usize()
+    }
+
+    fn set_down(&mut self, node: usize, value: usize) {
+        self.nodes[node].down = I::from_usize(value);
+    }
+
+    fn header(&self, node: usize) -> usize {
+        self.nodes[node].header.to_usize()
+    }
+
+    fn set_header(&mut self, node: usize, value: usize) {
+        self.nodes[node].header = I::from_usize(value);
+    }
+
+    // Purified nodes are marked with usize::MAX, which is stored as I::MAX.
+    fn color(&self, node: usize) -> usize {
+        let color = self.nodes[node].color;
+        if color == I::MAX {
+            usize::MAX
+        }
+        else {
+            color.to_usize()
+        }
+    }
+
+    fn set_color(&mut self, node: usize, value: usize) {
+        self.nodes[node].color = if value == usize::MAX {
+            I::MAX
+        }
+        else {
+            I::from_usize(value)
+        };
+    }
+
+    fn cost(&self, node: usize) -> W {
+        self.nodes[node].cost
+    }
+
+    fn set_cost(&mut self, node: usize, value: W) {
+        self.nodes[node].cost = value;
+    }
+
+    fn commit(&mut self, row_node: usize, threshold: Threshold<W>) {
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         if color == 0 {
             self.cover(header, threshold);
         }
         else if color != usize::MAX {
             self.purify(row_node, threshold);
-            self.colors[header] = color;
+            self.set_color(header, color);
         }
     }
 
-    fn uncommit(&mut self, row_node: usize, threshold: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+    fn uncommit(&mut self, row_node: usize, threshold: Threshold<W>) {
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         if color == 0 {
             self.uncover(header, threshold);
         }
         else if color != usize::MAX {
             self.unpurify(row_node, threshold);
-            self.colors[header] = 0;
+            self.set_color(header, 0);
         }
     }
 
-    fn purify(&mut self, row_node: usize, threshold: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+    fn purify(&mut self, row_node: usize, threshold: Threshold<W>) {
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         
-        let mut i = self.down_links[header];
-        while i != header && self.costs[i] < threshold {
-            if self.colors[i] == color {
-                self.colors[i] = usize::MAX;
+        let mut i = self.down(header);
+        while i != header && threshold.admits(self.cost(i)) {
+            if self.color(i) == color {
+                self.set_color(i, usize::MAX);
             }
             else {
                 self.hide(i);
             }
 
-            i = self.down_links[i];
+            i = self.down(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
-    fn unpurify(&mut self, row_node: usize, threshold: usize) {
-        let color = self.colors[row_node];
-        let header = self.header_links[row_node];
+    fn unpurify(&mut self, row_node: usize, threshold: Threshold<W>) {
+        let color = self.color(row_node);
+        let header = self.header(row_node);
         
-        let mut i = self.down_links[header];
-        while i != header && self.costs[i] < threshold {
-            if self.colors[i] == usize::MAX {
-                self.colors[i] = color;
+        let mut i = self.last_admitted(header, threshold);
+        while i != header {
+            if self.color(i) == usize::MAX {
+                self.set_color(i, color);
             }
             else {
                 self.unhide(i);
             }
 
-            i = self.down_links[i];
+            i = self.up(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
+        }
+    }
+
+    // The last node of the column that the threshold admits.
+    // The admitted nodes form a prefix of the column and must be restored from the bottom up.
+    fn last_admitted(&self, column: usize, threshold: Threshold<W>) -> usize {
+        let mut i = column;
+        while self.down(i) != column && threshold.admits(self.cost(self.down(i))) {
+            i = self.down(i);
         }
+        i
     }
 
-    fn cover(&mut self, column: usize, threshold: usize) {
-        self.left_links[self.right_links[column]] = self.left_links[column];
-        self.right_links[self.left_links[column]] = self.right_links[column];
+    fn cover(&mut self, column: usize, threshold: Threshold<W>) {
+        self.set_left(self.right(column), self.left(column));
+        self.set_right(self.left(column), self.right(column));
 
-        let mut i = self.down_links[column];
-        while i != column && self.costs[i] < threshold {
+        let mut i = self.down(column);
+        while i != column && threshold.admits(self.cost(i)) {
             self.hide(i);
-            i = self.down_links[i];
+            i = self.down(i);
+        }
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
         }
     }
 
-    fn uncover(&mut self, column: usize, threshold: usize) {
-        let mut i = self.down_links[column];
-        while i != column && self.costs[i] < threshold {
+    fn uncover(&mut self, column: usize, threshold: Threshold<W>) {
+        let mut i = self.last_admitted(column, threshold);
+        while i != column {
             self.unhide(i);
-            i = self.down_links[i];
+            i = self.up(i);
         }
 
-        self.left_links[self.right_links[column]] = column;
-        self.right_links[self.left_links[column]] = column;
+        self.set_left(self.right(column), column);
+        self.set_right(self.left(column), column);
+
+        if inspect::CHECK_LINKS {
+            self.check_links();
+        }
     }
     
     fn hide(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            if self.colors[i] != usize::MAX {
-                let header = self.header_links[i];
+            if self.color(i) != usize::MAX {
+                let header = self.header(i);
                 if header == 0 {
-                    i = self.up_links[i];
+                    i = self.up(i);
                 }
                 else {
-                    self.up_links[self.down_links[i]] = self.up_links[i];
-                    self.down_links[self.up_links[i]] = self.down_links[i];
-                    if self.lengths[header] == 0 {
-                        panic!("underflowing header {:?}", self.names[header]);
+                    self.set_up(self.down(i), self.up(i));
+                    self.set_down(self.up(i), self.down(i));
+                    if self.length(header) == 0 {
+                        panic!("underflowing header {:?}", self.columns.names[header]);
                     }
                     else {
-                        self.lengths[header] -= 1;
+                        self.dec_length(header);
                     }
     
                     i += 1;
@@ -281,15 +439,15 @@ C: Eq + Copy + std::fmt::Debug {
     fn unhide(&mut self, row_node: usize) {
         let mut i = row_node + 1;
         while i != row_node {
-            if self.colors[i] != usize::MAX {
-                let header = self.header_links[i];
+            if self.color(i) != usize::MAX {
+                let header = self.header(i);
                 if header == 0 {
-                    i = self.up_links[i];
+                    i = self.up(i);
                 }
                 else {
-                    self.lengths[header] += 1;
-                    self.up_links[self.down_links[i]] = i;
-                    self.down_links[self.up_links[i]] = i;
+                    self.inc_length(header);
+                    self.set_up(self.down(i), i);
+                    self.set_down(self.up(i), i);
     
                     i += 1;
                 }
@@ -300,12 +458,12 @@ C: Eq + Copy + std::fmt::Debug {
         }
     }
 
-    fn cover_row(&mut self, row_node: usize, threshold: usize) {
+    fn cover_row(&mut self, row_node: usize, threshold: Threshold<W>) {
         let mut i = row_node + 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.up_links[i];
+                i = self.up(i);
             }
             else {
                 self.commit(i, threshold);
@@ -314,181 +472,681 @@ C: Eq + Copy + std::fmt::Debug {
         }
     }
     
-    fn uncover_row(&mut self, row_node: usize, threshold: usize) {
-        let mut i = row_node + 1;
+    fn uncover_row(&mut self, row_node: usize, threshold: Threshold<W>) {
+        let mut i = row_node - 1;
         while i != row_node {
-            let header = self.header_links[i];
+            let header = self.header(i);
             if header == 0 {
-                i = self.up_links[i];
+                i = self.down(i);
             }
             else {
                 self.uncommit(i, threshold);
-                i += 1;
+                i -= 1;
             }
         }
     }
 
-    fn get_item(&self, row_node: usize) -> Item<P, S, C> {
-        let header = self.header_links[row_node];
-        match self.names[header] {
-            Some(Item::Primary(item)) => Item::Primary(item),
-            Some(Item::Secondary(item)) => {
-                if let Some(color) = self.color_names[self.colors[header]] {
-                    Item::ColoredSecondary(item, color)
-                }
-                else {
-                    Item::Secondary(item)
-                }
-            },
-            _ => panic!("None or ColoredSecondary in table headers. Something went horribly wrong.")
-        }
+    // Whether node belongs to an option, as opposed to a header or a spacer.
+    fn is_row_node(&self, node: usize) -> bool {
+        node >= self.headers.len() && node < self.nodes.len() && self.header(node) != 0
+    }
+
+    // Whether row_node can be the current row of the column at a level of the search:
+    // a node of the column, or the column itself once its rows are exhausted.
+    fn is_level(&self, column: usize, row_node: usize) -> bool {
+        column > 0 && column < self.headers.len()
+            && (row_node == column || (self.is_row_node(row_node) && self.header(row_node) == column))
     }
 
     fn get_row(&self, row_node: usize) -> Vec<Item<P, S, C>> {
-        let mut row = vec![self.get_item(row_node)];
+        let header = self.header(row_node);
+        let mut nodes = vec![(header, self.color(header))];
         let mut k = row_node + 1;
         while k != row_node {
-            let header = self.header_links[k];
+            let header = self.header(k);
             if header == 0 {
-                k = self.up_links[k];
+                k = self.up(k);
             }
             else {
-                let item = self.get_item(k);
-                row.push(item);
+                nodes.push((header, self.color(header)));
                 k += 1;
             }
         }
     
-        row
+        self.columns.items(&nodes)
     }
 
-    fn get_colors(&self) -> Vec<(S, Option<C>)> {
-        let mut assignments = Vec::new();
-        for (i, name) in self.names.iter().enumerate() {
-            if let Some(Item::Secondary(item)) = *name {
-                let color = self.color_names[self.colors[i]]; 
-                assignments.push((item, color));
+    // The options as they were given, sorted by cost and without empty ones.
+    // Only meaningful before any option is chosen, since choosing options recolors nodes.
+    fn options(&self) -> Vec<CostedSet<P, S, C, W>> {
+        self.option_starts()
+            .into_iter()
+            .map(|start| (self.option_items(start), self.cost(start)))
+            .collect()
+    }
+
+    fn option_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::new();
+        let mut start = self.columns.len() + 1;
+        while start < self.nodes.len() && self.header(start) != 0 {
+            starts.push(start);
+            let mut k = start;
+            while self.header(k) != 0 {
+                k += 1;
             }
+            start = k + 1;
+        }
+        starts
+    }
+
+    // The items of the option that row_node belongs to, with the colors of its nodes.
+    fn option_items(&self, row_node: usize) -> Vec<Item<P, S, C>> {
+        let nodes: Vec<_> = self.option_nodes(row_node)
+            .map(|k| (self.header(k), self.color(k)))
+            .collect();
+        self.columns.items(&nodes)
+    }
+
+    // Only identifies the problem while no columns are covered.
+    fn fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add_debug(&self.columns);
+        for header in &self.headers {
+            fingerprint.add(header.left.to_usize());
+            fingerprint.add(header.right.to_usize());
+            fingerprint.add(header.length.to_usize());
+        }
+        for i in 0..self.nodes.len() {
+            fingerprint.add(self.up(i));
+            fingerprint.add(self.down(i));
+            fingerprint.add(self.header(i));
+            fingerprint.add(self.color(i));
+            fingerprint.add_debug(&self.cost(i));
         }
+        fingerprint.finish()
+    }
 
-        assignments
+    fn primary_items(&self) -> Vec<P> {
+        self.columns.names[1..=self.columns.primary_count]
+            .iter()
+            .filter_map(|name| match name {
+                Some(Item::Primary(item)) => Some(*item),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn secondary_items(&self) -> Vec<S> {
+        self.columns.names
+            .iter()
+            .filter_map(|name| match name {
+                Some(Item::Secondary(item)) => Some(*item),
+                _ => None
+            })
+            .collect()
+    }
+
+    // The headers of the primary items that are not covered yet.
+    pub fn uncovered_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        successors(Some(self.right(0)), move |&column| Some(self.right(column)))
+            .take_while(|&column| column != 0)
+    }
+
+    pub fn column_item(&self, column: usize) -> Item<P, S, C> {
+        self.columns.names[column].unwrap()
+    }
+
+    pub fn is_primary(&self, column: usize) -> bool {
+        self.columns.is_primary(column)
+    }
+
+    // The first nodes of the options that are still linked into the column, cheapest first.
+    // Options that are too expensive for the current threshold may conflict with the chosen ones,
+    // since covering only hides the options that can still improve on the best solution.
+    pub fn column_options(&self, column: usize) -> impl Iterator<Item = usize> + '_ {
+        successors(Some(self.down(column)), move |&node| Some(self.down(node)))
+            .take_while(move |&node| node != column)
+    }
+
+    // All nodes of the option that row_node belongs to, starting with row_node.
+    pub fn option_nodes(&self, row_node: usize) -> impl Iterator<Item = usize> + '_ {
+        successors(Some(row_node), move |&node| {
+            let next = if self.header(node + 1) == 0 {
+                self.up(node + 1)
+            }
+            else {
+                node + 1
+            };
+            if next == row_node {
+                None
+            }
+            else {
+                Some(next)
+            }
+        })
+    }
+
+    pub fn node_column(&self, node: usize) -> usize {
+        self.header(node)
+    }
+
+    pub fn option_cost(&self, node: usize) -> W {
+        self.cost(node)
+    }
+
+    fn get_colors(&self) -> Vec<(S, Option<C>)> {
+        self.columns.assignments(|column| self.color(column))
+    }
+
+    // The items that are not covered with the number of options they have left,
+    // and the options that can still be chosen with their costs, see inspect.rs.
+    // The lengths include the options that are too expensive to be hidden, like column_options.
+    pub fn dump(&self) -> String {
+        self.columns.dump(self, |node| self.color(node), |node| Some(format!("{:?}", self.cost(node))))
+    }
+
+    // Panics if a link does not point back or a length is off.
+    pub fn check_links(&self) {
+        inspect::check_links(self);
     }
 }
 
-fn choose_column<P, S, C>(table: &DLXCTable<P, S, C>, threshold: usize) -> Option<usize> 
+impl<P, S, C, W, I> Links for DLXCTable<P, S, C, W, I>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    let mut header = table.right_links[0];
-    let mut choice_length = usize::MAX;
-    let mut choice = None;
-    while header != 0 {
-        let mut length = 0;
-        let mut i = table.down_links[header];
-        while i != header && table.costs[i] < threshold {
-            length += 1;
-            i = table.down_links[i];
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    fn header_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn left(&self, header: usize) -> usize {
+        DLXCTable::left(self, header)
+    }
+
+    fn right(&self, header: usize) -> usize {
+        DLXCTable::right(self, header)
+    }
+
+    fn length(&self, header: usize) -> usize {
+        DLXCTable::length(self, header)
+    }
+
+    fn up(&self, node: usize) -> usize {
+        DLXCTable::up(self, node)
+    }
+
+    fn down(&self, node: usize) -> usize {
+        DLXCTable::down(self, node)
+    }
+
+    fn header(&self, node: usize) -> usize {
+        DLXCTable::header(self, node)
+    }
+
+    fn label(&self, column: usize) -> String {
+        self.columns.label(column)
+    }
+}
+
+// Chooses the column to branch on, given the threshold of the level above.
+// Returns None when no primary column is left or when one of them has no option
+// within the threshold, since that branch cannot lead to a solution.
+// The options are tried in the order of the column, which is sorted by cost.
+pub type ColumnChooser<P, S, C, W, I> = fn(&DLXCTable<P, S, C, W, I>, Threshold<W>) -> Option<usize>;
+
+// The options of the column that are within the threshold, cheapest first.
+fn admitted_options<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>, column: usize, threshold: Threshold<W>) -> impl Iterator<Item = usize> + '_
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    table.column_options(column)
+        .take_while(move |&node| threshold.admits(table.cost(node)))
+}
+
+// The column with the fewest options within the threshold.
+// Ties go to the column whose cheapest option is the most expensive.
+pub fn fewest_cheap_options<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>, threshold: Threshold<W>) -> Option<usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    let mut choice: Option<(usize, usize, W)> = None;
+    for column in table.uncovered_columns() {
+        let mut options = admitted_options(table, column, threshold);
+        let cheapest = table.cost(options.next()?);
+        let count = 1 + options.count();
+        let better = match choice {
+            None => true,
+            Some((_, choice_count, choice_cheapest)) => 
+                count < choice_count || (count == choice_count && cheapest > choice_cheapest)
+        };
+        if better {
+            choice = Some((column, count, cheapest));
         }
+    }
+
+    choice.map(|(column, _, _)| column)
+}
 
-        if length == 0 {
-            return None
+// The column where taking its second cheapest option instead of the cheapest one costs the most.
+// A column with a single option within the threshold has the largest regret.
+// Regrets are differences of costs, so tuples are not supported.
+pub fn largest_regret<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>, threshold: Threshold<W>) -> Option<usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost + std::ops::Sub<Output = W>,
+I: Index {
+    let mut choice: Option<(usize, W)> = None;
+    for column in table.uncovered_columns() {
+        let mut options = admitted_options(table, column, threshold);
+        let cheapest = table.cost(options.next()?);
+        let regret = match options.next() {
+            Some(node) => table.cost(node) - cheapest,
+            None => W::max_value()
+        };
+        if choice.is_none_or(|(_, choice_regret)| regret > choice_regret) {
+            choice = Some((column, regret));
         }
-        else if length < choice_length {
-            choice = Some(header);
-            choice_length = table.lengths[header];
+    }
+
+    choice.map(|(column, _)| column)
+}
+
+// The column whose cheapest option within the threshold is the most expensive,
+// so that the cost of the partial solution grows as early as possible.
+// Ties go to the column with fewer options within the threshold.
+pub fn highest_minimum_cost<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>, threshold: Threshold<W>) -> Option<usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    let mut choice: Option<(usize, W, usize)> = None;
+    for column in table.uncovered_columns() {
+        let mut options = admitted_options(table, column, threshold);
+        let cheapest = table.cost(options.next()?);
+        let count = 1 + options.count();
+        let better = match choice {
+            None => true,
+            Some((_, choice_cheapest, choice_count)) => 
+                cheapest > choice_cheapest || (cheapest == choice_cheapest && count < choice_count)
+        };
+        if better {
+            choice = Some((column, cheapest, count));
         }
-        else if length == choice_length {
-            let choice_cost = table.costs[table.down_links[choice.unwrap()]];
-            let header_cost = table.costs[table.down_links[header]];
-            if header_cost > choice_cost {
-                choice = Some(header);
-                choice_length = table.lengths[header];
+    }
+
+    choice.map(|(column, _, _)| column)
+}
+
+// Estimates how much covering the remaining primary items will cost at least.
+// A branch is cut as soon as its cost plus the bound reaches the best solution found.
+pub type LowerBound<P, S, C, W, I> = Box<dyn Fn(&DLXCTable<P, S, C, W, I>) -> W>;
+
+// The options in the columns that can still be chosen, i.e. without covered primary items.
+fn available_options<'a, P, S, C, W, I>(table: &'a DLXCTable<P, S, C, W, I>, column: usize, uncovered: &'a [bool]) -> impl Iterator<Item = usize> + 'a
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    table.column_options(column)
+        .filter(move |&node| table
+            .option_nodes(node)
+            .all(|k| uncovered[table.header(k)] || !table.is_primary(table.header(k))))
+}
+
+fn uncovered_mask<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>) -> Vec<bool>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    let mut uncovered = vec![false; table.columns.len()];
+    for column in table.uncovered_columns() {
+        uncovered[column] = true;
+    }
+    uncovered
+}
+
+// Sums the cheapest options of uncovered items that no option shares.
+// Every such item needs an option of its own. The items are packed greedily.
+pub fn disjoint_items_bound<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>) -> W
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    let uncovered = uncovered_mask(table);
+    let mut packed = vec![false; table.columns.len()];
+    let mut bound = W::zero();
+    for column in table.uncovered_columns() {
+        let mut cheapest = None;
+        let mut disjoint = true;
+        for node in available_options(table, column, &uncovered) {
+            cheapest = cheapest.or(Some(table.cost(node)));
+            if table.option_nodes(node).any(|k| packed[table.header(k)]) {
+                disjoint = false;
+                break
             }
-        } 
-        header = table.right_links[header];
+        }
+
+        match cheapest {
+            // the item cannot be covered anymore
+            None => return W::max_value(),
+            Some(cost) if disjoint => {
+                packed[column] = true;
+                bound = bound.plus(cost);
+            },
+            Some(_) => ()
+        }
     }
+    bound
+}
+
+// Gives every uncovered item a price so that no option costs less than the prices
+// of its items, which bounds the cost of every exact cover from below.
+// The items are priced greedily, each as high as its options allow.
+// Pricing needs costs that can be subtracted, so tuples are not supported.
+pub fn packing_bound<P, S, C, W, I>(table: &DLXCTable<P, S, C, W, I>) -> W
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost + std::ops::Sub<Output = W>,
+I: Index {
+    let uncovered = uncovered_mask(table);
+    let mut prices = vec![W::zero(); table.columns.len()];
+    let mut bound = W::zero();
+    for column in table.uncovered_columns() {
+        let slack = available_options(table, column, &uncovered)
+            .map(|node| table.cost(node) - table
+                .option_nodes(node)
+                .map(|k| prices[table.header(k)])
+                .fold(W::zero(), W::plus))
+            .reduce(|slack, other| if other < slack { other } else { slack });
 
-    choice
+        match slack {
+            None => return W::max_value(),
+            Some(price) => {
+                prices[column] = price;
+                bound = bound.plus(price);
+            }
+        }
+    }
+    bound
 }
 
 #[derive(Clone,PartialEq,Eq,Debug)]
-pub struct Solution<P, S, C>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution<P, S, C, W = usize>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
     pub rows: Vec<Vec<Item<P, S, C>>>,
     pub colors: Vec<(S, Option<C>)>,
-    pub cost: usize
+    pub cost: W
+}
+
+impl<P, S, C, W> Solution<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn assignment(&self) -> Assignment<S, C> {
+        Assignment::new(self.colors.clone())
+    }
 }
 
 // type Solution<P, S, C> = (Vec<Vec<Item<P, S, C>>>, Vec<(S, Option<C>)>);
 
-#[derive(PartialEq,Eq,Clone,Copy,Debug)]
-pub enum State {
-    CoveringColumn,
-    CoveringRow,
-    BacktrackingColumn,
-    BacktrackingRow,
-    FoundSolution,
-}
+pub use crate::dlxc::State;
 
 #[derive(PartialEq,Eq,Clone,Copy,Debug)]
-struct LevelState {
+struct LevelState<W> {
     column: usize,
     row_node: usize,
-    hiding_threshold: usize,
-    covering_threshold: usize
+    // the cost of the rows chosen on the levels above
+    spent: W,
+    hiding_threshold: Threshold<W>,
+    covering_threshold: Threshold<W>
 }
 
-pub struct DLXCIter<P, S, C> 
+pub struct DLXCIter<P, S, C, W = usize, I = u32> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    table: DLXCTable<P, S, C>,
-    stack: Vec<LevelState>,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    table: DLXCTable<P, S, C, W, I>,
+    choose_column: ColumnChooser<P, S, C, W, I>,
+    stack: Vec<LevelState<W>>,
     state: State,
-    current_cost: usize,
-    best_cost: usize
+    current_cost: W,
+    limit: Limit<W>,
+    // the limit given by with_upper_bound, which solutions never make stricter
+    upper_bound: Limit<W>,
+    lower_bounds: Vec<LowerBound<P, S, C, W, I>>,
+    // the best solution so far, which can also be given by the caller,
+    // and the nodes of its rows
+    incumbent: Option<Solution<P, S, C, W>>,
+    incumbent_nodes: Vec<usize>,
+    omit_uncolored: bool,
+    fingerprint: u64,
+    trace: Option<Trace>
 }
 
-impl<P, S, C> DLXCIter<P, S, C>
+impl<P, S, C, W, I> DLXCIter<P, S, C, W, I>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    pub fn new(sets: Vec<(Vec<Item<P, S, C>>, usize)>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    pub fn new(sets: Vec<CostedSet<P, S, C, W>>, choose_column: ColumnChooser<P, S, C, W, I>,
+               primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
         let table = DLXCTable::new(sets, primary_items, secondary_items, colors);
+        let fingerprint = table.fingerprint();
         let stack = Vec::new();
         let state = State::CoveringColumn;
-        let current_cost = 0;
-        let best_cost = usize::MAX;
-        let mut this = DLXCIter { table, stack, state, current_cost, best_cost };
+        let current_cost = W::zero();
+        let limit = Limit::below(W::max_value());
+        let upper_bound = limit;
+        let lower_bounds = Vec::new();
+        let incumbent = None;
+        let incumbent_nodes = Vec::new();
+        let omit_uncolored = false;
+        let mut this = DLXCIter { 
+            table, 
+            choose_column, 
+            stack, 
+            state, 
+            current_cost, 
+            limit, 
+            upper_bound,
+            lower_bounds, 
+            incumbent, 
+            incumbent_nodes, 
+            omit_uncolored, 
+            fingerprint,
+            trace: None
+        };
         this.cover_column();
         this
     }
 
+    // Leaves the secondary items that no chosen option colors out of the colors of solutions.
+    pub fn omit_uncolored(mut self) -> Self {
+        self.omit_uncolored = true;
+        if let Some(incumbent) = &mut self.incumbent {
+            incumbent.colors.retain(|(_, color)| color.is_some());
+        }
+        self
+    }
+
+    // Records the search tree from here on, see trace.rs.
+    // The levels that are already on the stack are recorded as if they were just chosen.
+    pub fn with_trace(mut self) -> Self {
+        let mut trace = Trace::new();
+        for (i, level) in self.stack.iter().enumerate() {
+            trace.choose(&self.table.column_item(level.column), self.table.length(level.column));
+            if i + 1 < self.stack.len() || last_row_covered(self.state) {
+                trace.try_option(&self.table.get_row(level.row_node));
+            }
+        }
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    // Adds a bound that is checked at every node of the search. With several bounds the largest counts.
+    pub fn with_lower_bound(mut self, bound: impl Fn(&DLXCTable<P, S, C, W, I>) -> W + 'static) -> Self {
+        self.lower_bounds.push(Box::new(bound));
+        self
+    }
+
+    // Only searches for solutions that cost at most bound.
+    pub fn with_upper_bound(mut self, bound: W) -> Self {
+        self.limit = self.limit.min(Limit::at_most(bound));
+        self.upper_bound = self.upper_bound.min(Limit::at_most(bound));
+        if self.incumbent.as_ref().is_some_and(|incumbent| incumbent.cost > bound) {
+            self.incumbent = None;
+            self.incumbent_nodes.clear();
+        }
+        self
+    }
+
+    // Starts from a known solution, so only cheaper ones are searched for.
+    // best_solution returns it if there is nothing cheaper.
+    // Panics if the rows are not a solution of the problem.
+    pub fn with_initial_solution(mut self, rows: Vec<Vec<Item<P, S, C>>>) -> Self {
+        let problem = Problem::new(self.table.options(), self.table.primary_items(), self.table.secondary_items());
+        let (options, mut violations) = problem.resolve_rows(&rows);
+        let mut report = problem.verify_options(&options, None, None);
+        violations.append(&mut report.violations);
+        report.violations = violations;
+        if !report.is_valid() {
+            panic!("The initial solution is not valid.\n{}", report);
+        }
+
+        let cost = options
+            .iter()
+            .map(|&i| problem.options()[i].1)
+            .fold(W::zero(), W::plus);
+        let colors = self.row_colors(&rows);
+        if self.limit.admits(cost) {
+            let starts = self.table.option_starts();
+            self.limit = Limit::below(cost);
+            self.incumbent = Some(Solution { rows, colors, cost });
+            self.incumbent_nodes = options
+                .into_iter()
+                .map(|i| starts[i])
+                .collect();
+        }
+        self
+    }
+
+    // The colors of the secondary items in the given rows.
+    // Options that give an item several colors list all of them.
+    fn row_colors(&self, rows: &[Vec<Item<P, S, C>>]) -> Vec<(S, Option<C>)> {
+        let mut colors = Vec::new();
+        for item in self.table.secondary_items() {
+            let item_colors: Vec<C> = rows
+                .iter()
+                .map(|row| row
+                    .iter()
+                    .filter_map(|&other| match other {
+                        Item::ColoredSecondary(other, color) if other == item => Some(color),
+                        _ => None
+                    })
+                    .collect::<Vec<C>>())
+                .find(|row_colors| !row_colors.is_empty())
+                .unwrap_or_default();
+            if item_colors.is_empty() {
+                if !self.omit_uncolored {
+                    colors.push((item, None));
+                }
+            }
+            else {
+                colors.extend(item_colors.into_iter().map(|color| (item, Some(color))));
+            }
+        }
+        colors
+    }
+
+    pub fn lower_bound(&self) -> W {
+        self.lower_bounds
+            .iter()
+            .map(|bound| bound(&self.table))
+            .reduce(|bound, other| if other > bound { other } else { bound })
+            .unwrap_or(W::zero())
+    }
+
     fn cover_column(&mut self) {
+        if !self.lower_bounds.is_empty() && !self.limit.admits(self.current_cost.plus(self.lower_bound())) {
+            if let Some(trace) = &mut self.trace {
+                trace.prune();
+            }
+            self.state = State::BacktrackingRow;
+            return
+        }
+
         let hiding_threshold = self.stack
             .last()
             .map(|level| level.hiding_threshold)
-            .unwrap_or(usize::MAX);
-        if let Some(column) = choose_column(&self.table, hiding_threshold) {
-            let row_node = self.table.down_links[column];
-            let cost = self.table.costs[row_node];
-            if self.best_cost <= self.current_cost + cost {
+            .unwrap_or(Threshold::unbounded());
+        if let Some(column) = (self.choose_column)(&self.table, hiding_threshold) {
+            let row_node = self.table.down(column);
+            let cost = self.table.cost(row_node);
+            if let Some(trace) = &mut self.trace {
+                trace.choose(&self.table.column_item(column), self.table.length(column));
+            }
+            if !self.limit.admits(self.current_cost.plus(cost)) {
+                if let Some(trace) = &mut self.trace {
+                    trace.prune();
+                }
                 self.state = State::BacktrackingRow;
             }
             else {
-                let hiding_threshold = 
-                    self.best_cost - self.current_cost - cost;
+                let hiding_threshold = Threshold {
+                    spent: self.current_cost.plus(cost),
+                    limit: self.limit
+                };
                 let covering_threshold = hiding_threshold;
                 self.table.cover(column, hiding_threshold);
                 self.stack.push(LevelState { 
                     column, 
                     row_node,
+                    spent: self.current_cost,
                     hiding_threshold,
                     covering_threshold
                 });
@@ -504,10 +1162,13 @@ C: Eq + Copy + std::fmt::Debug {
                 }
             }
         }
-        else if self.table.right_links[0] == 0 {
+        else if self.table.right(0) == 0 {
             // all columns are covered
-            self.best_cost = self.current_cost;
+            self.limit = Limit::below(self.current_cost);
             self.state = State::FoundSolution;
+            if let Some(trace) = &mut self.trace {
+                trace.solution();
+            }
         }
         else {
             // no solutions exist on this branch
@@ -518,16 +1179,21 @@ C: Eq + Copy + std::fmt::Debug {
     fn cover_row(&mut self) {
         // cover the current row and set up for the next level 
         let level = self.stack.last_mut().unwrap();
-        let cost = self.table.costs[level.row_node];
-        if self.best_cost <= self.current_cost + cost {
+        let cost = self.table.cost(level.row_node);
+        if !self.limit.admits(self.current_cost.plus(cost)) {
             self.state = State::BacktrackingColumn;
         }
         else {
-            let threshold = 
-                self.best_cost - self.current_cost - cost;
+            let threshold = Threshold {
+                spent: self.current_cost.plus(cost),
+                limit: self.limit
+            };
             level.covering_threshold = threshold;
-            self.current_cost += cost;
+            self.current_cost = threshold.spent;
             self.table.cover_row(level.row_node, threshold);
+            if let Some(trace) = &mut self.trace {
+                trace.try_option(&self.table.get_row(level.row_node));
+            }
             self.state = State::CoveringColumn;
         }
     }
@@ -543,8 +1209,11 @@ C: Eq + Copy + std::fmt::Debug {
     fn backtrack_row(&mut self) {
         let mut level = self.stack.pop().unwrap();
         self.table.uncover_row(level.row_node, level.covering_threshold);
-        self.current_cost -= self.table.costs[level.row_node];
-        let row_node = self.table.down_links[level.row_node];
+        if let Some(trace) = &mut self.trace {
+            trace.leave();
+        }
+        self.current_cost = level.spent;
+        let row_node = self.table.down(level.row_node);
         level.row_node = row_node;
         self.stack.push(level);
         if level.row_node == level.column {
@@ -558,26 +1227,32 @@ C: Eq + Copy + std::fmt::Debug {
         }
     }
 
-    pub fn get_solution(&self) -> Solution<P, S, C> {
+    pub fn get_solution(&self) -> Solution<P, S, C, W> {
         let solution = self.stack
             .iter()
             .map(|level| level.row_node)
             .map(|i| self.table.get_row(i))
             .collect();
+        let mut colors = self.table.get_colors();
+        if self.omit_uncolored {
+            colors.retain(|(_, color)| color.is_some());
+        }
         Solution {
             rows: solution,
-            colors: self.table.get_colors(),
+            colors,
             cost: self.current_cost
         }
     }
 }
 
-impl<P, S, C> DLXCIter<P, S, C> 
+impl<P, S, C, W, I> DLXCIter<P, S, C, W, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    pub fn first_solution(mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    pub fn first_solution(mut self, time_limit: Duration) -> Option<Solution<P, S, C, W>> {
         let start_time = Instant::now();
         while !self.stack.is_empty() {
             if start_time.elapsed() > time_limit {
@@ -604,7 +1279,7 @@ C: Eq + Copy + std::fmt::Debug {
         None
     }
 
-    pub fn all_solutions(mut self, time_limit: Duration) -> Vec<Solution<P, S, C>> {
+    pub fn all_solutions(mut self, time_limit: Duration) -> Vec<Solution<P, S, C, W>> {
         let start_time = Instant::now();
         let mut solutions = Vec::new();
         while !self.stack.is_empty() {
@@ -633,16 +1308,193 @@ C: Eq + Copy + std::fmt::Debug {
         solutions
     }
 
-    pub fn best_solution(mut self, time_limit: Duration) -> Option<Solution<P, S, C>> {
+    // Searches for cheaper solutions until the time limit is hit.
+    // Returns true if the search is exhausted, so the incumbent is the cheapest solution.
+    // Can be called repeatedly, for example to take checkpoints in between.
+    pub fn run(&mut self, time_limit: Duration) -> bool {
         let start_time = Instant::now();
-        let mut best_solution = None;
         while !self.stack.is_empty() {
             if start_time.elapsed() >= time_limit {
-                return None
+                return false
+            }
+            match self.state {
+                State::FoundSolution => {
+                    self.incumbent = Some(self.get_solution());
+                    self.incumbent_nodes = self.stack
+                        .iter()
+                        .map(|level| level.row_node)
+                        .collect();
+                    self.state = State::BacktrackingRow;
+                },
+                State::CoveringColumn => {
+                    self.cover_column();
+                },
+                State::CoveringRow => {
+                    self.cover_row();
+                },
+                State::BacktrackingRow => {
+                    self.backtrack_row();
+                }
+                State::BacktrackingColumn => {
+                    self.backtrack_column();
+                },
+            }
+        }
+        true
+    }
+
+    // The table as the search left it, with the columns and rows of its levels covered.
+    pub fn table(&self) -> &DLXCTable<P, S, C, W, I> {
+        &self.table
+    }
+
+    // The cheapest solution found so far.
+    pub fn incumbent(&self) -> Option<&Solution<P, S, C, W>> {
+        self.incumbent.as_ref()
+    }
+
+    pub fn best_solution(mut self, time_limit: Duration) -> Option<Solution<P, S, C, W>> {
+        if self.run(time_limit) {
+            self.incumbent
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint<W> {
+        let threshold = |threshold: Threshold<W>| (threshold.spent, threshold.limit.cost, threshold.limit.inclusive);
+        let costs = SearchCosts {
+            current_cost: self.current_cost,
+            limit: (self.limit.cost, self.limit.inclusive),
+            levels: self.stack
+                .iter()
+                .map(|level| (level.spent, threshold(level.hiding_threshold), threshold(level.covering_threshold)))
+                .collect(),
+            incumbent: self.incumbent
+                .as_ref()
+                .map(|incumbent| (incumbent.cost, self.incumbent_nodes.clone()))
+        };
+        Checkpoint {
+            fingerprint: self.fingerprint,
+            state: self.state,
+            levels: self.stack
+                .iter()
+                .map(|level| (level.column, level.row_node))
+                .collect(),
+            costs: Some(costs)
+        }
+    }
+
+    // Undoes the search so far, leaving the table as it was before the first level.
+    // The rows below the last level are all covered.
+    fn rewind(&mut self) {
+        let mut row_covered = last_row_covered(self.state);
+        while let Some(level) = self.stack.pop() {
+            if row_covered {
+                self.table.uncover_row(level.row_node, level.covering_threshold);
+            }
+            self.table.uncover(level.column, level.hiding_threshold);
+            row_covered = true;
+        }
+        self.current_cost = W::zero();
+    }
+
+    // Continues from a checkpoint of the same problem instead of where this search stands,
+    // including the best solution found before the checkpoint.
+    // The bounds and the column choice are not part of the checkpoint and stay as they are.
+    pub fn restore(mut self, checkpoint: &Checkpoint<W>) -> Result<Self, CheckpointError> {
+        check_fingerprint(checkpoint, self.fingerprint)?;
+        let costs = match checkpoint.costs.as_ref() {
+            Some(costs) => costs,
+            None => return Err(CheckpointError::Mismatch("the checkpoint was not taken from min_cost_dlxc".to_string()))
+        };
+        check_levels(checkpoint, |column, row_node| self.table.is_level(column, row_node))?;
+        if let Some((_, nodes)) = &costs.incumbent {
+            if !nodes.iter().all(|&node| self.table.is_row_node(node)) {
+                return Err(CheckpointError::Mismatch("the incumbent of the checkpoint does not fit the table".to_string()))
+            }
+        }
+
+        self.rewind();
+
+        // the rows of the incumbent are read while the nodes have their own colors
+        match &costs.incumbent {
+            Some((cost, nodes)) => {
+                let rows: Vec<_> = nodes
+                    .iter()
+                    .map(|&node| self.table.option_items(node))
+                    .collect();
+                let colors = self.row_colors(&rows);
+                self.incumbent = Some(Solution { rows, colors, cost: *cost });
+                self.incumbent_nodes = nodes.clone();
+            },
+            None => {
+                self.incumbent = None;
+                self.incumbent_nodes.clear();
+            }
+        }
+
+        let threshold = |(spent, cost, inclusive)| Threshold { spent, limit: Limit { cost, inclusive } };
+        for (i, (&(column, row_node), &(spent, hiding, covering))) in checkpoint.levels.iter().zip(&costs.levels).enumerate() {
+            let hiding_threshold = threshold(hiding);
+            let covering_threshold = threshold(covering);
+            self.table.cover(column, hiding_threshold);
+            if i + 1 < checkpoint.levels.len() || last_row_covered(checkpoint.state) {
+                self.table.cover_row(row_node, covering_threshold);
+            }
+            self.stack.push(LevelState { column, row_node, spent, hiding_threshold, covering_threshold });
+        }
+        self.current_cost = costs.current_cost;
+        self.limit = Limit { cost: costs.limit.0, inclusive: costs.limit.1 };
+        self.state = checkpoint.state;
+        Ok(self)
+    }
+
+    // The k cheapest solutions that cost at most delta more than the cheapest one, sorted by cost.
+    // k can be usize::MAX and delta None. The search only looks for solutions that would make it
+    // into the result, so its bound is the k-th cost found so far or the cheapest cost plus delta.
+    // When the time limit is hit, the solutions found so far are returned.
+    // The search starts over from the first level, so the solutions do not depend on what run(),
+    // next() or a restored checkpoint explored before. Only the upper bound limits it at first:
+    // the incumbent is found again, and more expensive solutions can still be among the k cheapest.
+    pub fn cheapest_solutions(mut self, k: usize, delta: Option<W>, time_limit: Duration) -> Vec<Solution<P, S, C, W>> {
+        let start_time = Instant::now();
+        let mut solutions: Vec<Solution<P, S, C, W>> = Vec::new();
+        if k == 0 {
+            return solutions
+        }
+
+        // with nothing covered the limit can be looser than the one of the search so far
+        self.rewind();
+        let cap = self.upper_bound;
+        self.incumbent = None;
+        self.incumbent_nodes.clear();
+        self.limit = cap;
+        self.state = State::CoveringColumn;
+        self.cover_column();
+        while !self.stack.is_empty() {
+            if start_time.elapsed() >= time_limit {
+                break;
             }
             match self.state {
                 State::FoundSolution => {
-                    best_solution = Some(self.get_solution());
+                    let solution = self.get_solution();
+                    let position = solutions.partition_point(|other| other.cost <= solution.cost);
+                    solutions.insert(position, solution);
+                    solutions.truncate(k);
+
+                    // the limit only gets stricter, as the hiding thresholds on the stack require
+                    let mut limit = cap;
+                    if let Some(delta) = delta {
+                        let window = Limit::at_most(solutions[0].cost.plus(delta));
+                        solutions.retain(|other| window.admits(other.cost));
+                        limit = limit.min(window);
+                    }
+                    if solutions.len() == k {
+                        limit = limit.min(Limit::below(solutions[k - 1].cost));
+                    }
+                    self.limit = limit;
                     self.state = State::BacktrackingRow;
                 },
                 State::CoveringColumn => {
@@ -659,16 +1511,30 @@ C: Eq + Copy + std::fmt::Debug {
                 },
             }
         }
-        best_solution
+        solutions
+    }
+
+    // The k cheapest solutions, sorted by cost. Like solutions_within, this searches the whole problem
+    // again, even after run() or next(), and only the upper bound limits it, not the incumbent.
+    pub fn k_best_solutions(self, k: usize, time_limit: Duration) -> Vec<Solution<P, S, C, W>> {
+        self.cheapest_solutions(k, None, time_limit)
+    }
+
+    // All solutions that cost at most delta more than the cheapest one, sorted by cost.
+    // The search starts over like in k_best_solutions.
+    pub fn solutions_within(self, delta: W, time_limit: Duration) -> Vec<Solution<P, S, C, W>> {
+        self.cheapest_solutions(usize::MAX, Some(delta), time_limit)
     }
 }
 
-impl<P, S, C> Iterator for DLXCIter<P, S, C> 
+impl<P, S, C, W, I> Iterator for DLXCIter<P, S, C, W, I> 
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    type Item = Solution<P, S, C>;
+C: Eq + Copy + std::fmt::Debug,
+W: Cost,
+I: Index {
+    type Item = Solution<P, S, C, W>;
 
     fn next(&mut self) -> Option<Self::Item> {
         while !self.stack.is_empty() {
@@ -696,28 +1562,210 @@ C: Eq + Copy + std::fmt::Debug {
     }
 }
 
-pub fn min_cost_dlxc_iter<P, S, C>(sets: Vec<(Vec<Item<P, S, C>>, usize)>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> DLXCIter<P, S, C>
+pub fn min_cost_dlxc_iter<P, S, C, W>(sets: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> DLXCIter<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    DLXCIter::new(sets, fewest_cheap_options, primary_items, secondary_items, colors)
+}
+
+pub fn min_cost_dlxc<P, S, C, W>(sets: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, 
+                              colors: Vec<C>, time_limit: Duration) -> Option<Solution<P, S, C, W>>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    DLXCIter::new(sets, primary_items, secondary_items, colors)
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    min_cost_dlxc_iter(sets, primary_items, secondary_items, colors).best_solution(time_limit)
 }
 
-pub fn min_cost_dlxc<P, S, C>(sets: Vec<(Vec<Item<P, S, C>>, usize)>, primary_items: Vec<P>, secondary_items: Vec<S>, 
-                              colors: Vec<C>, time_limit: Duration) -> Option<Solution<P, S, C>>
+pub fn min_cost_dlxc_first<P, S, C, W>(sets: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, 
+                                    colors: Vec<C>, time_limit: Duration) -> Option<Solution<P, S, C, W>>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    DLXCIter::new(sets, primary_items, secondary_items, colors).best_solution(time_limit)
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    min_cost_dlxc_iter(sets, primary_items, secondary_items, colors).first_solution(time_limit)
 }
 
-pub fn min_cost_dlxc_first<P, S, C>(sets: Vec<(Vec<Item<P, S, C>>, usize)>, primary_items: Vec<P>, secondary_items: Vec<S>, 
-                                    colors: Vec<C>, time_limit: Duration) -> Option<Solution<P, S, C>>
+pub fn min_cost_dlxc_k_best<P, S, C, W>(sets: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, 
+                                     colors: Vec<C>, k: usize, time_limit: Duration) -> Vec<Solution<P, S, C, W>>
 where
 P: Eq + Copy + std::fmt::Debug,
 S: Eq + Copy + std::fmt::Debug,
-C: Eq + Copy + std::fmt::Debug {
-    DLXCIter::new(sets, primary_items, secondary_items, colors).first_solution(time_limit)
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    min_cost_dlxc_iter(sets, primary_items, secondary_items, colors).k_best_solutions(k, time_limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::verify::Problem;
+    use super::CostedSet;
+    use super::Item;
+    use super::Solution;
+    use super::min_cost_dlxc_iter;
+
+    type Sets = Vec<CostedSet<usize, usize, usize>>;
+
+    // Every option has a primary item, since the solvers never choose the others.
+    // No two options have the same items, so that the rows of a solution tell which options it uses.
+    fn random_problem(rng: &mut StdRng) -> (Sets, usize, usize) {
+        let primary_count = rng.gen_range(1..=5);
+        let secondary_count = rng.gen_range(0..=2);
+        let mut sets: Sets = Vec::new();
+        for _ in 0..rng.gen_range(1..=10) {
+            let (set, cost) = {
+                let mut set = vec![Item::Primary(rng.gen_range(0..primary_count))];
+                for item in 0..primary_count {
+                    if rng.gen_bool(0.3) && set[0] != Item::Primary(item) {
+                        set.push(Item::Primary(item));
+                    }
+                }
+                for item in 0..secondary_count {
+                    match rng.gen_range(0..4) {
+                        0 => set.push(Item::Secondary(item)),
+                        1 => set.push(Item::ColoredSecondary(item, rng.gen_range(0..2))),
+                        _ => {}
+                    }
+                }
+                (set, rng.gen_range(0..5))
+            };
+            if !sets.iter().any(|(other, _)| other.len() == set.len() && other.iter().all(|item| set.contains(item))) {
+                sets.push((set, cost));
+            }
+        }
+        (sets, primary_count, secondary_count)
+    }
+
+    fn problem(sets: &Sets, primary_count: usize, secondary_count: usize) -> Problem<usize, usize, usize> {
+        Problem::new(sets.clone(), (0..primary_count).collect(), (0..secondary_count).collect())
+    }
+
+    // The solutions as sets of options, found by trying every subset.
+    fn brute_force(problem: &Problem<usize, usize, usize>) -> Vec<Vec<usize>> {
+        let count = problem.options().len();
+        (0..1usize << count)
+            .map(|mask| (0..count).filter(|&i| mask & (1 << i) != 0).collect::<Vec<_>>())
+            .filter(|options| problem.verify_options(options, None, None).is_valid())
+            .collect()
+    }
+
+    fn sorted_costs(problem: &Problem<usize, usize, usize>) -> Vec<usize> {
+        let mut costs: Vec<usize> = brute_force(problem)
+            .iter()
+            .map(|options| options.iter().map(|&i| problem.options()[i].1).sum())
+            .collect();
+        costs.sort();
+        costs
+    }
+
+    // Checks that the solutions are valid and returns their costs.
+    fn costs(problem: &Problem<usize, usize, usize>, solutions: &[Solution<usize, usize, usize>]) -> Vec<usize> {
+        for solution in solutions {
+            let report = problem.verify_rows(&solution.rows, Some(&solution.colors), Some(solution.cost));
+            assert!(report.is_valid(), "{}", report);
+        }
+        solutions.iter().map(|solution| solution.cost).collect()
+    }
+
+    #[test]
+    fn k_best_solutions_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(33);
+        for _ in 0..300 {
+            let (sets, primary_count, secondary_count) = random_problem(&mut rng);
+            let problem = problem(&sets, primary_count, secondary_count);
+            let expected = sorted_costs(&problem);
+            let k = rng.gen_range(1..=4);
+            let iter = || min_cost_dlxc_iter(sets.clone(), (0..primary_count).collect(), (0..secondary_count).collect(), vec![0, 1]);
+
+            let solutions = iter().k_best_solutions(k, Duration::MAX);
+            assert_eq!(costs(&problem, &solutions), expected[..k.min(expected.len())], "{:?}", sets);
+
+            // the search starts over after next()
+            let mut searched = iter();
+            searched.next();
+            let solutions = searched.k_best_solutions(k, Duration::MAX);
+            assert_eq!(costs(&problem, &solutions), expected[..k.min(expected.len())], "{:?}", sets);
+
+            let bound = rng.gen_range(0..10);
+            let solutions = iter().with_upper_bound(bound).k_best_solutions(k, Duration::MAX);
+            let within_bound: Vec<usize> = expected.iter().cloned().filter(|&cost| cost <= bound).take(k).collect();
+            assert_eq!(costs(&problem, &solutions), within_bound, "{:?}", sets);
+
+            // an initial solution does not limit the search
+            if let Some(options) = brute_force(&problem).last() {
+                let rows = options.iter().map(|&i| sets[i].0.clone()).collect();
+                let solutions = iter().with_initial_solution(rows).k_best_solutions(k, Duration::MAX);
+                assert_eq!(costs(&problem, &solutions), expected[..k.min(expected.len())], "{:?}", sets);
+            }
+        }
+    }
+
+    #[test]
+    fn solutions_within_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(34);
+        for _ in 0..300 {
+            let (sets, primary_count, secondary_count) = random_problem(&mut rng);
+            let problem = problem(&sets, primary_count, secondary_count);
+            let expected = sorted_costs(&problem);
+            let delta = rng.gen_range(0..4);
+            let iter = || min_cost_dlxc_iter(sets.clone(), (0..primary_count).collect(), (0..secondary_count).collect(), vec![0, 1]);
+
+            let window: Vec<usize> = expected.iter().cloned().filter(|&cost| cost <= expected[0] + delta).collect();
+            let solutions = iter().solutions_within(delta, Duration::MAX);
+            assert_eq!(costs(&problem, &solutions), window, "{:?}", sets);
+
+            let mut searched = iter();
+            searched.next();
+            let solutions = searched.solutions_within(delta, Duration::MAX);
+            assert_eq!(costs(&problem, &solutions), window, "{:?}", sets);
+        }
+    }
+
+    #[test]
+    fn float_and_tuple_costs_verify() {
+        let mut rng = StdRng::seed_from_u64(35);
+        for _ in 0..300 {
+            let (sets, primary_count, secondary_count) = random_problem(&mut rng);
+            let options = brute_force(&problem(&sets, primary_count, secondary_count));
+
+            let float_sets: Vec<CostedSet<usize, usize, usize, f64>> = sets.iter()
+                .map(|(set, cost)| (set.clone(), *cost as f64 / 4.0))
+                .collect();
+            let float_problem = Problem::new(float_sets.clone(), (0..primary_count).collect(), (0..secondary_count).collect());
+            let expected = options.iter()
+                .map(|options| options.iter().map(|&i| float_sets[i].1).sum::<f64>())
+                .fold(None, |min: Option<f64>, cost| Some(min.map_or(cost, |min| min.min(cost))));
+            let solution = min_cost_dlxc_iter(float_sets, (0..primary_count).collect(), (0..secondary_count).collect(), vec![0, 1])
+                .best_solution(Duration::MAX);
+            assert_eq!(solution.as_ref().map(|solution| solution.cost), expected, "{:?}", sets);
+            if let Some(solution) = solution {
+                let report = float_problem.verify_rows(&solution.rows, Some(&solution.colors), Some(solution.cost));
+                assert!(report.is_valid(), "{}", report);
+            }
+
+            let tuple_sets: Vec<CostedSet<usize, usize, usize, (usize, usize)>> = sets.iter()
+                .map(|(set, cost)| (set.clone(), (cost % 2, *cost)))
+                .collect();
+            let tuple_problem = Problem::new(tuple_sets.clone(), (0..primary_count).collect(), (0..secondary_count).collect());
+            let expected = options.iter()
+                .map(|options| options.iter().fold((0, 0), |(a, b), &i| (a + tuple_sets[i].1.0, b + tuple_sets[i].1.1)))
+                .min();
+            let solution = min_cost_dlxc_iter(tuple_sets, (0..primary_count).collect(), (0..secondary_count).collect(), vec![0, 1])
+                .best_solution(Duration::MAX);
+            assert_eq!(solution.as_ref().map(|solution| solution.cost), expected, "{:?}", sets);
+            if let Some(solution) = solution {
+                let report = tuple_problem.verify_rows(&solution.rows, Some(&solution.colors), Some(solution.cost));
+                assert!(report.is_valid(), "{}", report);
+            }
+        }
+    }
 }
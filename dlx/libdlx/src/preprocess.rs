@@ -0,0 +1,542 @@
+// Reductions of exact cover problems before the search. They are narrower than the ones
+// of Knuth's DLX-PRE: an option is only dominated by another option with exactly the same items,
+// and a primary item is only merged into another one that occurs in exactly the same options.
+// A primary item whose options all contain some other primary item does not lead to any removals.
+use std::fmt;
+use crate::cost::Cost;
+use crate::dlxc::Item;
+use crate::verify::Problem;
+use crate::verify::ResolvedRows;
+
+// Why an option was dropped from the problem.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum OptionRemoval<P>
+where
+P: Eq + Copy + std::fmt::Debug {
+    // the option has no primary items, so the solvers never choose it
+    NoPrimary,
+    // another option has the same items and is not more expensive
+    Dominated { by: usize },
+    // every remaining option of the item conflicts with this one,
+    // so choosing it would leave the item uncoverable
+    Blocking { item: P }
+}
+
+// Why an item was dropped from the problem.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum ItemRemoval<P, S>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug {
+    // the primary item occurs in exactly the same options as same_as
+    Primary { item: P, same_as: P },
+    // all options of the secondary item contain the primary item within,
+    // so it can never be used twice. None if no option uses it.
+    Secondary { item: S, within: Option<P> }
+}
+
+// Options that preprocessing cannot handle. The reductions only know uncolored primary items
+// and options that give every secondary item at most one color.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum Unsupported {
+    // the option has a colored primary item
+    ColoredPrimary { option: usize },
+    // the option gives an item several colors
+    SeveralColors { option: usize }
+}
+
+// The result of preprocessing. Option indices always refer to the original problem.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Preprocessed<P, S, C, W = usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    // the reduced problem
+    pub problem: Problem<P, S, C, W>,
+    // the original index of every option of the reduced problem
+    pub option_indices: Vec<usize>,
+    pub removed_options: Vec<(usize, OptionRemoval<P>)>,
+    pub removed_items: Vec<ItemRemoval<P, S>>,
+    // primary items that are left with a single option, which every solution must contain
+    pub forced: Vec<(P, usize)>,
+    // primary items without any option; if there are any, the problem has no solution
+    pub uncoverable: Vec<P>,
+    pub rounds: usize
+}
+
+// An option with its items numbered, primary items first.
+// Uncolored secondary items have no color.
+#[derive(Clone,PartialEq,Debug)]
+struct Row<C, W>
+where
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    items: Vec<(usize, Option<C>)>,
+    cost: W,
+    alive: bool
+}
+
+fn conflicts<C, W>(a: &Row<C, W>, b: &Row<C, W>, primary_count: usize) -> bool
+where
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    a.items.iter().any(|&(item, color)| b.items
+        .iter()
+        .any(|&(other, other_color)| item == other && (item < primary_count || color.is_none() || color != other_color)))
+}
+
+// The alive options of every item.
+fn item_options<C, W>(rows: &[Row<C, W>], item_count: usize) -> Vec<Vec<usize>>
+where
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    let mut item_options = vec![Vec::new(); item_count];
+    for (index, row) in rows.iter().enumerate().filter(|(_, row)| row.alive) {
+        for &(item, _) in row.items.iter() {
+            item_options[item].push(index);
+        }
+    }
+    item_options
+}
+
+fn same_items<C, W>(a: &Row<C, W>, b: &Row<C, W>) -> bool
+where
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    a.items.len() == b.items.len() && a.items.iter().all(|item| b.items.contains(item))
+}
+
+impl<P, S, C, W> Preprocessed<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn is_infeasible(&self) -> bool {
+        !self.uncoverable.is_empty()
+    }
+
+    // Maps option indices of the reduced problem to the original ones.
+    pub fn original_options(&self, options: &[usize]) -> Vec<usize> {
+        options.iter()
+            .map(|&option| self.option_indices[option])
+            .collect()
+    }
+
+    // Finds the original options of the rows of a solution of the reduced problem.
+    pub fn resolve_rows(&self, rows: &[Vec<Item<P, S, C>>]) -> ResolvedRows<P, S, C, W> {
+        let (options, violations) = self.problem.resolve_rows(rows);
+        (self.original_options(&options), violations)
+    }
+
+    // The options of the reduced problem without their costs, as dlxc expects them.
+    pub fn sets(&self) -> Vec<Vec<Item<P, S, C>>> {
+        self.problem
+            .options()
+            .iter()
+            .map(|(set, _)| set.clone())
+            .collect()
+    }
+}
+
+impl<T> Preprocessed<T, T, ()>
+where
+T: Eq + Copy + std::fmt::Debug {
+    // The options of the reduced problem in the form dlx expects them.
+    pub fn dlx_sets(&self) -> Vec<Vec<T>> {
+        self.problem
+            .options()
+            .iter()
+            .map(|(set, _)| set
+                .iter()
+                .map(|&item| match item {
+                    Item::Primary(item) | Item::ColoredPrimary(item, _) | Item::PrimaryColor(item, _) => item,
+                    Item::Secondary(item) => item,
+                    Item::ColoredSecondary(item, _) => item
+                })
+                .collect())
+            .collect()
+    }
+}
+
+impl<P> fmt::Display for OptionRemoval<P>
+where
+P: Eq + Copy + std::fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionRemoval::NoPrimary => write!(f, "has no primary items"),
+            OptionRemoval::Dominated { by } => write!(f, "is dominated by option {}", by),
+            OptionRemoval::Blocking { item } => write!(f, "blocks item {:?}", item)
+        }
+    }
+}
+
+impl<P, S> fmt::Display for ItemRemoval<P, S>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ItemRemoval::Primary { item, same_as } => write!(f, "item {:?} always occurs with {:?}", item, same_as),
+            ItemRemoval::Secondary { item, within: Some(primary) } => write!(f, "item {:?} only occurs with {:?}", item, primary),
+            ItemRemoval::Secondary { item, within: None } => write!(f, "item {:?} is not used", item)
+        }
+    }
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unsupported::ColoredPrimary { option } =>
+                write!(f, "option {} has a colored primary item, which preprocessing does not support", option),
+            Unsupported::SeveralColors { option } =>
+                write!(f, "option {} gives an item several colors, which preprocessing does not support", option)
+        }
+    }
+}
+
+impl<P, S, C, W> fmt::Display for Preprocessed<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (option, reason) in self.removed_options.iter() {
+            writeln!(f, "removed option {}: it {}", option, reason)?;
+        }
+        for removal in self.removed_items.iter() {
+            writeln!(f, "removed {}", removal)?;
+        }
+        for (item, option) in self.forced.iter() {
+            writeln!(f, "item {:?} is forced to option {}", item, option)?;
+        }
+        for item in self.uncoverable.iter() {
+            writeln!(f, "item {:?} cannot be covered", item)?;
+        }
+        write!(f, "{} options and {} items removed in {} rounds, {} options left",
+               self.removed_options.len(), self.removed_items.len(), self.rounds, self.option_indices.len())
+    }
+}
+
+// Reduces the problem in rounds until nothing changes:
+// options without primary items, dominated options and options that block a primary item
+// are removed, then items that are implied by a primary item are merged into it.
+// Only options with the same items count as dominated, and only primary items with the same
+// options as implied, see the top of this file.
+// The reduced problem has a solution iff the original one has, with the same minimum cost,
+// but duplicate options are only kept once.
+pub fn preprocess<P, S, C, W>(problem: &Problem<P, S, C, W>) -> Result<Preprocessed<P, S, C, W>, Unsupported>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    let primary_items = problem.primary_items();
+    let secondary_items = problem.secondary_items();
+    let primary_count = primary_items.len();
+    let item_count = primary_count + secondary_items.len();
+
+    let mut rows: Vec<Row<C, W>> = problem.options()
+        .iter()
+        .enumerate()
+        .map(|(index, (set, cost))| Ok(Row {
+            items: set.iter()
+                .map(|&item| match item {
+                    Item::Primary(p) => Ok((primary_items
+                        .iter()
+                        .position(|&other| other == p)
+                        .unwrap_or_else(|| panic!("Option {}: {:?} not present", index, item)), None)),
+                    Item::ColoredPrimary(_, _) | Item::PrimaryColor(_, _) => Err(Unsupported::ColoredPrimary { option: index }),
+                    Item::Secondary(s) | Item::ColoredSecondary(s, _) => Ok((primary_count + secondary_items
+                        .iter()
+                        .position(|&other| other == s)
+                        .unwrap_or_else(|| panic!("Option {}: {:?} not present", index, item)), match item {
+                            Item::ColoredSecondary(_, c) => Some(c),
+                            _ => None
+                        }))
+                })
+                .collect::<Result<_, _>>()?,
+            cost: *cost,
+            alive: true
+        }))
+        .collect::<Result<_, _>>()?;
+    if let Some(index) = rows.iter().position(|row| row.items
+        .iter()
+        .enumerate()
+        .any(|(i, &(item, color))| row.items[..i].iter().any(|&(other, other_color)| other == item && other_color != color))) {
+        return Err(Unsupported::SeveralColors { option: index })
+    }
+
+    let mut removed_options = Vec::new();
+    let mut removed_items = Vec::new();
+    let mut item_alive = vec![true; item_count];
+
+    for (index, row) in rows.iter_mut().enumerate() {
+        if row.items.iter().all(|&(item, _)| item >= primary_count) {
+            row.alive = false;
+            removed_options.push((index, OptionRemoval::NoPrimary));
+        }
+    }
+
+    let mut rounds = 0;
+    let uncoverable = 'rounds: loop {
+        rounds += 1;
+        let mut changed = false;
+
+        let options = item_options(&rows, item_count);
+        let uncoverable: Vec<P> = (0..primary_count)
+            .filter(|&item| item_alive[item] && options[item].is_empty())
+            .map(|item| primary_items[item])
+            .collect();
+        if !uncoverable.is_empty() {
+            break uncoverable
+        }
+
+        // dominated options
+        for a in 0..rows.len() {
+            if !rows[a].alive {
+                continue
+            }
+            let first = rows[a].items[0].0;
+            for &b in options[first].iter() {
+                if b != a && rows[b].alive && same_items(&rows[a], &rows[b])
+                    && (rows[b].cost < rows[a].cost || (rows[b].cost == rows[a].cost && b < a)) {
+                    rows[a].alive = false;
+                    removed_options.push((a, OptionRemoval::Dominated { by: b }));
+                    changed = true;
+                    break
+                }
+            }
+        }
+
+        // blocking options
+        let mut conflicting = vec![false; rows.len()];
+        for a in 0..rows.len() {
+            if !rows[a].alive {
+                continue
+            }
+            let mut marked = Vec::new();
+            for &(item, _) in rows[a].items.iter() {
+                for &b in options[item].iter() {
+                    if b != a && !conflicting[b] && conflicts(&rows[a], &rows[b], primary_count) {
+                        conflicting[b] = true;
+                        marked.push(b);
+                    }
+                }
+            }
+
+            let blocked = (0..primary_count).find(|&item| item_alive[item]
+                && !rows[a].items.iter().any(|&(other, _)| other == item)
+                && options[item]
+                    .iter()
+                    .all(|&b| !rows[b].alive || conflicting[b]));
+            for b in marked {
+                conflicting[b] = false;
+            }
+
+            if let Some(item) = blocked {
+                rows[a].alive = false;
+                removed_options.push((a, OptionRemoval::Blocking { item: primary_items[item] }));
+                changed = true;
+
+                // stop as soon as the problem is known to be infeasible,
+                // the remaining removals would only hide the reason
+                let uncoverable: Vec<P> = rows[a].items
+                    .iter()
+                    .filter(|&&(other, _)| other < primary_count && options[other].iter().all(|&b| !rows[b].alive))
+                    .map(|&(other, _)| primary_items[other])
+                    .collect();
+                if !uncoverable.is_empty() {
+                    break 'rounds uncoverable
+                }
+            }
+        }
+
+        // implied items
+        let options = item_options(&rows, item_count);
+        let mut removed = vec![false; item_count];
+        for item in 0..item_count {
+            if !item_alive[item] {
+                continue
+            }
+            if item < primary_count {
+                if options[item].is_empty() {
+                    continue
+                }
+                let same_as = (0..item).find(|&other| item_alive[other] && !removed[other] && options[other] == options[item]);
+                if let Some(other) = same_as {
+                    removed[item] = true;
+                    removed_items.push(ItemRemoval::Primary { item: primary_items[item], same_as: primary_items[other] });
+                }
+            }
+            else {
+                let within = (0..primary_count).find(|&other| item_alive[other] && !removed[other] && !options[other].is_empty()
+                    && options[item].iter().all(|option| options[other].contains(option)));
+                if within.is_some() || options[item].is_empty() {
+                    removed[item] = true;
+                    removed_items.push(ItemRemoval::Secondary {
+                        item: secondary_items[item - primary_count],
+                        within: within.map(|other| primary_items[other])
+                    });
+                }
+            }
+        }
+        if removed.iter().any(|&r| r) {
+            changed = true;
+            for item in 0..item_count {
+                item_alive[item] &= !removed[item];
+            }
+            for row in rows.iter_mut() {
+                row.items.retain(|&(item, _)| !removed[item]);
+            }
+        }
+
+        if !changed {
+            break Vec::new()
+        }
+    };
+
+    let options = problem.options();
+    let mut option_indices = Vec::new();
+    let mut reduced_options = Vec::new();
+    for (index, row) in rows.iter().enumerate().filter(|(_, row)| row.alive) {
+        let set = options[index].0
+            .iter()
+            .filter(|&&item| match item {
+                Item::Primary(p) | Item::ColoredPrimary(p, _) | Item::PrimaryColor(p, _) =>
+                    item_alive[primary_items.iter().position(|&other| other == p).unwrap()],
+                Item::Secondary(s) | Item::ColoredSecondary(s, _) =>
+                    item_alive[primary_count + secondary_items.iter().position(|&other| other == s).unwrap()]
+            })
+            .cloned()
+            .collect();
+        option_indices.push(index);
+        reduced_options.push((set, row.cost));
+    }
+
+    let mut forced = Vec::new();
+    if uncoverable.is_empty() {
+        for item in (0..primary_count).filter(|&item| item_alive[item]) {
+            let mut item_options = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row.alive && row.items.iter().any(|&(other, _)| other == item));
+            if let (Some((option, _)), None) = (item_options.next(), item_options.next()) {
+                forced.push((primary_items[item], option));
+            }
+        }
+    }
+
+    let reduced_primary = (0..primary_count)
+        .filter(|&item| item_alive[item])
+        .map(|item| primary_items[item])
+        .collect();
+    let reduced_secondary = (primary_count..item_count)
+        .filter(|&item| item_alive[item])
+        .map(|item| secondary_items[item - primary_count])
+        .collect();
+
+    Ok(Preprocessed {
+        problem: Problem::new(reduced_options, reduced_primary, reduced_secondary),
+        option_indices,
+        removed_options,
+        removed_items,
+        forced,
+        uncoverable,
+        rounds
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::dlxc::Item;
+    use crate::verify::Problem;
+    use super::Unsupported;
+    use super::preprocess;
+
+    fn random_problem(rng: &mut StdRng) -> Problem<usize, usize, usize> {
+        let primary_count = rng.gen_range(1..=5);
+        let secondary_count = rng.gen_range(0..=2);
+        let options = (0..rng.gen_range(1..=10))
+            .map(|_| {
+                let mut set = Vec::new();
+                for item in 0..primary_count {
+                    if rng.gen_bool(0.4) {
+                        set.push(Item::Primary(item));
+                    }
+                }
+                for item in 0..secondary_count {
+                    match rng.gen_range(0..4) {
+                        0 => set.push(Item::Secondary(item)),
+                        1 => set.push(Item::ColoredSecondary(item, rng.gen_range(0..2))),
+                        _ => {}
+                    }
+                }
+                (set, rng.gen_range(0..4))
+            })
+            .collect();
+        Problem::new(options, (0..primary_count).collect(), (0..secondary_count).collect())
+    }
+
+    // Every set of options that solves the problem, found by trying them all.
+    fn brute_force(problem: &Problem<usize, usize, usize>) -> Vec<Vec<usize>> {
+        let count = problem.options().len();
+        (0..1usize << count)
+            .map(|mask| (0..count).filter(|&i| mask & (1 << i) != 0).collect::<Vec<_>>())
+            .filter(|options| problem.verify_options(options, None, None).is_valid())
+            .collect()
+    }
+
+    fn cost(problem: &Problem<usize, usize, usize>, options: &[usize]) -> usize {
+        options.iter().map(|&i| problem.options()[i].1).sum()
+    }
+
+    #[test]
+    fn keeps_feasibility_and_minimum_cost() {
+        let mut rng = StdRng::seed_from_u64(30);
+        for _ in 0..500 {
+            let problem = random_problem(&mut rng);
+            let reduced = preprocess(&problem).unwrap();
+            let original_min = brute_force(&problem).iter().map(|options| cost(&problem, options)).min();
+            let solutions = brute_force(&reduced.problem);
+            let reduced_min = solutions.iter().map(|options| cost(&reduced.problem, options)).min();
+            if reduced.is_infeasible() {
+                assert_eq!(original_min, None, "{:?}", problem);
+                continue;
+            }
+            assert_eq!(original_min, reduced_min, "{:?}", problem);
+
+            for options in solutions {
+                let original = reduced.original_options(&options);
+                assert!(problem.verify_options(&original, None, Some(cost(&reduced.problem, &options))).is_valid(),
+                        "{:?} maps to {:?} in {:?}", options, original, problem);
+                for (item, option) in reduced.forced.iter() {
+                    assert!(original.contains(option), "{:?} is not forced to {} in {:?}", item, option, problem);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_colored_primaries_and_several_colors() {
+        let options = vec![
+            (vec![Item::Primary(0)], 1),
+            (vec![Item::ColoredPrimary(0, 0), Item::Secondary(0)], 1)
+        ];
+        let problem: Problem<usize, usize, usize> = Problem::new(options, vec![0], vec![0]);
+        assert_eq!(preprocess(&problem), Err(Unsupported::ColoredPrimary { option: 1 }));
+
+        let options = vec![
+            (vec![Item::Primary(0), Item::ColoredSecondary(0, 0), Item::ColoredSecondary(0, 1)], 1),
+            (vec![Item::Primary(1), Item::ColoredSecondary(0, 0)], 1)
+        ];
+        let problem: Problem<usize, usize, usize> = Problem::new(options, vec![0, 1], vec![0]);
+        assert_eq!(preprocess(&problem), Err(Unsupported::SeveralColors { option: 0 }));
+    }
+}
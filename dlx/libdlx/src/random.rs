@@ -0,0 +1,18 @@
+use std::cell::RefCell;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// The randomness of the randomized column choices. Every thread has its own generator,
+// seeded from the system unless seed is called, which makes the runs on that thread repeatable.
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub(crate) fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
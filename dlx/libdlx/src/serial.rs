@@ -0,0 +1,131 @@
+use std::fs;
+use serde::Serialize;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use bincode::Options;
+use crate::cost::Cost;
+use crate::dlxc;
+use crate::dlxc::Item;
+use crate::min_cost_dlxc;
+use crate::min_cost_dlxc::CostedSet;
+use crate::verify::Problem;
+
+// Saving and loading problems, solutions and search statistics, enabled by the serde feature.
+// Items, colors and options are written with the types they are made of, so any item type
+// that implements Serialize and Deserialize can be used.
+// Everything can be encoded as JSON, to be read by other tools, or with bincode,
+// which is more compact and faster to load for caching large instances.
+
+// A problem with everything needed to start a solver on it.
+// The options of problems without costs have cost 0.
+#[derive(Clone,PartialEq,Eq,Debug,Serialize,Deserialize)]
+pub struct Instance<P, S, C, W = usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub primary_items: Vec<P>,
+    pub secondary_items: Vec<S>,
+    pub colors: Vec<C>,
+    pub options: Vec<CostedSet<P, S, C, W>>
+}
+
+impl<P, S, C, W> Instance<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn new(options: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+        Instance { primary_items, secondary_items, colors, options }
+    }
+
+    pub fn from_dlxc(sets: Vec<Vec<Item<P, S, C>>>, primary_items: Vec<P>, secondary_items: Vec<S>, colors: Vec<C>) -> Self {
+        let options = sets
+            .into_iter()
+            .map(|set| (set, W::zero()))
+            .collect();
+        Instance { primary_items, secondary_items, colors, options }
+    }
+
+    // The options without their costs.
+    pub fn sets(&self) -> Vec<Vec<Item<P, S, C>>> {
+        self.options
+            .iter()
+            .map(|(set, _)| set.clone())
+            .collect()
+    }
+
+    pub fn dlxc_iter(&self) -> dlxc::DLXCIter<P, S, C> {
+        dlxc::dlxc_iter(self.sets(), self.primary_items.clone(), self.secondary_items.clone(), self.colors.clone())
+    }
+
+    pub fn min_cost_dlxc_iter(&self) -> min_cost_dlxc::DLXCIter<P, S, C, W> {
+        min_cost_dlxc::min_cost_dlxc_iter(self.options.clone(), self.primary_items.clone(),
+                                          self.secondary_items.clone(), self.colors.clone())
+    }
+}
+
+impl<P, S, C> Instance<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    // The problem for checking solutions with verify.rs.
+    pub fn problem(&self) -> Problem<P, S, C> {
+        Problem::new(self.options.clone(), self.primary_items.clone(), self.secondary_items.clone())
+    }
+}
+
+// What a search did: how many solutions it found, whether it explored the whole search tree
+// and how long it took, with the cost of the best solution for min_cost_dlxc.
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Serialize,Deserialize)]
+pub struct Stats<W = usize> {
+    pub solutions: usize,
+    pub complete: bool,
+    pub millis: u64,
+    pub cost: Option<W>
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("The value cannot be written as JSON.")
+}
+
+pub fn to_json_pretty<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("The value cannot be written as JSON.")
+}
+
+pub fn from_json<T: DeserializeOwned>(text: &str) -> T {
+    serde_json::from_str(text).unwrap_or_else(|err| panic!("Invalid JSON: {}.", err))
+}
+
+// Integers are written in as few bytes as they need, which keeps item indices small.
+pub fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::DefaultOptions::new().serialize(value).expect("The value cannot be encoded.")
+}
+
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> T {
+    bincode::DefaultOptions::new().deserialize(bytes).unwrap_or_else(|err| panic!("Invalid encoding: {}.", err))
+}
+
+// Files ending with .json are JSON, all others are in the binary encoding.
+pub fn save<T: Serialize>(value: &T, filename: &str) {
+    let result = if filename.ends_with(".json") {
+        fs::write(filename, to_json(value))
+    }
+    else {
+        fs::write(filename, to_bytes(value))
+    };
+    result.unwrap_or_else(|err| panic!("Could not write {}: {}", filename, err));
+}
+
+pub fn load<T: DeserializeOwned>(filename: &str) -> T {
+    let bytes = fs::read(filename).unwrap_or_else(|err| panic!("Could not read {}: {}", filename, err));
+    if filename.ends_with(".json") {
+        let text = String::from_utf8(bytes).unwrap_or_else(|_| panic!("{} is not a text file.", filename));
+        from_json(&text)
+    }
+    else {
+        from_bytes(&bytes)
+    }
+}
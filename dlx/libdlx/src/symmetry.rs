@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+// A group of permutations of the options that maps solutions to solutions, like the
+// rotations and reflections of a board. With it, DLXIter only enumerates the solution
+// that is the smallest of its orbit, comparing the sorted option indices, and tells
+// how many solutions it stands for. All elements of the group are kept, so it should be small.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Symmetry {
+    // every element maps option i to element[i], the first one is the identity
+    elements: Vec<Vec<usize>>
+}
+
+// What is known about an option in a partial solution.
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub(crate) enum Choice {
+    Chosen,
+    Excluded,
+    Open
+}
+
+impl Symmetry {
+    // The group generated by the permutations of the option indices.
+    pub fn from_option_permutations(option_count: usize, generators: Vec<Vec<usize>>) -> Self {
+        for generator in &generators {
+            let mut images = vec![false; option_count];
+            for &image in generator {
+                if image >= option_count || images[image] {
+                    panic!("{:?} is not a permutation of {} options.", generator, option_count);
+                }
+                images[image] = true;
+            }
+            if images.contains(&false) {
+                panic!("{:?} is not a permutation of {} options.", generator, option_count);
+            }
+        }
+
+        let identity: Vec<usize> = (0..option_count).collect();
+        let mut seen = HashSet::new();
+        seen.insert(identity.clone());
+        let mut elements = vec![identity];
+        let mut i = 0;
+        while i < elements.len() {
+            for generator in &generators {
+                let element: Vec<usize> = elements[i]
+                    .iter()
+                    .map(|&option| generator[option])
+                    .collect();
+                if seen.insert(element.clone()) {
+                    elements.push(element);
+                }
+            }
+            i += 1;
+        }
+
+        Symmetry { elements }
+    }
+
+    // The group generated by maps of the items. Every map has to take each option
+    // to one of the options, which are compared as sets of items.
+    pub fn from_item_maps<T>(sets: &[Vec<T>], maps: &[&dyn Fn(T) -> T]) -> Self
+    where
+    T: Eq + Copy + std::fmt::Debug {
+        let mut items = Vec::new();
+        let mut key = |set: &mut dyn Iterator<Item = T>| {
+            let mut key: Vec<usize> = set
+                .map(|item| match items.iter().position(|&other| other == item) {
+                    Some(i) => i,
+                    None => {
+                        items.push(item);
+                        items.len() - 1
+                    }
+                })
+                .collect();
+            key.sort_unstable();
+            key.dedup();
+            key
+        };
+
+        // identical options are mapped to each other in order
+        let mut options: HashMap<Vec<usize>, Vec<usize>> = HashMap::new();
+        let mut keys = Vec::with_capacity(sets.len());
+        for (i, set) in sets.iter().enumerate() {
+            let set_key = key(&mut set.iter().cloned());
+            options.entry(set_key.clone()).or_default().push(i);
+            keys.push(set_key);
+        }
+
+        let generators = maps
+            .iter()
+            .map(|map| sets
+                .iter()
+                .enumerate()
+                .map(|(i, set)| {
+                    let copy = options[&keys[i]]
+                        .iter()
+                        .position(|&other| other == i)
+                        .unwrap();
+                    let image = key(&mut set.iter().map(|&item| map(item)));
+                    options
+                        .get(&image)
+                        .map(|images| images[copy])
+                        .unwrap_or_else(|| panic!("The map takes the option {:?} to a set that is not an option.", set))
+                })
+                .collect())
+            .collect();
+        Symmetry::from_option_permutations(sets.len(), generators)
+    }
+
+    // The number of elements of the group.
+    pub fn order(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn option_count(&self) -> usize {
+        self.elements[0].len()
+    }
+
+    // False if the partial solution cannot be completed to the smallest solution of its orbit.
+    // Each element is compared option by option, as long as both sides are known.
+    pub(crate) fn may_lead(&self, choice: impl Fn(usize) -> Choice) -> bool {
+        self.elements
+            .iter()
+            .skip(1)
+            .all(|element| {
+                for (option, &image) in element.iter().enumerate() {
+                    if option == image {
+                        continue
+                    }
+                    match (choice(option), choice(image)) {
+                        (Choice::Chosen, Choice::Chosen) | (Choice::Excluded, Choice::Excluded) => {},
+                        (Choice::Excluded, Choice::Chosen) => return false,
+                        _ => return true
+                    }
+                }
+                true
+            })
+    }
+
+    // The size of the orbit of a solution, or None if it is not the smallest solution of its orbit.
+    pub(crate) fn orbit_size(&self, chosen: &[bool]) -> Option<usize> {
+        let mut stabilizer = 0;
+        for element in &self.elements {
+            match element
+                .iter()
+                .enumerate()
+                .find(|&(option, &image)| chosen[option] != chosen[image]) {
+                Some((option, _)) if !chosen[option] => return None,
+                Some(_) => {},
+                None => stabilizer += 1
+            }
+        }
+        Some(self.order() / stabilizer)
+    }
+}
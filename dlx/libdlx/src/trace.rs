@@ -0,0 +1,246 @@
+use std::fmt::Debug;
+use std::fmt::Write;
+
+// A record of the search tree that a solver explored, for looking at how an encoding
+// and a column choice shape the search. The solvers only record it when it is
+// switched on with their with_trace method. Items and options are kept as their
+// Debug output, so a trace does not depend on the types of the problem.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Trace {
+    events: Vec<TraceEvent>,
+    // the solutions found below every option that is being tried, and above all of them
+    solutions: Vec<usize>
+}
+
+// The depth of an event is the number of options chosen above it.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub enum TraceEvent {
+    // the item that the options at this depth are tried for, with its number of options
+    Choose { depth: usize, item: String, branching: usize },
+    Try { depth: usize, option: String },
+    // a solution with depth options
+    Solution { depth: usize },
+    // the solver gave up on the options at this depth, because of a bound or a symmetry
+    Prune { depth: usize },
+    // the option at this depth is done, with the number of solutions found with it
+    Leave { depth: usize, solutions: usize }
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace { events: Vec::new(), solutions: vec![0] }
+    }
+
+    pub fn solution_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, TraceEvent::Solution { .. }))
+            .count()
+    }
+
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    fn depth(&self) -> usize {
+        self.solutions.len() - 1
+    }
+
+    pub(crate) fn choose(&mut self, item: &impl Debug, branching: usize) {
+        let depth = self.depth();
+        self.events.push(TraceEvent::Choose { depth, item: format!("{:?}", item), branching });
+    }
+
+    pub(crate) fn try_option<T: Debug>(&mut self, option: &[T]) {
+        let depth = self.depth();
+        let option = option
+            .iter()
+            .map(|item| format!("{:?}", item))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.events.push(TraceEvent::Try { depth, option });
+        self.solutions.push(0);
+    }
+
+    pub(crate) fn solution(&mut self) {
+        let depth = self.depth();
+        self.events.push(TraceEvent::Solution { depth });
+        *self.solutions.last_mut().unwrap() += 1;
+    }
+
+    pub(crate) fn prune(&mut self) {
+        let depth = self.depth();
+        self.events.push(TraceEvent::Prune { depth });
+    }
+
+    pub(crate) fn leave(&mut self) {
+        // a trace that was switched on in the middle of a search does not know the levels above it
+        if self.solutions.len() > 1 {
+            let solutions = self.solutions.pop().unwrap();
+            *self.solutions.last_mut().unwrap() += solutions;
+            let depth = self.depth();
+            self.events.push(TraceEvent::Leave { depth, solutions });
+        }
+    }
+
+    // The events as a JSON array with one object per line.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[\n");
+        for (i, event) in self.events.iter().enumerate() {
+            let separator = if i + 1 < self.events.len() { "," } else { "" };
+            let object = match event {
+                TraceEvent::Choose { depth, item, branching } =>
+                    format!("{{\"event\": \"choose\", \"depth\": {}, \"item\": {}, \"branching\": {}}}",
+                            depth, json_string(item), branching),
+                TraceEvent::Try { depth, option } =>
+                    format!("{{\"event\": \"try\", \"depth\": {}, \"option\": {}}}", depth, json_string(option)),
+                TraceEvent::Solution { depth } =>
+                    format!("{{\"event\": \"solution\", \"depth\": {}}}", depth),
+                TraceEvent::Prune { depth } =>
+                    format!("{{\"event\": \"prune\", \"depth\": {}}}", depth),
+                TraceEvent::Leave { depth, solutions } =>
+                    format!("{{\"event\": \"leave\", \"depth\": {}, \"solutions\": {}}}", depth, solutions)
+            };
+            writeln!(json, "  {}{}", object, separator).unwrap();
+        }
+        json.push_str("]\n");
+        json
+    }
+
+    // The search tree in Graphviz DOT. Every node is an option that was tried, labeled with
+    // the item chosen below it and its number of options. Subtrees with solutions are green,
+    // pruned nodes gray. Nodes deeper than max_depth or after the first max_nodes are left out
+    // and counted at their parent.
+    pub fn to_dot(&self, max_depth: usize, max_nodes: usize) -> String {
+        let mut nodes = vec![DotNode::new(String::from("root"))];
+        let mut edges = Vec::new();
+        let mut path = vec![Some(0)];
+        for event in &self.events {
+            let current = path.last().cloned().flatten();
+            match event {
+                TraceEvent::Choose { item, branching, .. } => {
+                    if let Some(node) = current {
+                        nodes[node].choice = Some((item.clone(), *branching));
+                    }
+                },
+                TraceEvent::Try { depth, option } => {
+                    match current {
+                        Some(parent) if *depth < max_depth && nodes.len() < max_nodes => {
+                            nodes.push(DotNode::new(option.clone()));
+                            edges.push((parent, nodes.len() - 1));
+                            path.push(Some(nodes.len() - 1));
+                        },
+                        _ => {
+                            if let Some(parent) = current {
+                                nodes[parent].omitted += 1;
+                            }
+                            path.push(None);
+                        }
+                    }
+                },
+                TraceEvent::Solution { .. } => {
+                    if let Some(node) = current {
+                        nodes[node].solution = true;
+                        nodes[node].solutions += 1;
+                    }
+                },
+                TraceEvent::Prune { .. } => {
+                    if let Some(node) = current {
+                        nodes[node].pruned = true;
+                    }
+                },
+                TraceEvent::Leave { solutions, .. } => {
+                    if path.len() > 1 {
+                        if let Some(Some(node)) = path.pop() {
+                            nodes[node].solutions = *solutions;
+                        }
+                    }
+                }
+            }
+        }
+        nodes[0].solutions = self.solution_count();
+
+        let mut dot = String::from("digraph search {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for (i, node) in nodes.iter().enumerate() {
+            let mut label = node.label.clone();
+            if let Some((item, branching)) = &node.choice {
+                label.push_str(&format!("\n{}: {}", item, branching));
+            }
+            if node.solutions > 0 {
+                label.push_str(&format!("\n{} solutions", node.solutions));
+            }
+            let style = if node.solution {
+                ", style=filled, fillcolor=palegreen"
+            }
+            else if node.solutions > 0 {
+                ", color=green"
+            }
+            else if node.pruned {
+                ", color=gray, fontcolor=gray"
+            }
+            else {
+                ""
+            };
+            writeln!(dot, "    n{} [label={}{}];", i, dot_string(&label), style).unwrap();
+            if node.omitted > 0 {
+                writeln!(dot, "    n{}_omitted [label=\"{} more\", shape=plaintext];", i, node.omitted).unwrap();
+                writeln!(dot, "    n{} -> n{}_omitted [style=dashed];", i, i).unwrap();
+            }
+        }
+        for (parent, child) in edges {
+            writeln!(dot, "    n{} -> n{};", parent, child).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Trace::new()
+    }
+}
+
+struct DotNode {
+    label: String,
+    choice: Option<(String, usize)>,
+    solutions: usize,
+    solution: bool,
+    pruned: bool,
+    omitted: usize
+}
+
+impl DotNode {
+    fn new(label: String) -> Self {
+        DotNode { label, choice: None, solutions: 0, solution: false, pruned: false, omitted: 0 }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn dot_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
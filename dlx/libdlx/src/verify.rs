@@ -0,0 +1,502 @@
+use std::fmt;
+use crate::dlxc::Item;
+use crate::dlxc::add_color;
+use crate::dlxc::same_colors;
+use crate::dlxc::Solution as DLXCSolution;
+use crate::min_cost_dlxc::CostedSet;
+use crate::min_cost_dlxc::Solution as MinCostSolution;
+use crate::cost::Cost;
+
+// The options matching the rows of a solution, and the rows that match none or several of them.
+pub type ResolvedRows<P, S, C, W = usize> = (Vec<usize>, Vec<Violation<P, S, C, W>>);
+
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub enum Violation<P, S, C, W = usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    // an option index that is not part of the problem
+    UnknownOption(usize),
+    // a row that does not match any (unused) option of the problem
+    UnmatchedRow(Vec<Item<P, S, C>>),
+    // a row that matches several options, which differ in their items or costs
+    AmbiguousRow(Vec<Item<P, S, C>>, Vec<usize>),
+    // an option that refers to an item missing from the item lists
+    UnknownItem(usize, Item<P, S, C>),
+    PrimaryUncovered(P),
+    PrimaryCoveredMultiple(P, Vec<usize>),
+    SecondaryCoveredMultiple(S, Vec<usize>),
+    ColorConflict(S, Vec<(usize, C)>),
+    // options that disagree on the colors of a primary item, None for covering it without a color
+    PrimaryColorConflict(P, Vec<(usize, Option<C>)>),
+    ColorMismatch {
+        item: S,
+        reported: Vec<C>,
+        actual: Vec<C>
+    },
+    CostMismatch {
+        reported: W,
+        actual: W
+    }
+}
+
+impl<P, S, C, W> fmt::Display for Violation<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnknownOption(option) =>
+                write!(f, "option {} does not exist", option),
+            Violation::UnmatchedRow(row) =>
+                write!(f, "row {:?} does not match any option", row),
+            Violation::AmbiguousRow(row, options) =>
+                write!(f, "row {:?} matches several options {:?}", row, options),
+            Violation::UnknownItem(option, item) =>
+                write!(f, "option {} contains unknown item {:?}", option, item),
+            Violation::PrimaryUncovered(item) =>
+                write!(f, "primary item {:?} is not covered", item),
+            Violation::PrimaryCoveredMultiple(item, options) =>
+                write!(f, "primary item {:?} is covered by options {:?}", item, options),
+            Violation::SecondaryCoveredMultiple(item, options) =>
+                write!(f, "secondary item {:?} is covered by options {:?}", item, options),
+            Violation::ColorConflict(item, colors) =>
+                write!(f, "secondary item {:?} gets conflicting colors {:?}", item, colors),
+            Violation::PrimaryColorConflict(item, colors) =>
+                write!(f, "primary item {:?} gets conflicting colors {:?}", item, colors),
+            Violation::ColorMismatch { item, reported, actual } =>
+                write!(f, "secondary item {:?} is reported as {:?}, but the options give {:?}", item, reported, actual),
+            Violation::CostMismatch { reported, actual } =>
+                write!(f, "reported cost is {:?}, but the options cost {:?}", reported, actual),
+        }
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Report<P, S, C, W = usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub options: Vec<usize>,
+    pub violations: Vec<Violation<P, S, C, W>>
+}
+
+impl<P, S, C, W> Report<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl<P, S, C, W> Violation<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    // Renames the items and colors, e.g. to turn indices back into readable names.
+    pub fn map_names<Q, T, D>(self, primary: impl Fn(P) -> Q, secondary: impl Fn(S) -> T,
+                              color: impl Fn(C) -> D) -> Violation<Q, T, D, W>
+    where
+    Q: Eq + Copy + std::fmt::Debug,
+    T: Eq + Copy + std::fmt::Debug,
+    D: Eq + Copy + std::fmt::Debug {
+        let map_item = |item| match item {
+            Item::Primary(p) => Item::Primary(primary(p)),
+            Item::Secondary(s) => Item::Secondary(secondary(s)),
+            Item::ColoredSecondary(s, c) => Item::ColoredSecondary(secondary(s), color(c)),
+            Item::ColoredPrimary(p, c) => Item::ColoredPrimary(primary(p), color(c)),
+            Item::PrimaryColor(p, c) => Item::PrimaryColor(primary(p), color(c)),
+        };
+        match self {
+            Violation::UnknownOption(option) => Violation::UnknownOption(option),
+            Violation::UnmatchedRow(row) => Violation::UnmatchedRow(row.into_iter().map(map_item).collect()),
+            Violation::AmbiguousRow(row, options) =>
+                Violation::AmbiguousRow(row.into_iter().map(map_item).collect(), options),
+            Violation::UnknownItem(option, item) => Violation::UnknownItem(option, map_item(item)),
+            Violation::PrimaryUncovered(item) => Violation::PrimaryUncovered(primary(item)),
+            Violation::PrimaryCoveredMultiple(item, options) =>
+                Violation::PrimaryCoveredMultiple(primary(item), options),
+            Violation::SecondaryCoveredMultiple(item, options) =>
+                Violation::SecondaryCoveredMultiple(secondary(item), options),
+            Violation::ColorConflict(item, colors) => Violation::ColorConflict(
+                secondary(item),
+                colors.into_iter().map(|(option, c)| (option, color(c))).collect()
+            ),
+            Violation::PrimaryColorConflict(item, colors) => Violation::PrimaryColorConflict(
+                primary(item),
+                colors.into_iter().map(|(option, c)| (option, c.map(&color))).collect()
+            ),
+            Violation::ColorMismatch { item, reported, actual } => Violation::ColorMismatch {
+                item: secondary(item),
+                reported: reported.into_iter().map(&color).collect(),
+                actual: actual.into_iter().map(&color).collect()
+            },
+            Violation::CostMismatch { reported, actual } => Violation::CostMismatch { reported, actual },
+        }
+    }
+}
+
+impl<P, S, C, W> Report<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn map_names<Q, T, D>(self, primary: impl Fn(P) -> Q, secondary: impl Fn(S) -> T,
+                              color: impl Fn(C) -> D) -> Report<Q, T, D, W>
+    where
+    Q: Eq + Copy + std::fmt::Debug,
+    T: Eq + Copy + std::fmt::Debug,
+    D: Eq + Copy + std::fmt::Debug {
+        Report {
+            options: self.options,
+            violations: self.violations
+                .into_iter()
+                .map(|violation| violation.map_names(&primary, &secondary, &color))
+                .collect()
+        }
+    }
+}
+
+impl<P, S, C, W> fmt::Display for Report<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            write!(f, "valid solution with options {:?}", self.options)
+        }
+        else {
+            write!(f, "{} violation(s) in solution with options {:?}", self.violations.len(), self.options)?;
+            for violation in &self.violations {
+                write!(f, "\n  {}", violation)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn same_name<P, S, C>(a: Item<P, S, C>, b: Item<P, S, C>) -> bool
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    match (a, b) {
+        (Item::Primary(a), Item::Primary(b)) => a == b,
+        (Item::Primary(a), Item::ColoredPrimary(b, _)) => a == b,
+        (Item::ColoredPrimary(a, _), Item::Primary(b)) => a == b,
+        (Item::ColoredPrimary(a, _), Item::ColoredPrimary(b, _)) => a == b,
+        (Item::PrimaryColor(a, _), Item::PrimaryColor(b, _)) => a == b,
+        (Item::Secondary(a), Item::Secondary(b)) => a == b,
+        (Item::Secondary(a), Item::ColoredSecondary(b, _)) => a == b,
+        (Item::ColoredSecondary(a, _), Item::Secondary(b)) => a == b,
+        (Item::ColoredSecondary(a, _), Item::ColoredSecondary(b, _)) => a == b,
+        _ => false
+    }
+}
+
+// An exact cover problem in the most general form that the solvers accept.
+// Options without costs are given cost 0.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Problem<P, S, C, W = usize>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    options: Vec<CostedSet<P, S, C, W>>,
+    primary_items: Vec<P>,
+    secondary_items: Vec<S>
+}
+
+impl<T> Problem<T, T, ()>
+where
+T: Eq + Copy + std::fmt::Debug {
+    pub fn from_dlx(sets: &[Vec<T>], primary_items: &[T], secondary_items: &[T]) -> Self {
+        let options = sets
+            .iter()
+            .map(|set| (to_items(set, primary_items), 0))
+            .collect();
+        Problem {
+            options,
+            primary_items: primary_items.to_vec(),
+            secondary_items: secondary_items.to_vec()
+        }
+    }
+}
+
+fn to_items<T>(set: &[T], primary_items: &[T]) -> Vec<Item<T, T, ()>>
+where
+T: Eq + Copy + std::fmt::Debug {
+    set.iter()
+        .map(|&item| if primary_items.contains(&item) {
+            Item::Primary(item)
+        }
+        else {
+            Item::Secondary(item)
+        })
+        .collect()
+}
+
+impl<P, S, C> Problem<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    pub fn from_dlxc(sets: &[Vec<Item<P, S, C>>], primary_items: &[P], secondary_items: &[S]) -> Self {
+        let options = sets
+            .iter()
+            .map(|set| (set.clone(), 0))
+            .collect();
+        Problem {
+            options,
+            primary_items: primary_items.to_vec(),
+            secondary_items: secondary_items.to_vec()
+        }
+    }
+}
+
+impl<P, S, C, W> Problem<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    pub fn new(options: Vec<CostedSet<P, S, C, W>>, primary_items: Vec<P>, secondary_items: Vec<S>) -> Self {
+        Problem { options, primary_items, secondary_items }
+    }
+
+    pub fn options(&self) -> &[CostedSet<P, S, C, W>] {
+        &self.options
+    }
+
+    pub fn primary_items(&self) -> &[P] {
+        &self.primary_items
+    }
+
+    pub fn secondary_items(&self) -> &[S] {
+        &self.secondary_items
+    }
+
+    // Finds the options that the rows of a solution were taken from.
+    // Solvers return rows rotated and with the final colors of the secondary items,
+    // so rows are matched by item names, preferring options with identical items.
+    // Options with the same items and cost are interchangeable. If a row matches several
+    // options that are not, it is reported as ambiguous instead of guessing.
+    pub fn resolve_rows(&self, rows: &[Vec<Item<P, S, C>>]) -> ResolvedRows<P, S, C, W> {
+        let mut used = vec![false; self.options.len()];
+        let mut options = Vec::new();
+        let mut violations = Vec::new();
+        for row in rows {
+            let candidates: Vec<usize> = self.options
+                .iter()
+                .enumerate()
+                .filter(|(i, (option, _))| !used[*i] && option.len() == row.len() && row
+                    .iter()
+                    .all(|&item| option.iter().any(|&other| same_name(item, other))))
+                .map(|(i, _)| i)
+                .collect();
+            let exact: Vec<usize> = candidates
+                .iter()
+                .cloned()
+                .filter(|&i| row.iter().all(|item| self.options[i].0.contains(item)))
+                .collect();
+            let choices = if exact.is_empty() { candidates } else { exact };
+
+            match choices.first() {
+                Some(&i) if choices.iter().all(|&j| self.interchangeable(i, j)) => {
+                    used[i] = true;
+                    options.push(i);
+                },
+                Some(_) => violations.push(Violation::AmbiguousRow(row.clone(), choices)),
+                None => violations.push(Violation::UnmatchedRow(row.clone()))
+            }
+        }
+
+        (options, violations)
+    }
+
+    fn interchangeable(&self, a: usize, b: usize) -> bool {
+        let (a_items, a_cost) = &self.options[a];
+        let (b_items, b_cost) = &self.options[b];
+        a_cost == b_cost && a_items.len() == b_items.len() && a_items.iter().all(|item| b_items.contains(item))
+    }
+
+    // Checks a set of option indices against the problem.
+    // The reported colors and cost are only checked when given.
+    // The costs are added up in the order of the options, like the solvers add up the costs of their rows,
+    // so that floating point costs come out the same.
+    pub fn verify_options(&self, options: &[usize], reported_colors: Option<&[(S, Option<C>)]>,
+                          reported_cost: Option<W>) -> Report<P, S, C, W> {
+        let mut violations = Vec::new();
+        let mut primary_covers = vec![Vec::new(); self.primary_items.len()];
+        let mut primary_colors = vec![Vec::new(); self.primary_items.len()];
+        let mut secondary_covers = vec![Vec::new(); self.secondary_items.len()];
+        let mut cost = W::zero();
+        for &option in options {
+            if option >= self.options.len() {
+                violations.push(Violation::UnknownOption(option));
+                continue;
+            }
+
+            let (items, option_cost) = &self.options[option];
+            cost = cost.plus(*option_cost);
+            for &item in items {
+                let position = match item {
+                    Item::Primary(p) | Item::ColoredPrimary(p, _) | Item::PrimaryColor(p, _) => self.primary_items
+                        .iter()
+                        .position(|&name| name == p)
+                        .map(|i| {
+                            // an option covers its primary items once, whatever colors it gives them
+                            if !matches!(item, Item::PrimaryColor(_, _)) && primary_covers[i].last() != Some(&option) {
+                                primary_covers[i].push(option);
+                            }
+                            let color = match item {
+                                Item::ColoredPrimary(_, c) | Item::PrimaryColor(_, c) => Some(c),
+                                _ => None
+                            };
+                            add_color(&mut primary_colors[i], option, color);
+                        }),
+                    Item::Secondary(s) => self.secondary_items
+                        .iter()
+                        .position(|&name| name == s)
+                        .map(|i| add_color(&mut secondary_covers[i], option, None)),
+                    Item::ColoredSecondary(s, c) => self.secondary_items
+                        .iter()
+                        .position(|&name| name == s)
+                        .map(|i| add_color(&mut secondary_covers[i], option, Some(c))),
+                };
+                if position.is_none() {
+                    violations.push(Violation::UnknownItem(option, item));
+                }
+            }
+        }
+
+        for ((&item, covers), colors) in self.primary_items.iter().zip(&primary_covers).zip(&primary_colors) {
+            if covers.is_empty() {
+                violations.push(Violation::PrimaryUncovered(item));
+            }
+            else if covers.len() > 1 {
+                violations.push(Violation::PrimaryCoveredMultiple(item, covers.clone()));
+            }
+
+            // only primary items that some option colors have to agree
+            if colors.iter().any(|(_, item_colors)| !item_colors.is_empty())
+                && colors.iter().any(|(_, item_colors)| !same_colors(item_colors, &colors[0].1)) {
+                let colors = colors
+                    .iter()
+                    .flat_map(|(option, item_colors)| if item_colors.is_empty() {
+                        vec![(*option, None)]
+                    }
+                    else {
+                        item_colors.iter().map(|&c| (*option, Some(c))).collect()
+                    })
+                    .collect();
+                violations.push(Violation::PrimaryColorConflict(item, colors));
+            }
+        }
+
+        let mut actual_colors = Vec::with_capacity(self.secondary_items.len());
+        for (&item, covers) in self.secondary_items.iter().zip(&secondary_covers) {
+            if covers.len() > 1 && covers.iter().any(|(_, colors)| colors.is_empty()) {
+                let options = covers.iter().map(|&(option, _)| option).collect();
+                violations.push(Violation::SecondaryCoveredMultiple(item, options));
+            }
+            else if covers.iter().any(|(_, colors)| !same_colors(colors, &covers[0].1)) {
+                let colors = covers
+                    .iter()
+                    .flat_map(|(option, colors)| colors.iter().map(move |&c| (*option, c)))
+                    .collect();
+                violations.push(Violation::ColorConflict(item, colors));
+            }
+            actual_colors.push((item, covers.first().map(|(_, colors)| colors.clone()).unwrap_or_default()));
+        }
+
+        if let Some(reported_colors) = reported_colors {
+            // an item with several colors is reported once per color
+            let mut reported_items: Vec<(S, Vec<C>)> = Vec::new();
+            for &(item, color) in reported_colors {
+                match reported_items.iter_mut().find(|(other, _)| *other == item) {
+                    Some((_, colors)) => colors.extend(color),
+                    None => reported_items.push((item, color.into_iter().collect()))
+                }
+            }
+
+            for (item, reported) in reported_items {
+                let actual = actual_colors
+                    .iter()
+                    .find(|(name, _)| *name == item)
+                    .map(|(_, colors)| colors.clone())
+                    .unwrap_or_default();
+                if !same_colors(&reported, &actual) {
+                    violations.push(Violation::ColorMismatch { item, reported, actual });
+                }
+            }
+        }
+
+        if let Some(reported) = reported_cost {
+            if reported != cost {
+                violations.push(Violation::CostMismatch { reported, actual: cost });
+            }
+        }
+
+        Report {
+            options: options.to_vec(),
+            violations
+        }
+    }
+
+    pub fn verify_rows(&self, rows: &[Vec<Item<P, S, C>>], reported_colors: Option<&[(S, Option<C>)]>,
+                       reported_cost: Option<W>) -> Report<P, S, C, W> {
+        let (options, mut violations) = self.resolve_rows(rows);
+        let mut report = self.verify_options(&options, reported_colors, reported_cost);
+        violations.append(&mut report.violations);
+        report.violations = violations;
+        report
+    }
+}
+
+pub fn verify_dlx_solution<T>(sets: &[Vec<T>], primary_items: &[T], secondary_items: &[T],
+                              solution: &[Vec<T>]) -> Report<T, T, ()>
+where
+T: Eq + Copy + std::fmt::Debug {
+    let rows: Vec<_> = solution
+        .iter()
+        .map(|row| to_items(row, primary_items))
+        .collect();
+    Problem::from_dlx(sets, primary_items, secondary_items).verify_rows(&rows, None, None)
+}
+
+pub fn verify_dlxc_solution<P, S, C>(sets: &[Vec<Item<P, S, C>>], primary_items: &[P], secondary_items: &[S],
+                                     solution: &DLXCSolution<P, S, C>) -> Report<P, S, C>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug {
+    let (rows, colors) = solution;
+    Problem::from_dlxc(sets, primary_items, secondary_items).verify_rows(rows, Some(colors), None)
+}
+
+pub fn verify_min_cost_solution<P, S, C, W>(sets: &[CostedSet<P, S, C, W>], primary_items: &[P], secondary_items: &[S],
+                                            solution: &MinCostSolution<P, S, C, W>) -> Report<P, S, C, W>
+where
+P: Eq + Copy + std::fmt::Debug,
+S: Eq + Copy + std::fmt::Debug,
+C: Eq + Copy + std::fmt::Debug,
+W: Cost {
+    Problem::new(sets.to_vec(), primary_items.to_vec(), secondary_items.to_vec())
+        .verify_rows(&solution.rows, Some(&solution.colors), Some(solution.cost))
+}
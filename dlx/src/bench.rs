@@ -0,0 +1,337 @@
+use std::any::Any;
+use std::fs;
+use std::io::Write;
+use std::panic;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use std::thread::spawn;
+use std::time::Duration;
+use std::time::Instant;
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use libdlx::random;
+use crate::cli::Format;
+use crate::cli::Options;
+use crate::cli::QueensAlgorithm;
+use crate::cli::VcAlgorithm;
+use crate::solve::outcome_of;
+use crate::solve::queens_first_solver;
+use crate::solve::vertex_cover_solver;
+use crate::graph::read_graph_file;
+use crate::vertex_cover;
+
+// Benchmarks described by a JSON config file, for example
+//   {
+//     "time_limit_ms": 60000,
+//     "seed": 1,
+//     "sets": [
+//       {"name": "queens", "problem": {"queens": {"from": 5, "to": 80, "step": 5}},
+//        "algorithms": ["dlx", "dfs"], "repeat": 10},
+//       {"name": "dense_02", "problem": {"vc": "../vc/instances/dense/02"}, "algorithms": ["pure", "reduce"]}
+//     ]
+//   }
+// Every set runs each of its algorithms repeat times on each of its instances, which are the board sizes
+// of a range for queens and the graphs in a directory, relative to the config file, for vc.
+// The graphs can be in any of the formats of graph.rs, and gzipped.
+// The algorithms are named as in the --algorithm option of the queens and vc commands.
+//
+// Every run finds the first queens solution or a vertex cover within the time limit, which defaults
+// to a minute, and gets its own seed: the base seed plus the number of the run. Without a base seed
+// the runs are seeded randomly, so any run can be repeated from its row.
+// The runs share the worker threads, and their rows are written as soon as they finish.
+
+static DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(60);
+pub static CSV_HEADER: &str = "set,instance,algorithm,repetition,seed,n,m,outcome,millis,cost,message";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    time_limit_ms: Option<u64>,
+    seed: Option<u64>,
+    sets: Vec<InstanceSet>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstanceSet {
+    name: String,
+    problem: ProblemSet,
+    algorithms: Vec<String>,
+    #[serde(default = "one")]
+    repeat: usize
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProblemSet {
+    Queens(Range),
+    Vc(String)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Range {
+    from: usize,
+    to: usize,
+    #[serde(default = "one")]
+    step: usize
+}
+
+#[derive(Clone)]
+enum Task {
+    Queens(usize, QueensAlgorithm),
+    Vc(PathBuf, VcAlgorithm)
+}
+
+struct Run {
+    set: String,
+    instance: String,
+    algorithm: String,
+    repetition: usize,
+    seed: u64,
+    task: Task
+}
+
+// One run, as report.rs reads it back.
+#[derive(Serialize,Deserialize,Clone,Debug)]
+pub struct Row {
+    pub set: String,
+    pub instance: String,
+    pub algorithm: String,
+    pub repetition: usize,
+    pub seed: u64,
+    pub n: Option<usize>,
+    pub m: Option<usize>,
+    // solved, infeasible, timeout, or error when the graph could not be read or the run panicked
+    pub outcome: String,
+    pub millis: u64,
+    pub cost: Option<usize>,
+    // why the run failed
+    pub message: Option<String>
+}
+
+fn parse_algorithm<A: ValueEnum>(set: &str, name: &str) -> Result<A, String> {
+    A::from_str(name, false).map_err(|_| format!("Set {} has an unknown algorithm {}.", set, name))
+}
+
+// The graphs of a directory in the order of their names.
+fn graph_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let error = |err: std::io::Error| format!("Could not read the directory {}: {}.", directory.display(), err);
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory).map_err(error)? {
+        let path = entry.map_err(error)?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// A board size or a graph file.
+enum Instance {
+    Board(usize),
+    Graph(PathBuf)
+}
+
+fn set_instances(set: &InstanceSet, base_directory: &Path) -> Result<Vec<(String, Instance)>, String> {
+    match &set.problem {
+        ProblemSet::Queens(range) => {
+            if range.step == 0 {
+                return Err(format!("Set {} has a step of 0.", set.name));
+            }
+            Ok((range.from..=range.to)
+                .step_by(range.step)
+                .map(|n| (n.to_string(), Instance::Board(n)))
+                .collect())
+        },
+        ProblemSet::Vc(directory) => Ok(graph_files(&base_directory.join(directory))?
+            .into_iter()
+            .map(|path| (path.file_name().unwrap().to_string_lossy().into_owned(), Instance::Graph(path)))
+            .collect())
+    }
+}
+
+// Everything that is wrong with the config is found here, before the first run starts.
+fn plan_runs(config: &Config, base_directory: &Path, base_seed: Option<u64>) -> Result<Vec<Run>, String> {
+    let mut runs = Vec::new();
+    for set in config.sets.iter() {
+        let instances = set_instances(set, base_directory)?;
+        for algorithm in set.algorithms.iter() {
+            for (name, instance) in instances.iter() {
+                let task = match instance {
+                    Instance::Board(n) => Task::Queens(*n, parse_algorithm(&set.name, algorithm)?),
+                    Instance::Graph(path) => Task::Vc(path.clone(), parse_algorithm(&set.name, algorithm)?)
+                };
+                for repetition in 0..set.repeat {
+                    let seed = match base_seed {
+                        Some(seed) => seed.wrapping_add(runs.len() as u64),
+                        None => rand::random()
+                    };
+                    runs.push(Run {
+                        set: set.name.clone(),
+                        instance: name.clone(),
+                        algorithm: algorithm.clone(),
+                        repetition,
+                        seed,
+                        task: task.clone()
+                    });
+                }
+            }
+        }
+    }
+    Ok(runs)
+}
+
+// The row of a run that has not finished, or failed.
+fn empty_row(run: &Run) -> Row {
+    Row {
+        set: run.set.clone(),
+        instance: run.instance.clone(),
+        algorithm: run.algorithm.clone(),
+        repetition: run.repetition,
+        seed: run.seed,
+        n: None,
+        m: None,
+        outcome: String::from("error"),
+        millis: 0,
+        cost: None,
+        message: None
+    }
+}
+
+// The message of a panic, which is a String when it has arguments and a &str otherwise.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or_else(|| String::from("the run panicked"), |message| message.to_string())
+    }
+}
+
+fn execute(run: &Run, time_limit: Duration) -> Row {
+    random::seed(run.seed);
+    let mut row = empty_row(run);
+    let outcome = match &run.task {
+        Task::Queens(n, algorithm) => {
+            row.n = Some(*n);
+            let start_time = Instant::now();
+            let solution = queens_first_solver(*algorithm)(*n, time_limit);
+            row.millis = start_time.elapsed().as_millis() as u64;
+            outcome_of(solution.is_some(), start_time, time_limit)
+        },
+        Task::Vc(path, algorithm) => {
+            let graph = match read_graph_file(Some(&path.to_string_lossy()), None) {
+                Ok((graph, _)) => graph,
+                Err(message) => {
+                    row.message = Some(message);
+                    return row
+                }
+            };
+            row.n = Some(graph.vertices);
+            row.m = Some(graph.edges.len());
+            let graph = vertex_cover::graph_from_edges(&graph.edges);
+            // only the search is timed, not reading the graph
+            let start_time = Instant::now();
+            let cover = vertex_cover_solver(*algorithm)(graph, time_limit);
+            row.millis = start_time.elapsed().as_millis() as u64;
+            row.cost = cover.as_ref().map(|cover| cover.len());
+            outcome_of(cover.is_some(), start_time, time_limit)
+        }
+    };
+    row.outcome = json!(outcome).as_str().unwrap().to_string();
+    row
+}
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+fn csv_row(row: &Row) -> String {
+    let optional = |value: Option<usize>| value.map_or(String::new(), |value| value.to_string());
+    [
+        csv_field(&row.set),
+        csv_field(&row.instance),
+        csv_field(&row.algorithm),
+        row.repetition.to_string(),
+        row.seed.to_string(),
+        optional(row.n),
+        optional(row.m),
+        row.outcome.clone(),
+        row.millis.to_string(),
+        optional(row.cost),
+        csv_field(row.message.as_deref().unwrap_or(""))
+    ].join(",")
+}
+
+// Returns an error for configs that cannot be read and results that cannot be written.
+pub fn run_bench(options: &Options, config_filename: &str, results: Option<&str>) -> Result<(), String> {
+    let text = fs::read_to_string(config_filename)
+        .map_err(|err| format!("Could not read {}: {}.", config_filename, err))?;
+    let config: Config = serde_json::from_str(&text)
+        .map_err(|err| format!("Invalid config {}: {}.", config_filename, err))?;
+    let base_directory = Path::new(config_filename).parent().unwrap_or_else(|| Path::new(""));
+    let time_limit = options.time_limit
+        .or_else(|| config.time_limit_ms.map(Duration::from_millis))
+        .unwrap_or(DEFAULT_TIME_LIMIT);
+    let runs = plan_runs(&config, base_directory, options.seed.or(config.seed))?;
+
+    let mut output: Box<dyn Write> = match results {
+        Some(filename) => Box::new(fs::File::create(filename)
+            .map_err(|err| format!("Could not create {}: {}.", filename, err))?),
+        None => Box::new(std::io::stdout())
+    };
+    if options.output == Format::Text {
+        writeln!(output, "{}", CSV_HEADER).expect("Could not write the results.");
+    }
+
+    let run_count = runs.len();
+    let runs = Arc::new(Mutex::new(runs.into_iter()));
+    let (tx, rx) = channel::<Row>();
+    let workers: Vec<_> = (0..options.threads.max(1))
+        .map(|_| {
+            let (runs, tx) = (runs.clone(), tx.clone());
+            spawn(move || loop {
+                let next = runs.lock().unwrap().next();
+                let run = match next {
+                    Some(run) => run,
+                    None => break
+                };
+                // a run that panics, which would be a bug in a solver, only fails its own row
+                let row = panic::catch_unwind(|| execute(&run, time_limit)).unwrap_or_else(|payload| Row {
+                    message: Some(panic_message(payload)),
+                    ..empty_row(&run)
+                });
+                tx.send(row).unwrap();
+            })
+        })
+        .collect();
+    drop(tx);
+
+    for (finished, row) in rx.iter().enumerate() {
+        let line = match options.output {
+            Format::Text => csv_row(&row),
+            Format::Json => serde_json::to_string(&row).unwrap()
+        };
+        writeln!(output, "{}", line).expect("Could not write the results.");
+        output.flush().expect("Could not write the results.");
+        eprintln!("{}/{} {} {} {} {}", finished + 1, run_count, row.set, row.instance, row.algorithm, row.outcome);
+    }
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    Ok(())
+}
@@ -0,0 +1,319 @@
+use std::time::Duration;
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
+use crate::graph::GraphFormat;
+use crate::solve::Mode;
+
+// The command line of the dlx binary. The options before the subcommands apply to all of them,
+// and can be given anywhere on the command line.
+
+static EXIT_CODES: &str = "Exit codes: 0 when a solution was found or the check passed, \
+1 when there is no solution or the check failed, 2 on usage errors and input files that cannot be read \
+and 3 when the time limit ran out.";
+
+#[derive(Parser,Debug)]
+#[command(name = "dlx", version, about = "Exact cover solvers based on dancing links", after_help = EXIT_CODES)]
+pub struct Cli {
+    #[command(flatten)]
+    pub options: Options,
+    #[command(subcommand)]
+    pub command: Command
+}
+
+#[derive(Args,Clone,Debug)]
+pub struct Options {
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = parse_seconds,
+          help = "Stop searching after this many seconds, fractions are allowed")]
+    pub time_limit: Option<Duration>,
+    #[arg(long, global = true, value_name = "N", default_value_t = 14, help = "Worker threads for bench and serve")]
+    pub threads: usize,
+    #[arg(long, global = true, help = "Seed for the randomized algorithms")]
+    pub seed: Option<u64>,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text,
+          help = "How results are printed, bench writes CSV as text and JSON lines as json")]
+    pub output: Format
+}
+
+impl Options {
+    pub fn time_limit(&self) -> Duration {
+        self.time_limit.unwrap_or(Duration::MAX)
+    }
+}
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    let seconds: f64 = text.parse().map_err(|_| format!("{} is not a number of seconds", text))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("{} is not a valid time limit", text))
+}
+
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum Format {
+    Text,
+    Json
+}
+
+// At most one of them, the first solution is searched for when none is given.
+#[derive(Args,Clone,Copy,Debug)]
+#[group(multiple = false)]
+pub struct ModeFlags {
+    #[arg(long, help = "Stop at the first solution, the default")]
+    pub first: bool,
+    #[arg(long, help = "Count the solutions")]
+    pub count: bool,
+    #[arg(long, help = "List all solutions")]
+    pub all: bool
+}
+
+impl ModeFlags {
+    pub fn mode(self) -> Mode {
+        if self.count {
+            Mode::Count
+        }
+        else if self.all {
+            Mode::All
+        }
+        else {
+            Mode::First
+        }
+    }
+}
+
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum QueensAlgorithm {
+    Dlx,
+    DlxRandom,
+    Dfs,
+    Links,
+    Cells
+}
+
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum SudokuAlgorithm {
+    Dlx,
+    Links,
+    Cells
+}
+
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum VcAlgorithm {
+    Pure,
+    Reduce,
+    Links,
+    Cells
+}
+
+// The fields of benchmark rows that report can group by.
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum Field {
+    Set,
+    Algorithm,
+    Instance,
+    N,
+    M
+}
+
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum TableFormat {
+    Markdown,
+    Csv,
+    Latex
+}
+
+// The lines that the commands printed before bench, which report reads.
+// With auto, 'n millis' and 'n unique total millis' are told apart by their number of fields,
+// but 'n m millis' and 'n count millis' are not, so those need vc or count.
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum Legacy {
+    Auto,
+    // n millis, from queens
+    Queens,
+    // n m millis, from vc
+    Vc,
+    // n count millis, from queens --count
+    Count,
+    // n unique total millis, from queens --unique
+    Unique
+}
+
+// A number of vertices, or a range of them written as FROM..TO, both included.
+#[derive(Clone,Copy,Debug)]
+pub struct Sizes {
+    pub from: usize,
+    pub to: usize
+}
+
+fn parse_sizes(text: &str) -> Result<Sizes, String> {
+    let number = |text: &str| text.parse::<usize>().map_err(|_| format!("{} is not a number of vertices", text));
+    match text.split_once("..") {
+        Some((from, to)) => {
+            let (from, to) = (number(from)?, number(to)?);
+            if from > to {
+                return Err(format!("the range {} is empty", text))
+            }
+            Ok(Sizes { from, to })
+        },
+        None => number(text).map(|n| Sizes { from: n, to: n })
+    }
+}
+
+#[derive(Subcommand,Debug)]
+pub enum GraphModel {
+    #[command(about = "Every edge with probability P")]
+    Gnp {
+        #[arg(value_parser = parse_sizes)]
+        n: Sizes,
+        p: f64
+    },
+    #[command(about = "M edges chosen uniformly")]
+    Gnm {
+        #[arg(value_parser = parse_sizes)]
+        n: Sizes,
+        m: usize
+    },
+    #[command(about = "A random graph where every vertex has D neighbors")]
+    Regular {
+        #[arg(value_parser = parse_sizes)]
+        n: Sizes,
+        d: usize
+    },
+    #[command(alias = "ba", about = "Preferential attachment, every new vertex gets K neighbors")]
+    BarabasiAlbert {
+        #[arg(value_parser = parse_sizes)]
+        n: Sizes,
+        k: usize
+    },
+    #[command(about = "A ROWS by COLUMNS grid")]
+    Grid {
+        rows: usize,
+        columns: usize,
+        #[arg(long, help = "Join the last row and column to the first ones")]
+        torus: bool
+    },
+    #[command(about = "A graph whose minimum vertex cover has K vertices, with the other edges from the cover taken with probability P")]
+    Planted {
+        #[arg(value_parser = parse_sizes)]
+        n: Sizes,
+        k: usize,
+        p: f64
+    }
+}
+
+#[derive(Args,Debug)]
+pub struct VcArgs {
+    #[arg(value_name = "FILE", help = "Read the graph from FILE instead of stdin")]
+    pub input: Option<String>,
+    #[arg(long, value_enum, help = "The format of the graph, detected from the file name or its first lines otherwise")]
+    pub format: Option<GraphFormat>,
+    #[arg(long, value_enum, default_value_t = VcAlgorithm::Pure,
+          help = "links and cells find some cover that is not always a minimum one")]
+    pub algorithm: VcAlgorithm,
+    #[arg(long, value_name = "FILE",
+          help = "Save the search of pure to FILE every minute and resume from it if it exists")]
+    pub checkpoint: Option<String>,
+    #[arg(long, value_name = "COVER", conflicts_with_all = ["encode", "trace"],
+          help = "Check the vertex cover in the file COVER, numbered like the graph file, instead of solving")]
+    pub verify: Option<String>,
+    #[arg(long, value_name = "FILE", conflicts_with = "trace",
+          help = "Save the encoding that pure searches to FILE, as JSON if it ends with .json and binary otherwise")]
+    pub encode: Option<String>,
+    #[arg(long, value_name = "FILE", help = "Write the search tree of pure to FILE, as DOT or JSON")]
+    pub trace: Option<String>
+}
+
+#[derive(Subcommand,Debug)]
+pub enum Command {
+    #[command(about = "Place n queens on an n by n board so that none attack each other")]
+    Queens {
+        n: usize,
+        #[arg(long, value_enum, default_value_t = QueensAlgorithm::Dlx)]
+        algorithm: QueensAlgorithm,
+        #[command(flatten)]
+        mode: ModeFlags,
+        #[arg(long, conflicts_with_all = ["first", "count", "all"],
+              help = "Count the solutions that are different up to rotations and reflections")]
+        unique: bool,
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["first", "count", "all", "unique"],
+              help = "Write the search tree of dlx to FILE, as DOT if it ends with .dot and as JSON otherwise")]
+        trace: Option<String>
+    },
+    #[command(about = "Solve a sudoku given as 81 digits, with 0 or . for empty cells")]
+    Sudoku {
+        #[arg(value_name = "FILE", help = "Read the puzzle from FILE instead of stdin")]
+        input: Option<String>,
+        #[arg(long, conflicts_with = "input", help = "The puzzle itself")]
+        puzzle: Option<String>,
+        #[arg(long, value_enum, default_value_t = SudokuAlgorithm::Dlx, help = "count and all need dlx")]
+        algorithm: SudokuAlgorithm,
+        #[command(flatten)]
+        mode: ModeFlags
+    },
+    #[command(about = "Find a minimum vertex cover of a graph in one of the formats of graph.rs, which can be gzipped")]
+    Vc(VcArgs),
+    #[command(about = "Solve an exact cover problem in the text format of Knuth's programs, or saved as .json or .bin")]
+    Xc {
+        #[arg(value_name = "FILE", help = "Read the problem from FILE instead of stdin")]
+        input: Option<String>,
+        #[command(flatten)]
+        mode: ModeFlags,
+        #[arg(long, conflicts_with_all = ["first", "count", "all"], help = "Find the cheapest solution")]
+        min_cost: bool,
+        #[arg(long, value_name = "SOLUTION", conflicts_with_all = ["diagnose", "preprocess", "encode"],
+              help = "Check the solution in the file SOLUTION instead of solving")]
+        verify: Option<String>,
+        #[arg(long, conflicts_with_all = ["preprocess", "encode"],
+              help = "Look for a smallest set of items that cannot be covered")]
+        diagnose: bool,
+        #[arg(long, conflicts_with = "encode",
+              help = "Print the problem with options and items removed that cannot matter. An option is only \
+                      dominated by one with the same items, and a primary item is only merged into one that is \
+                      in the same options")]
+        preprocess: bool,
+        #[arg(long, value_name = "FILE", help = "Save the problem to FILE, as .json, .bin or text")]
+        encode: Option<String>
+    },
+    #[command(about = "Run the benchmarks listed in a JSON config file in parallel, see bench.rs for the format")]
+    Bench {
+        #[arg(value_name = "CONFIG")]
+        config: String,
+        #[arg(long, value_name = "FILE", help = "Write the rows to FILE instead of stdout")]
+        results: Option<String>
+    },
+    #[command(about = "Summarize benchmark results in tables of times and timeout rates")]
+    Report {
+        #[arg(required = true, value_name = "FILE",
+              help = "Rows written by bench, or the lines of the other commands, see --legacy, with - for \
+                      timeouts. LABEL=FILE names the algorithm of the lines, which is the file name otherwise")]
+        inputs: Vec<String>,
+        #[arg(long, value_enum, default_value_t = Legacy::Auto,
+              help = "What the lines of files that are not written by bench hold")]
+        legacy: Legacy,
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Field::Set, Field::Algorithm, Field::N],
+              help = "The fields that make up a group")]
+        group_by: Vec<Field>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Markdown)]
+        format: TableFormat,
+        #[arg(long, value_delimiter = ',', default_values_t = [90, 99],
+              value_parser = clap::value_parser!(u32).range(1..=100), help = "Percentiles of the times to show")]
+        percentiles: Vec<u32>,
+        #[arg(long, num_args = 2, value_names = ["A", "B"],
+              help = "Put the times of algorithms A and B side by side, with the ratio of their medians")]
+        compare: Option<Vec<String>>
+    },
+    #[command(about = "Generate random graphs for vc, N can be a range FROM..TO of sizes")]
+    Generate {
+        #[command(subcommand)]
+        model: GraphModel,
+        #[arg(long, global = true, value_enum, default_value_t = GraphFormat::Hash, help = "The file format of the graphs")]
+        format: GraphFormat,
+        #[arg(long, global = true, default_value_t = 1, help = "Graphs for every size")]
+        count: usize,
+        #[arg(long, global = true, value_name = "DIR", help = "Write the graphs to files in DIR instead of stdout")]
+        out: Option<String>
+    },
+    #[command(about = "Solve JSON-line requests from stdin, or from the connections to a Unix socket")]
+    Serve {
+        #[arg(value_name = "SOCKET")]
+        socket: Option<String>
+    }
+}
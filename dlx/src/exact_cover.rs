@@ -0,0 +1,236 @@
+use std::fs;
+use serde::Serialize;
+use serde::Deserialize;
+use libdlx::dlxc::Item;
+use libdlx::serial;
+
+// Items and colors are numbered in the order of their first appearance,
+// primary items first, so the solvers can work with plain indices.
+pub type XCItem = Item<usize, usize, usize>;
+
+// An exact cover problem in the text format of Knuth's DLX programs.
+// Lines starting with '|' are comments. The first line lists the items,
+// with a '|' separating the primary items from the secondary ones.
+// Every following line is an option. Items in options can be colored with
+// 'item:color', more than once to give an item several colors, and '?item:color'
+// requires the color of a primary item without covering it.
+// A '$cost' token sets the cost of the option.
+// Problems can also be cached as JSON or in the binary encoding of serial.rs.
+#[derive(Clone,PartialEq,Eq,Debug,Serialize,Deserialize)]
+pub struct ExactCoverProblem {
+    pub item_names: Vec<String>,
+    pub primary_count: usize,
+    pub color_names: Vec<String>,
+    pub options: Vec<(Vec<XCItem>, usize)>
+}
+
+// A solution as stored on disk: one option per line as the list of its items,
+// or the indices of options in the problem on '#options i j ...' lines.
+// A '#cost n' line records the cost reported by the solver. The markers keep
+// options whose items are named like numbers or 'cost' from being misread.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct SavedSolution {
+    pub options: Vec<usize>,
+    pub rows: Vec<Vec<XCItem>>,
+    pub cost: Option<usize>
+}
+
+fn content_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('|'))
+}
+
+impl ExactCoverProblem {
+    // Errors name the line they are on, so that they can be shown to whoever wrote the problem.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = content_lines(text);
+        let (_, header) = lines.next().ok_or("The problem has no item line.")?;
+
+        let mut item_names = Vec::new();
+        let mut primary_count = None;
+        for token in header.split_whitespace() {
+            if token == "|" {
+                primary_count = Some(item_names.len());
+            }
+            else if item_names.iter().any(|name| name == token) {
+                return Err(format!("Item {} is listed twice.", token));
+            }
+            else {
+                item_names.push(token.to_owned());
+            }
+        }
+
+        let mut problem = ExactCoverProblem {
+            primary_count: primary_count.unwrap_or(item_names.len()),
+            item_names,
+            color_names: Vec::new(),
+            options: Vec::new()
+        };
+
+        for (line_number, line) in lines {
+            let mut option = Vec::new();
+            let mut cost = 0;
+            for token in line.split_whitespace() {
+                if let Some(cost_token) = token.strip_prefix('$') {
+                    cost = cost_token.parse()
+                        .map_err(|_| format!("Line {}: invalid cost {}.", line_number, token))?;
+                }
+                else {
+                    option.push(problem.parse_item(token, line_number, true)?);
+                }
+            }
+            problem.options.push((option, cost));
+        }
+
+        Ok(problem)
+    }
+
+    // Files ending with .json or .bin hold a problem saved with save, all others are in the text format.
+    pub fn read(filename: &str) -> Result<Self, String> {
+        if filename.ends_with(".json") || filename.ends_with(".bin") {
+            return Ok(serial::load(filename));
+        }
+        let text = fs::read_to_string(filename).map_err(|err| format!("Could not read {}: {}.", filename, err))?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, filename: &str) {
+        if filename.ends_with(".json") || filename.ends_with(".bin") {
+            serial::save(self, filename);
+        }
+        else {
+            let text = self.format(&self.primary_items(), &self.secondary_items(), &self.options);
+            fs::write(filename, text).unwrap_or_else(|err| panic!("Could not write {}: {}", filename, err));
+        }
+    }
+
+    // Parses 'item', 'item:color' or '?item:color'. New colors are only registered when reading the problem itself.
+    pub fn parse_item(&mut self, token: &str, line_number: usize, new_colors: bool) -> Result<XCItem, String> {
+        let (token, color_only) = match token.strip_prefix('?') {
+            Some(token) => (token, true),
+            None => (token, false)
+        };
+        let (name, color) = match token.split_once(':') {
+            Some((name, color)) => (name, Some(color)),
+            None => (token, None)
+        };
+
+        let index = self.item_names
+            .iter()
+            .position(|item| item == name)
+            .ok_or_else(|| format!("Line {}: unknown item {}.", line_number, name))?;
+
+        let color_index = match color.map(|color| (color, self.color_names.iter().position(|c| c == color))) {
+            Some((_, Some(color_index))) => Some(color_index),
+            Some((color, None)) if new_colors => {
+                self.color_names.push(color.to_owned());
+                Some(self.color_names.len() - 1)
+            },
+            Some((color, None)) => return Err(format!("Line {}: unknown color {}.", line_number, color)),
+            None => None
+        };
+
+        match (index < self.primary_count, color_index) {
+            (true, Some(color_index)) if color_only => Ok(Item::PrimaryColor(index, color_index)),
+            (_, _) if color_only => Err(format!("Line {}: ?{} needs a primary item and a color.", line_number, token)),
+            (true, Some(color_index)) => Ok(Item::ColoredPrimary(index, color_index)),
+            (true, None) => Ok(Item::Primary(index)),
+            (false, Some(color_index)) => Ok(Item::ColoredSecondary(index, color_index)),
+            (false, None) => Ok(Item::Secondary(index))
+        }
+    }
+
+    pub fn primary_items(&self) -> Vec<usize> {
+        (0..self.primary_count).collect()
+    }
+
+    pub fn secondary_items(&self) -> Vec<usize> {
+        (self.primary_count..self.item_names.len()).collect()
+    }
+
+    pub fn colors(&self) -> Vec<usize> {
+        (0..self.color_names.len()).collect()
+    }
+
+    pub fn format_item(&self, item: XCItem) -> String {
+        match item {
+            Item::Primary(i) | Item::Secondary(i) => self.item_names[i].clone(),
+            Item::ColoredPrimary(i, c) | Item::ColoredSecondary(i, c) => format!("{}:{}", self.item_names[i], self.color_names[c]),
+            Item::PrimaryColor(i, c) => format!("?{}:{}", self.item_names[i], self.color_names[c])
+        }
+    }
+
+    pub fn format_row(&self, row: &[XCItem]) -> String {
+        row.iter()
+            .map(|&item| self.format_item(item))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Writes the given items and options back in the text format.
+    pub fn format(&self, primary_items: &[usize], secondary_items: &[usize], options: &[(Vec<XCItem>, usize)]) -> String {
+        let mut header: Vec<&str> = primary_items
+            .iter()
+            .map(|&i| self.item_names[i].as_str())
+            .collect();
+        if !secondary_items.is_empty() {
+            header.push("|");
+            header.extend(secondary_items.iter().map(|&i| self.item_names[i].as_str()));
+        }
+
+        let mut text = header.join(" ");
+        text.push('\n');
+        for (option, cost) in options {
+            text.push_str(&self.format_row(option));
+            if *cost != 0 {
+                text.push_str(&format!(" ${}", cost));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl SavedSolution {
+    pub fn parse(text: &str, problem: &mut ExactCoverProblem) -> Result<Self, String> {
+        let mut solution = SavedSolution {
+            options: Vec::new(),
+            rows: Vec::new(),
+            cost: None
+        };
+
+        for (line_number, line) in content_lines(text) {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("#cost") => {
+                    let cost = tokens.collect::<Vec<_>>().join(" ");
+                    let cost = cost.parse()
+                        .map_err(|_| format!("Line {}: invalid cost {}.", line_number, cost))?;
+                    solution.cost = Some(cost);
+                },
+                Some("#options") => {
+                    for token in tokens {
+                        let index = token.parse::<usize>()
+                            .map_err(|_| format!("Line {}: invalid option index {}.", line_number, token))?;
+                        solution.options.push(index);
+                    }
+                },
+                _ => {
+                    let row = line.split_whitespace()
+                        .map(|token| problem.parse_item(token, line_number, false))
+                        .collect::<Result<_, _>>()?;
+                    solution.rows.push(row);
+                }
+            }
+        }
+
+        Ok(solution)
+    }
+
+    pub fn read(filename: &str, problem: &mut ExactCoverProblem) -> Result<Self, String> {
+        let text = fs::read_to_string(filename).map_err(|err| format!("Could not read {}: {}.", filename, err))?;
+        Self::parse(&text, problem)
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::seq::index;
+use crate::cli::GraphModel;
+use crate::graph::Graph;
+use crate::graph::GraphFormat;
+
+// Random graphs for the vertex cover experiments, replacing the networkx scripts in vc.
+// All of them draw from one generator, so the same seed gives the same graphs.
+
+// How many times random_regular starts over before it gives up.
+static REGULAR_ATTEMPTS: usize = 1000;
+
+fn check_probability(p: f64) {
+    if !(0.0..=1.0).contains(&p) {
+        panic!("The edge probability {} is not between 0 and 1.", p);
+    }
+}
+
+// Erdős–Rényi: every edge with probability p.
+pub fn gnp(n: usize, p: f64, rng: &mut StdRng) -> Graph {
+    check_probability(p);
+    let mut edges = Vec::new();
+    for u in 0..n {
+        for v in u + 1..n {
+            if rng.gen_bool(p) {
+                edges.push((u, v));
+            }
+        }
+    }
+    Graph::new(n, edges)
+}
+
+// Erdős–Rényi: m edges chosen uniformly from all n(n-1)/2.
+pub fn gnm(n: usize, m: usize, rng: &mut StdRng) -> Graph {
+    let pairs = n * n.saturating_sub(1) / 2;
+    if m > pairs {
+        panic!("A graph on {} vertices has at most {} edges, not {}.", n, pairs, m);
+    }
+    let mut chosen = index::sample(rng, pairs, m).into_vec();
+    chosen.sort_unstable();
+
+    // the pairs are numbered row by row, (0, 1), (0, 2), ..., (1, 2), ...
+    let mut edges = Vec::with_capacity(m);
+    let (mut u, mut row_start) = (0, 0);
+    for k in chosen {
+        while k >= row_start + (n - 1 - u) {
+            row_start += n - 1 - u;
+            u += 1;
+        }
+        edges.push((u, u + 1 + k - row_start));
+    }
+    Graph::new(n, edges)
+}
+
+// Pairs up d copies of every vertex at random, avoiding loops and parallel edges,
+// and starts over when the copies that are left cannot be paired.
+pub fn random_regular(n: usize, d: usize, rng: &mut StdRng) -> Graph {
+    if d >= n.max(1) || (n * d) % 2 == 1 {
+        panic!("There is no {}-regular graph on {} vertices.", d, n);
+    }
+    for _ in 0..REGULAR_ATTEMPTS {
+        let mut points: Vec<usize> = (0..n).flat_map(|v| std::iter::repeat_n(v, d)).collect();
+        let mut edges = BTreeSet::new();
+        let mut failures = 0;
+        while !points.is_empty() && failures < 100 * points.len() {
+            let i = rng.gen_range(0..points.len());
+            let j = rng.gen_range(0..points.len());
+            let (u, v) = (points[i].min(points[j]), points[i].max(points[j]));
+            if u == v || edges.contains(&(u, v)) {
+                failures += 1;
+                continue;
+            }
+            edges.insert((u, v));
+            points.swap_remove(i.max(j));
+            points.swap_remove(i.min(j));
+        }
+        if points.is_empty() {
+            return Graph::new(n, edges.into_iter().collect())
+        }
+    }
+    panic!("Could not pair up a {}-regular graph on {} vertices.", d, n);
+}
+
+// Barabási–Albert: starts with a star on k + 1 vertices, then every new vertex is joined
+// to k distinct vertices chosen with probability proportional to their degrees.
+pub fn barabasi_albert(n: usize, k: usize, rng: &mut StdRng) -> Graph {
+    if k == 0 || k >= n {
+        panic!("Every vertex needs between 1 and {} neighbors, not {}.", n.saturating_sub(1), k);
+    }
+    let mut edges: Vec<(usize, usize)> = (1..=k).map(|v| (0, v)).collect();
+    // every vertex appears once for every edge it is on
+    let mut ends: Vec<usize> = edges.iter().flat_map(|&(u, v)| [u, v]).collect();
+    for v in k + 1..n {
+        let mut targets = BTreeSet::new();
+        while targets.len() < k {
+            targets.insert(*ends.choose(rng).unwrap());
+        }
+        for u in targets {
+            edges.push((u, v));
+            ends.push(u);
+            ends.push(v);
+        }
+    }
+    Graph::new(n, edges)
+}
+
+// Vertex r * columns + c is joined to its right and lower neighbors,
+// which wrap around to the first column and row on a torus.
+pub fn grid(rows: usize, columns: usize, torus: bool) -> Graph {
+    let vertex = |r: usize, c: usize| r * columns + c;
+    let mut edges = Vec::new();
+    for r in 0..rows {
+        for c in 0..columns {
+            if c + 1 < columns {
+                edges.push((vertex(r, c), vertex(r, c + 1)));
+            }
+            else if torus && columns > 2 {
+                edges.push((vertex(r, c), vertex(r, 0)));
+            }
+            if r + 1 < rows {
+                edges.push((vertex(r, c), vertex(r + 1, c)));
+            }
+            else if torus && rows > 2 {
+                edges.push((vertex(r, c), vertex(0, c)));
+            }
+        }
+    }
+    Graph::new(rows * columns, edges)
+}
+
+// A graph with a minimum vertex cover of size k that is known. Every edge has an end
+// in a random cover of k vertices, so it covers the graph, and a matching of k edges
+// from the cover to the other vertices means that no smaller cover exists.
+// The other edges from the cover are added with probability p.
+pub fn planted_cover(n: usize, k: usize, p: f64, rng: &mut StdRng) -> (Graph, Vec<usize>) {
+    check_probability(p);
+    if 2 * k > n {
+        panic!("Planting a cover of {} needs at least {} vertices, not {}.", k, 2 * k, n);
+    }
+    let mut vertices: Vec<usize> = (0..n).collect();
+    vertices.shuffle(rng);
+    let (cover, others) = vertices.split_at(k);
+
+    let mut edges: Vec<(usize, usize)> = cover.iter().copied().zip(others.iter().copied()).collect();
+    let in_cover: BTreeSet<usize> = cover.iter().copied().collect();
+    for u in 0..n {
+        for v in u + 1..n {
+            if (in_cover.contains(&u) || in_cover.contains(&v)) && rng.gen_bool(p) {
+                edges.push((u, v));
+            }
+        }
+    }
+
+    let mut cover = cover.to_vec();
+    cover.sort_unstable();
+    (Graph::new(n, edges), cover)
+}
+
+// The graph and, for planted covers, the cover.
+fn generate(model: &GraphModel, n: usize, rng: &mut StdRng) -> (Graph, Option<Vec<usize>>) {
+    match *model {
+        GraphModel::Gnp { p, .. } => (gnp(n, p, rng), None),
+        GraphModel::Gnm { m, .. } => (gnm(n, m, rng), None),
+        GraphModel::Regular { d, .. } => (random_regular(n, d, rng), None),
+        GraphModel::BarabasiAlbert { k, .. } => (barabasi_albert(n, k, rng), None),
+        GraphModel::Grid { rows, columns, torus } => (grid(rows, columns, torus), None),
+        GraphModel::Planted { k, p, .. } => {
+            let (graph, cover) = planted_cover(n, k, p, rng);
+            (graph, Some(cover))
+        }
+    }
+}
+
+fn model_name(model: &GraphModel) -> &'static str {
+    match model {
+        GraphModel::Gnp { .. } => "gnp",
+        GraphModel::Gnm { .. } => "gnm",
+        GraphModel::Regular { .. } => "regular",
+        GraphModel::BarabasiAlbert { .. } => "ba",
+        GraphModel::Grid { torus: false, .. } => "grid",
+        GraphModel::Grid { torus: true, .. } => "torus",
+        GraphModel::Planted { .. } => "planted"
+    }
+}
+
+// Writes count graphs for every size to the directory, named after the model, the size and
+// their number, with the planted covers next to them in .cover files in the numbering of the format.
+// Without a directory the single graph goes to stdout and its planted cover to stderr.
+pub fn run_generate(model: &GraphModel, format: GraphFormat, count: usize, directory: Option<&str>, seed: Option<u64>) {
+    let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    let sizes = match *model {
+        GraphModel::Gnp { n, .. } | GraphModel::Gnm { n, .. } | GraphModel::Regular { n, .. } |
+        GraphModel::BarabasiAlbert { n, .. } | GraphModel::Planted { n, .. } => n.from..=n.to,
+        GraphModel::Grid { rows, columns, .. } => rows * columns..=rows * columns
+    };
+    if directory.is_none() && (count != 1 || sizes.start() != sizes.end()) {
+        crate::usage_error("Several graphs can only be written to a directory given with --out.");
+    }
+
+    let write_cover = |cover: &[usize]| -> String {
+        let cover: Vec<String> = cover.iter().map(|v| (v + format.first_vertex()).to_string()).collect();
+        cover.join(" ")
+    };
+    if let Some(directory) = directory {
+        fs::create_dir_all(directory).unwrap_or_else(|err| panic!("Could not create {}: {}", directory, err));
+    }
+    for n in sizes {
+        for i in 0..count {
+            let (graph, cover) = generate(model, n, &mut rng);
+            match directory {
+                Some(directory) => {
+                    let path = Path::new(directory).join(format!("{}_{}_{}.{}", model_name(model), n, i, format.extension()));
+                    fs::write(&path, graph.write(format))
+                        .unwrap_or_else(|err| panic!("Could not write {}: {}", path.display(), err));
+                    if let Some(cover) = cover {
+                        let cover_path = path.with_extension("cover");
+                        fs::write(&cover_path, write_cover(&cover) + "\n")
+                            .unwrap_or_else(|err| panic!("Could not write {}: {}", cover_path.display(), err));
+                    }
+                    println!("{} {} {}", path.display(), graph.vertices, graph.edges.len());
+                },
+                None => {
+                    print!("{}", graph.write(format));
+                    if let Some(cover) = cover {
+                        eprintln!("minimum cover of {}: {}", cover.len(), write_cover(&cover));
+                    }
+                }
+            }
+        }
+    }
+}
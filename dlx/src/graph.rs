@@ -0,0 +1,377 @@
+use std::fmt;
+use std::fmt::Write;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+
+// Graphs as the vertex cover tools read and write them: a vertex count and a list of edges
+// between the vertices 0..vertices, each written once with the smaller vertex first.
+
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct Graph {
+    pub vertices: usize,
+    pub edges: Vec<(usize, usize)>
+}
+
+// The file formats of graphs. Hash is the '#n m' header followed by one 'u v' line per edge
+// that the instances in vc/instances use, with vertices from 0. DIMACS ('p edge n m' and 'e u v'),
+// PACE 2019 ('p td n m' and 'u v') and METIS (an 'n m' header and the neighbors of every vertex
+// on its own line) number the vertices from 1. An edge list has no header, so it loses
+// the vertices without edges after the last one with edges.
+// DIMACS and PACE comments start with c, METIS comments with % and edge list comments with # or %.
+#[derive(ValueEnum,Clone,Copy,PartialEq,Eq,Debug)]
+pub enum GraphFormat {
+    Hash,
+    Dimacs,
+    Pace,
+    Metis,
+    Edges
+}
+
+impl GraphFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            GraphFormat::Hash => "input",
+            GraphFormat::Dimacs => "dimacs",
+            GraphFormat::Pace => "gr",
+            GraphFormat::Metis => "graph",
+            GraphFormat::Edges => "edges"
+        }
+    }
+
+    // The number of the first vertex in files of this format.
+    pub fn first_vertex(self) -> usize {
+        match self {
+            GraphFormat::Hash | GraphFormat::Edges => 0,
+            _ => 1
+        }
+    }
+}
+
+impl Graph {
+    // Sorts the edges and removes loops and duplicates.
+    pub fn new(vertices: usize, edges: Vec<(usize, usize)>) -> Self {
+        let mut edges: Vec<(usize, usize)> = edges
+            .into_iter()
+            .filter(|(u, v)| u != v)
+            .map(|(u, v)| (u.min(v), u.max(v)))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        assert!(edges.iter().all(|&(_, v)| v < vertices), "An edge has a vertex that is not in the graph.");
+        Graph { vertices, edges }
+    }
+
+    pub fn neighbors(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.vertices];
+        for &(u, v) in self.edges.iter() {
+            neighbors[u].push(v);
+            neighbors[v].push(u);
+        }
+        for list in neighbors.iter_mut() {
+            list.sort_unstable();
+        }
+        neighbors
+    }
+
+    pub fn write(&self, format: GraphFormat) -> String {
+        let (n, m) = (self.vertices, self.edges.len());
+        let mut text = String::new();
+        match format {
+            GraphFormat::Hash => {
+                writeln!(text, "#{} {}", n, m).unwrap();
+                for (u, v) in self.edges.iter() {
+                    writeln!(text, "{} {}", u, v).unwrap();
+                }
+            },
+            GraphFormat::Dimacs => {
+                writeln!(text, "p edge {} {}", n, m).unwrap();
+                for (u, v) in self.edges.iter() {
+                    writeln!(text, "e {} {}", u + 1, v + 1).unwrap();
+                }
+            },
+            GraphFormat::Pace => {
+                writeln!(text, "p td {} {}", n, m).unwrap();
+                for (u, v) in self.edges.iter() {
+                    writeln!(text, "{} {}", u + 1, v + 1).unwrap();
+                }
+            },
+            GraphFormat::Metis => {
+                writeln!(text, "{} {}", n, m).unwrap();
+                for neighbors in self.neighbors() {
+                    let neighbors: Vec<String> = neighbors.iter().map(|v| (v + 1).to_string()).collect();
+                    writeln!(text, "{}", neighbors.join(" ")).unwrap();
+                }
+            },
+            GraphFormat::Edges => {
+                for (u, v) in self.edges.iter() {
+                    writeln!(text, "{} {}", u, v).unwrap();
+                }
+            }
+        }
+        text
+    }
+}
+
+// What is wrong with a graph file, and on which line, counted from 1.
+#[derive(Clone,PartialEq,Eq,Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, message })
+}
+
+fn parse_number(word: &str, what: &str, line: usize) -> Result<usize, ParseError> {
+    word.parse().or_else(|_| error(line, format!("{} is not a valid {}", word, what)))
+}
+
+// The lines that are not empty or comments, with their numbers.
+fn content_lines<'a>(text: &'a str, comments: &'a [&'a str]) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(move |(_, line)| !line.is_empty() && !comments.iter().any(|comment| line.starts_with(comment)))
+}
+
+// A vertex as numbered in the file, moved to start from 0.
+fn parse_vertex(word: &str, first_vertex: usize, vertices: Option<usize>, line: usize) -> Result<usize, ParseError> {
+    let vertex = parse_number(word, "vertex", line)?;
+    match vertices {
+        _ if vertex < first_vertex => error(line, format!("vertex {} is below the first vertex {}", vertex, first_vertex)),
+        Some(n) if vertex - first_vertex >= n => error(line, format!("vertex {} is not one of the {} vertices", vertex, n)),
+        _ => Ok(vertex - first_vertex)
+    }
+}
+
+fn parse_edge(words: &[&str], first_vertex: usize, vertices: Option<usize>, line: usize) -> Result<(usize, usize), ParseError> {
+    match words {
+        [u, v] => {
+            let (u, v) = (parse_vertex(u, first_vertex, vertices, line)?, parse_vertex(v, first_vertex, vertices, line)?);
+            if u == v {
+                return error(line, format!("vertex {} has a loop", u + first_vertex))
+            }
+            Ok((u, v))
+        },
+        _ => error(line, format!("expected an edge of two vertices, found '{}'", words.join(" ")))
+    }
+}
+
+// The header 'p <kind> n m' of DIMACS and PACE files, as n and m.
+fn parse_problem_line(words: &[&str], kinds: &[&str], line: usize) -> Result<(usize, usize), ParseError> {
+    match words {
+        ["p", kind, n, m] if kinds.contains(kind) =>
+            Ok((parse_number(n, "number of vertices", line)?, parse_number(m, "number of edges", line)?)),
+        _ => error(line, format!("expected the header 'p {} n m', found '{}'", kinds[0], words.join(" ")))
+    }
+}
+
+// A file that lost its last lines, or has lines too many, still reads as a graph,
+// so the edges are counted against the header. Duplicate edges count as they are written.
+fn check_edge_count(header_line: usize, m: usize, edges: usize) -> Result<(), ParseError> {
+    if edges != m {
+        return error(header_line, format!("the header has {} edges, but the file has {}", m, edges))
+    }
+    Ok(())
+}
+
+fn read_hash(text: &str) -> Result<Graph, ParseError> {
+    let mut lines = content_lines(text, &[]);
+    let (header_line, vertices, m) = match lines.next() {
+        Some((line, header)) => match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [n, m] if n.starts_with('#') =>
+                (line, parse_number(&n[1..], "number of vertices", line)?, parse_number(m, "number of edges", line)?),
+            _ => return error(line, format!("expected the header '#n m', found '{}'", header))
+        },
+        None => return error(1, String::from("the file is empty"))
+    };
+    let edges: Vec<(usize, usize)> = lines
+        .map(|(line, text)| parse_edge(&text.split_whitespace().collect::<Vec<_>>(), 0, Some(vertices), line))
+        .collect::<Result<_, _>>()?;
+    check_edge_count(header_line, m, edges.len())?;
+    Ok(Graph::new(vertices, edges))
+}
+
+// DIMACS edge lines start with e, PACE edge lines are just the two vertices.
+fn read_problem_file(text: &str, kinds: &[&str], edge_prefix: Option<&str>) -> Result<Graph, ParseError> {
+    let mut header = None;
+    let mut edges = Vec::new();
+    for (line, text) in content_lines(text, &["c"]) {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match header {
+            None => header = Some((line, parse_problem_line(&words, kinds, line)?)),
+            Some(_) if words[0] == "p" => return error(line, String::from("there is a second header")),
+            Some(_) => {
+                let words = match edge_prefix {
+                    Some(prefix) if words[0] == prefix => &words[1..],
+                    Some(prefix) => return error(line, format!("expected an edge '{} u v', found '{}'", prefix, text)),
+                    None => &words[..]
+                };
+                edges.push(parse_edge(words, 1, header.map(|(_, (n, _))| n), line)?);
+            }
+        }
+    }
+    match header {
+        Some((header_line, (vertices, m))) => {
+            check_edge_count(header_line, m, edges.len())?;
+            Ok(Graph::new(vertices, edges))
+        },
+        None => error(1, format!("the header 'p {} n m' is missing", kinds[0]))
+    }
+}
+
+// The header is 'n m', or 'n m fmt ncon' where fmt says whether the vertices (fmt 10 and 11,
+// with ncon weights each) and the edges (fmt 1 and 11) have weights. The weights are skipped.
+fn read_metis(text: &str) -> Result<Graph, ParseError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).filter(|(_, line)| !line.starts_with('%'));
+    let (header_line, vertices, m, vertex_weights, edge_weights) = loop {
+        match lines.next() {
+            Some((_, "")) => continue,
+            Some((line, header)) => {
+                let words: Vec<&str> = header.split_whitespace().collect();
+                if words.len() < 2 || words.len() > 4 {
+                    return error(line, format!("expected the header 'n m [fmt [ncon]]', found '{}'", header))
+                }
+                let vertices = parse_number(words[0], "number of vertices", line)?;
+                let m = parse_number(words[1], "number of edges", line)?;
+                let format = words.get(2).copied().unwrap_or("0");
+                let ncon = words.get(3).map_or(Ok(1), |ncon| parse_number(ncon, "number of vertex weights", line))?;
+                let (vertex_weights, edge_weights) = match format {
+                    "0" | "00" | "000" => (0, false),
+                    "1" | "01" | "001" => (0, true),
+                    "10" | "010" => (ncon, false),
+                    "11" | "011" => (ncon, true),
+                    _ => return error(line, format!("{} is not a supported METIS format", format))
+                };
+                break (line, vertices, m, vertex_weights, edge_weights)
+            },
+            None => return error(1, String::from("the file is empty"))
+        }
+    };
+
+    // every vertex has a line, which is empty when it has no neighbors
+    let mut edges = Vec::new();
+    let mut u = 0;
+    for (line, text) in lines {
+        if u == vertices {
+            if text.is_empty() {
+                continue;
+            }
+            return error(line, format!("there are more than {} vertex lines", vertices))
+        }
+        let words: Vec<&str> = text.split_whitespace().skip(vertex_weights).collect();
+        let step = if edge_weights { 2 } else { 1 };
+        if edge_weights && words.len() % 2 == 1 {
+            return error(line, String::from("a neighbor is missing its edge weight"))
+        }
+        for word in words.iter().step_by(step) {
+            let v = parse_vertex(word, 1, Some(vertices), line)?;
+            if u == v {
+                return error(line, format!("vertex {} has a loop", u + 1))
+            }
+            edges.push((u, v));
+        }
+        u += 1;
+    }
+    if u < vertices {
+        return error(text.lines().count(), format!("expected {} vertex lines, found {}", vertices, u))
+    }
+    // both ends list every edge
+    if edges.len() != 2 * m {
+        return error(header_line, format!("the header has {} edges, but the vertex lines list {} neighbors instead of {}", m, edges.len(), 2 * m))
+    }
+    Ok(Graph::new(vertices, edges))
+}
+
+fn read_edge_list(text: &str) -> Result<Graph, ParseError> {
+    let edges: Vec<(usize, usize)> = content_lines(text, &["#", "%"])
+        .map(|(line, text)| parse_edge(&text.split_whitespace().collect::<Vec<_>>(), 0, None, line))
+        .collect::<Result<_, _>>()?;
+    let vertices = edges.iter().map(|&(u, v)| u.max(v) + 1).max().unwrap_or(0);
+    Ok(Graph::new(vertices, edges))
+}
+
+impl GraphFormat {
+    // Guesses the format from the extension of the file, and from its first lines otherwise.
+    // METIS files have a line for every vertex after their 'n m' header, edge lists usually do not.
+    pub fn detect(filename: Option<&str>, text: &str) -> GraphFormat {
+        let extension = filename
+            .map(|filename| filename.strip_suffix(".gz").unwrap_or(filename))
+            .and_then(|filename| Path::new(filename).extension())
+            .and_then(|extension| extension.to_str());
+        // others, like the .input files of vc/instances that may or may not have a '#n m' header, are read
+        match extension {
+            Some("dimacs") | Some("col") | Some("clq") => return GraphFormat::Dimacs,
+            Some("gr") | Some("hgr") => return GraphFormat::Pace,
+            Some("graph") | Some("metis") => return GraphFormat::Metis,
+            Some("edges") => return GraphFormat::Edges,
+            _ => ()
+        }
+
+        let first_line = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('c') && !line.starts_with('%'));
+        let words: Vec<&str> = first_line.unwrap_or("").split_whitespace().collect();
+        match words[..] {
+            [n, _] if n.starts_with('#') && n[1..].parse::<usize>().is_ok() => GraphFormat::Hash,
+            ["p", "td", _, _] | ["p", "vc", _, _] => GraphFormat::Pace,
+            ["p", _, _, _] => GraphFormat::Dimacs,
+            [n, _, ..] if words.len() <= 4 => {
+                // the lines after the header, including the empty lines of the last vertices without neighbors
+                let lines = text.lines().filter(|line| !line.trim_start().starts_with('%')).count() - 1;
+                if n.parse() == Ok(lines) { GraphFormat::Metis } else { GraphFormat::Edges }
+            },
+            _ => GraphFormat::Edges
+        }
+    }
+}
+
+impl Graph {
+    pub fn read(text: &str, format: GraphFormat) -> Result<Self, ParseError> {
+        match format {
+            GraphFormat::Hash => read_hash(text),
+            GraphFormat::Dimacs => read_problem_file(text, &["edge", "col"], Some("e")),
+            GraphFormat::Pace => read_problem_file(text, &["td", "vc"], None),
+            GraphFormat::Metis => read_metis(text),
+            GraphFormat::Edges => read_edge_list(text)
+        }
+    }
+}
+
+// Reads a graph from the file, or from stdin when there is no file or it is '-'.
+// Files compressed with gzip are recognized by their first bytes, and the format is detected
+// unless it is given. The error says which file could not be read, and the line for invalid graphs.
+pub fn read_graph_file(filename: Option<&str>, format: Option<GraphFormat>) -> Result<(Graph, GraphFormat), String> {
+    let filename = filename.filter(|&filename| filename != "-");
+    let name = filename.unwrap_or("stdin");
+    let mut bytes = Vec::new();
+    match filename {
+        Some(filename) => bytes = fs::read(filename).map_err(|err| format!("{}: {}", filename, err))?,
+        None => {
+            std::io::stdin().read_to_end(&mut bytes).map_err(|err| format!("stdin: {}", err))?;
+        }
+    }
+
+    let text = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut text = String::new();
+        GzDecoder::new(&bytes[..])
+            .read_to_string(&mut text)
+            .map_err(|err| format!("{}: could not decompress: {}", name, err))?;
+        text
+    }
+    else {
+        String::from_utf8(bytes).map_err(|_| format!("{}: not a text file", name))?
+    };
+
+    let format = format.unwrap_or_else(|| GraphFormat::detect(filename, &text));
+    let graph = Graph::read(&text, format).map_err(|err| format!("{}: {}", name, err))?;
+    Ok((graph, format))
+}
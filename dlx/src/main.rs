@@ -1,41 +1,102 @@
+mod bench;
+mod cli;
+mod exact_cover;
+mod generate;
+mod graph;
 mod queens;
+mod report;
+mod serve;
+mod solve;
 mod sudoku;
 mod vertex_cover;
 
-use crate::queens::n_queens_dlx_first_randomized;
-use std::path::Display;
-use std::fs::read_dir;
-use std::fs::metadata;
 use std::time::Duration;
-use std::collections::BTreeSet;
-use std::iter::FromIterator;
-use std::collections::BTreeMap;
-use std::io::BufRead;
-use std::io::BufReader;
-use vertex_cover::vc_reduce_dlxc;
-use sudoku::sudoku_dlx_first;
 use std::time::Instant;
-use std::thread::JoinHandle;
-use std::thread::spawn;
-use std::sync::mpsc::Sender;
-use std::sync::mpsc::Receiver;
-use std::sync::mpsc::channel;
+use std::io::Read;
+use std::collections::BTreeSet;
+use std::sync::atomic::AtomicBool;
 use std::fs;
-use std::env;
-use sudoku::Clue;
-use sudoku::sudoku_dlx;
+use std::process;
+use clap::CommandFactory;
+use clap::Parser;
+use clap::error::ErrorKind;
+use serde_json::json;
+use serde_json::Value;
+use cli::Cli;
+use cli::Command;
+use cli::Format;
+use cli::ModeFlags;
+use cli::Options;
+use cli::QueensAlgorithm;
+use cli::SudokuAlgorithm;
+use cli::VcAlgorithm;
+use cli::VcArgs;
+use graph::Graph;
+use graph::GraphFormat;
+use exact_cover::ExactCoverProblem;
+use exact_cover::SavedSolution;
+use solve::Mode;
+use solve::Outcome;
+use solve::Limits;
+use solve::outcome_of;
+use solve::queens_first_solver;
 use queens::n_queens_dlx_iter;
-use queens::n_queens_dlx_first;
 use queens::n_queens_dfs;
-use queens::n_queens_dfs_first;
+use queens::n_queens_xcc_count;
+use queens::n_queens_dlx_count_unique;
+use queens::n_queens_dlx_trace;
+use libdlx::trace::Trace;
+use libdlx::dancing_cells::Backend;
+use libdlx::serial::Stats;
 use libdlx::*;
-use maplit::*;
 
-static NTHREADS: usize = 14;
-static QUEENS_TIME_LIMIT: Duration = Duration::MAX;
-static VC_TIME_LIMIT: Duration = Duration::MAX;
+static VC_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+static TRACE_MAX_DEPTH: usize = 12;
+static TRACE_MAX_NODES: usize = 2000;
+static DIAGNOSE_RUN_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+fn usage_error(message: &str) -> ! {
+    Cli::command().error(ErrorKind::ArgumentConflict, message).exit()
+}
+
+// Input that cannot be read or parsed exits with 2 like a usage error, but without the usage.
+fn input_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2)
+}
+
+// Reads the whole file, or stdin when there is no file or it is '-'.
+fn read_input(input: Option<&str>) -> Result<String, String> {
+    match input {
+        Some(filename) if filename != "-" => fs::read_to_string(filename)
+            .map_err(|err| format!("Could not read {}: {}.", filename, err)),
+        _ => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|err| format!("Could not read stdin: {}.", err))?;
+            Ok(text)
+        }
+    }
+}
+
+fn stats(solutions: usize, complete: bool, start_time: Instant, cost: Option<usize>) -> Stats {
+    Stats { solutions, complete, millis: start_time.elapsed().as_millis() as u64, cost }
+}
 
-fn print_queens_solution(n: usize, solution: Vec<(usize, usize)>) {
+// Prints the result as text, or as a JSON object with the outcome and the stats added,
+// and exits with the code of the outcome.
+fn finish(options: &Options, outcome: Outcome, stats: Stats, text: &str, mut result: Value) -> ! {
+    match options.output {
+        Format::Text => print!("{}", text),
+        Format::Json => {
+            result["outcome"] = json!(outcome);
+            result["stats"] = json!(stats);
+            println!("{}", result);
+        }
+    }
+    process::exit(outcome.exit_code())
+}
+
+fn format_queens_solution(n: usize, solution: &[(usize, usize)]) -> String {
     let mut output = String::from("");
     for row in 0..n {
         for column in 0..n {
@@ -48,194 +109,410 @@ fn print_queens_solution(n: usize, solution: Vec<(usize, usize)>) {
         }
         output.push('\n');
     }
-    println!("{}", output);
+    output
 }
 
-fn queens_spawn_thread(n: usize, tx: &Sender<(usize, Option<(Vec<(usize, usize)>, Duration)>)>, 
-                       func: fn(usize, Duration) -> Option<Vec<(usize, usize)>>) -> JoinHandle<()> {
-    let thread_tx = tx.clone();
-    spawn(move || {
-        let now = Instant::now();
-        if let Some(solution) = func(n, QUEENS_TIME_LIMIT) {
-            thread_tx.send((n, Some((solution, now.elapsed())))).unwrap();
-        }
-        else {
-            thread_tx.send((n, None)).unwrap();
-        }
-    })
+// All solutions with dlx or dfs, None if the time limit ran out.
+fn queens_all_solutions(n: usize, algorithm: QueensAlgorithm, time_limit: Duration) -> Option<Vec<Vec<(usize, usize)>>> {
+    let start_time = Instant::now();
+    match algorithm {
+        QueensAlgorithm::Dfs => n_queens_dfs(n, time_limit),
+        QueensAlgorithm::Dlx | QueensAlgorithm::DlxRandom => {
+            let mut solutions = Vec::new();
+            for solution in n_queens_dlx_iter(n) {
+                if start_time.elapsed() >= time_limit {
+                    return None
+                }
+                solutions.push(solution);
+            }
+            Some(solutions)
+        },
+        _ => usage_error("Only dlx and dfs can list all solutions of the queens problem.")
+    }
 }
 
-fn queens_message_format(n: usize, message: Option<(Vec<(usize, usize)>, Duration)>) -> String {
-    if let Some((_, time_elapsed)) = message {
-        String::from(format!("{} {}", n, time_elapsed.as_millis()))
+fn run_queens(options: &Options, n: usize, algorithm: QueensAlgorithm, mode: ModeFlags, unique: bool, trace: Option<&str>) -> ! {
+    if let Some(seed) = options.seed {
+        random::seed(seed);
     }
-    else {
-        String::from(format!("{} -", n))
+    if let Some(filename) = trace {
+        write_trace(&n_queens_dlx_trace(n), filename);
+        process::exit(0);
     }
-}
 
-fn solve_queens_threaded(func: fn(usize, Duration) -> Option<Vec<(usize, usize)>>) {
-    let (tx, rx) = channel();
-    let mut thread_handles = Vec::new();
-
-    let n_iter: &mut dyn Iterator<Item=usize> = &mut (5..=80).step_by(5)
-        .into_iter()
-        .map(|n| itertools::repeat_n(n, 10))
-        .flatten();
-    for _ in 0..NTHREADS {
-        let thread = queens_spawn_thread(n_iter.next().unwrap(), &tx, func);
-        thread_handles.push(thread);
+    let time_limit = options.time_limit();
+    let start_time = Instant::now();
+    if unique {
+        let result = n_queens_dlx_count_unique(n, time_limit);
+        let outcome = outcome_of(result.is_some(), start_time, time_limit);
+        let stats = stats(result.map_or(0, |(_, total)| total), result.is_some(), start_time, None);
+        let text = match result {
+            Some((unique, total)) => format!("{} {} {} {}\n", n, unique, total, stats.millis),
+            None => format!("{} -\n", n)
+        };
+        let unique = result.map(|(unique, _)| unique);
+        finish(options, outcome, stats, &text, json!({ "n": n, "unique": unique }));
     }
 
-    let mut i = 0;
-    while let Ok((n, message)) = rx.recv() {
-        println!("{}", queens_message_format(n, message));
-        
-        i += 1;
-        if let Some(n) = n_iter.next() {
-            let thread = queens_spawn_thread(n, &tx, func);
-            thread_handles.push(thread);
-        }
-
-        // break when all calculations have finished
-        // 37 = 40 - 4 + 1
-        if i == 37 {
-            break;
+    match mode.mode() {
+        Mode::First => {
+            let solution = queens_first_solver(algorithm)(n, time_limit);
+            let outcome = outcome_of(solution.is_some(), start_time, time_limit);
+            let stats = stats(solution.iter().count(), outcome != Outcome::Timeout, start_time, None);
+            let text = match solution {
+                Some(_) => format!("{} {}\n", n, stats.millis),
+                None => format!("{} -\n", n)
+            };
+            finish(options, outcome, stats, &text, json!({ "n": n, "solution": solution }));
+        },
+        Mode::Count if algorithm == QueensAlgorithm::Links || algorithm == QueensAlgorithm::Cells => {
+            let backend = if algorithm == QueensAlgorithm::Links { Backend::DancingLinks } else { Backend::DancingCells };
+            let count = n_queens_xcc_count(n, backend, time_limit);
+            let complete = start_time.elapsed() < time_limit;
+            let outcome = if complete { outcome_of(count > 0, start_time, time_limit) } else { Outcome::Timeout };
+            let stats = stats(count, complete, start_time, None);
+            let text = format!("{} {} {}\n", n, count, stats.millis);
+            finish(options, outcome, stats, &text, json!({ "n": n, "count": count }));
+        },
+        mode => {
+            let solutions = queens_all_solutions(n, algorithm, time_limit);
+            let outcome = match &solutions {
+                Some(solutions) => outcome_of(!solutions.is_empty(), start_time, time_limit),
+                None => Outcome::Timeout
+            };
+            let count = solutions.as_ref().map(|solutions| solutions.len());
+            let stats = stats(count.unwrap_or(0), solutions.is_some(), start_time, None);
+            let mut text = String::new();
+            if mode == Mode::All {
+                for solution in solutions.iter().flatten() {
+                    text.push_str(&format_queens_solution(n, solution));
+                    text.push('\n');
+                }
+            }
+            text.push_str(&match count {
+                Some(count) => format!("{} {} {}\n", n, count, stats.millis),
+                None => format!("{} -\n", n)
+            });
+            let result = if mode == Mode::All {
+                json!({ "n": n, "count": count, "solutions": solutions })
+            }
+            else {
+                json!({ "n": n, "count": count })
+            };
+            finish(options, outcome, stats, &text, result);
         }
     }
+}
 
-    for thread in thread_handles {
-        thread.join().unwrap();
+// DOT if the file name ends with .dot, a JSON event log otherwise.
+fn write_trace(trace: &Trace, filename: &str) {
+    let text = if filename.ends_with(".dot") {
+        trace.to_dot(TRACE_MAX_DEPTH, TRACE_MAX_NODES)
     }
+    else {
+        trace.to_json()
+    };
+    fs::write(filename, text).unwrap_or_else(|err| panic!("Could not write the trace to {}: {}", filename, err));
+    println!("{} events, {} solutions", trace.events().len(), trace.solution_count());
 }
 
-fn solve_queens(n: usize, func: fn(usize, Duration) -> Option<Vec<(usize, usize)>>) {
-    let now = Instant::now();
-    func(n, QUEENS_TIME_LIMIT);
-    println!("{} {}", n, now.elapsed().as_millis());
-}
+fn run_sudoku(options: &Options, puzzle: &str, algorithm: SudokuAlgorithm, mode: ModeFlags) -> ! {
+    let clues = sudoku::parse_sudoku(puzzle).unwrap_or_else(|err| input_error(&err));
+    let time_limit = options.time_limit();
+    let start_time = Instant::now();
+    let mode = mode.mode();
+    if mode == Mode::First {
+        let solution = match algorithm {
+            SudokuAlgorithm::Dlx => sudoku::sudoku_dlx_first(&clues),
+            SudokuAlgorithm::Links => sudoku::sudoku_xcc_first(&clues, Backend::DancingLinks),
+            SudokuAlgorithm::Cells => sudoku::sudoku_xcc_first(&clues, Backend::DancingCells)
+        };
+        let outcome = outcome_of(solution.is_some(), start_time, Duration::MAX);
+        let stats = stats(solution.iter().count(), true, start_time, None);
+        let solution = solution.map(|solution| sudoku::format_sudoku(&solution));
+        let text = match &solution {
+            Some(grid) => format!("{}\n{}\n", grid, stats.millis),
+            None => String::from("-\n")
+        };
+        let rows: Option<Vec<&str>> = solution.as_ref().map(|grid| grid.lines().collect());
+        finish(options, outcome, stats, &text, json!({ "solution": rows }));
+    }
 
-fn test_vertex_cover() {
-    let triangle_graph_edges = btreemap!{
-        0 => vec![1,2].into_iter().collect(), 
-        1 => vec![0,2].into_iter().collect(), 
-        2 => vec![0,1].into_iter().collect() 
-    };
-    let cover = vertex_cover::vc_reduce_dlxc(triangle_graph_edges, Duration::from_secs(1));
-    println!("solution: {:?}", cover);
-    
-    println!();
-
-    let star_graph_edges = btreemap!{
-        0 => vec![1,2,3,4].into_iter().collect(), 
-        1 => vec![0].into_iter().collect(), 
-        2 => vec![0].into_iter().collect(), 
-        3 => vec![0].into_iter().collect(), 
-        4 => vec![0].into_iter().collect()
-    };
-    let cover = vertex_cover::vc_reduce_dlxc(star_graph_edges, Duration::from_secs(1));
-    println!("solution: {:?}", cover);
-}
-
-fn read_dimacs_graph(filename: &str) -> (usize, usize, BTreeMap<usize, BTreeSet<usize>>) {
-    let file = fs::File::open(filename).expect("The input file does not exist.");
-    let reader = BufReader::new(file);
-    let mut lines_iter = reader.lines().into_iter()
-        .map(|line| line.unwrap()
-            .to_owned()
-            .split(" ")
-            .map(|word| word.to_owned())
-            .collect::<Vec<_>>())
-        .map(|tokens| (tokens[0].clone(), tokens[1].clone()));
-
-    let mut edges = Vec::<(usize, usize)>::new();
-    // n has form #nnnn
-    let (n, m) = lines_iter.next().unwrap();
-    let vertex_count = str::parse(&n[1..]).unwrap();
-    let edge_count = str::parse(&m).unwrap();
-    for (v1, v2) in lines_iter {
-        edges.push((str::parse(&v1).unwrap(), str::parse(&v2).unwrap()));
-    }
-
-    let mut graph = BTreeMap::<usize, BTreeSet<usize>>::new();
-    for (v1, v2) in edges {
-        if !graph.contains_key(&v1) {
-            graph.insert(v1, BTreeSet::new());
+    if algorithm != SudokuAlgorithm::Dlx {
+        usage_error("Only dlx can count or list the solutions of a sudoku.");
+    }
+    let mut solutions = Vec::new();
+    let mut count = 0;
+    let mut complete = true;
+    for solution in sudoku::sudoku_dlx(&clues) {
+        if start_time.elapsed() >= time_limit {
+            complete = false;
+            break;
         }
-        graph.get_mut(&v1).unwrap().insert(v2);
-
-        if !graph.contains_key(&v2) {
-            graph.insert(v2, BTreeSet::new());
+        count += 1;
+        if mode == Mode::All {
+            solutions.push(sudoku::format_sudoku(&solution));
         }
-        graph.get_mut(&v2).unwrap().insert(v1);
     }
 
-    (vertex_count, edge_count, graph)
+    let outcome = if complete { outcome_of(count > 0, start_time, time_limit) } else { Outcome::Timeout };
+    let stats = stats(count, complete, start_time, None);
+    let mut text: String = solutions.iter().map(|grid| format!("{}\n\n", grid)).collect();
+    text.push_str(&format!("{} {}\n", count, stats.millis));
+    let solutions: Vec<Vec<&str>> = solutions.iter().map(|grid| grid.lines().collect()).collect();
+    let result = if mode == Mode::All { json!({ "count": count, "solutions": solutions }) } else { json!({ "count": count }) };
+    finish(options, outcome, stats, &text, result);
 }
 
-fn solve_reduce_vc(filename: &str) {
-    let (vertex_count, edge_count, graph) = read_dimacs_graph(filename);
+// The cover file lists the vertices as the graph file numbers them. Exits with 1 if the cover
+// is not valid, and only returns errors for cover files that cannot be read.
+fn verify_vertex_cover(graph: &Graph, format: GraphFormat, cover_filename: &str) -> Result<(), String> {
+    let first_vertex = format.first_vertex();
+    let cover = fs::read_to_string(cover_filename)
+        .map_err(|err| format!("Could not read {}: {}.", cover_filename, err))?
+        .split_whitespace()
+        .map(|vertex| str::parse::<usize>(vertex)
+            .ok()
+            .and_then(|vertex| vertex.checked_sub(first_vertex))
+            .ok_or_else(|| format!("{} is not a vertex.", vertex)))
+        .collect::<Result<BTreeSet<usize>, String>>()?;
 
-    let start_time = Instant::now();
-    if let Some(_) = vertex_cover::vc_reduce_dlxc(graph, VC_TIME_LIMIT) {
-        let elapsed = start_time.elapsed();
-        println!("{} {} {}", vertex_count, edge_count, elapsed.as_millis());
+    let uncovered = vertex_cover::uncovered_edges(&graph.edges, &cover);
+    if uncovered.is_empty() {
+        println!("valid vertex cover of size {}", cover.len());
     }
     else {
-        println!("{} {} -", vertex_count, edge_count);
+        println!("{} uncovered edge(s) for cover of size {}", uncovered.len(), cover.len());
+        for (a, b) in uncovered {
+            println!("  {} {}", a + first_vertex, b + first_vertex);
+        }
+        process::exit(1);
     }
+    Ok(())
 }
 
-fn solve_pure_vc(filename: &str) {
-    let (vertex_count, edge_count, graph) = read_dimacs_graph(filename);
+fn run_vc(options: &Options, args: &VcArgs) -> ! {
+    let algorithm = args.algorithm;
+    let (checkpoint, verify) = (args.checkpoint.as_deref(), args.verify.as_deref());
+    let (encode, trace) = (args.encode.as_deref(), args.trace.as_deref());
+    if checkpoint.is_some() && algorithm != VcAlgorithm::Pure {
+        usage_error("Only pure can save checkpoints.");
+    }
+    let (input_graph, format) = graph::read_graph_file(args.input.as_deref(), args.format)
+        .unwrap_or_else(|err| input_error(&err));
+    let (vertex_count, edge_count) = (input_graph.vertices, input_graph.edges.len());
+    let time_limit = options.time_limit();
+    if let Some(cover_filename) = verify {
+        verify_vertex_cover(&input_graph, format, cover_filename).unwrap_or_else(|err| input_error(&err));
+        process::exit(0);
+    }
+    // vertices without edges are never in a minimum cover, so the solvers only get the others
+    let graph = vertex_cover::graph_from_edges(&input_graph.edges);
+    if let Some(filename) = encode {
+        vertex_cover::vc_save_instance(graph, filename);
+        process::exit(0);
+    }
+    if let Some(filename) = trace {
+        write_trace(&vertex_cover::vc_pure_dlxc_trace(graph, time_limit), filename);
+        process::exit(0);
+    }
 
     let start_time = Instant::now();
-    if let Some(_) = vertex_cover::vc_pure_dlxc(graph, VC_TIME_LIMIT) {
-        let elapsed = start_time.elapsed();
-        // println!("{:?}, {:?}", cover.len(), cover);
-        println!("{} {} {}", vertex_count, edge_count, elapsed.as_millis());
+    let cover = match checkpoint {
+        Some(checkpoint) =>
+            vertex_cover::vc_pure_dlxc_resumable(graph, time_limit, VC_CHECKPOINT_INTERVAL, checkpoint),
+        None => solve::vertex_cover_solver(algorithm)(graph, time_limit)
+    };
+
+    // every graph has a cover, so only the time limit can stop the search without one
+    let outcome = if cover.is_some() { Outcome::Solved } else { Outcome::Timeout };
+    let stats = stats(cover.iter().count(), cover.is_some(), start_time, cover.as_ref().map(|cover| cover.len()));
+    let text = match cover {
+        Some(_) => format!("{} {} {}\n", vertex_count, edge_count, stats.millis),
+        None => format!("{} {} -\n", vertex_count, edge_count)
+    };
+    // in the numbering of the graph file, like the covers that --verify reads
+    let cover: Option<Vec<usize>> = cover.map(|cover| cover.iter().map(|v| v + format.first_vertex()).collect());
+    let result = json!({ "vertices": vertex_count, "edges": edge_count, "cover": cover });
+    finish(options, outcome, stats, &text, result);
+}
+
+fn verify_exact_cover(mut problem: ExactCoverProblem, solution_filename: &str) {
+    let solution = SavedSolution::read(solution_filename, &mut problem).unwrap_or_else(|err| input_error(&err));
+    let checker = verify::Problem::new(problem.options.clone(), problem.primary_items(), problem.secondary_items());
+
+    let (mut options, mut violations) = checker.resolve_rows(&solution.rows);
+    options.extend(solution.options);
+    let mut report = checker.verify_options(&options, None, solution.cost);
+    violations.append(&mut report.violations);
+    report.violations = violations;
+
+    let item_name = |i: usize| problem.item_names[i].as_str();
+    let color_name = |c: usize| problem.color_names[c].as_str();
+    let report = report.map_names(item_name, item_name, color_name);
+    println!("{}", report);
+    if !report.is_valid() {
+        process::exit(1);
+    }
+}
+
+fn diagnose_exact_cover(problem: &ExactCoverProblem) {
+    let sets: Vec<_> = problem.options
+        .iter()
+        .map(|(option, _)| option.clone())
+        .collect();
+
+    let core = diagnose::infeasible_core(sets, problem.primary_items(), problem.secondary_items(),
+                                         problem.colors(), DIAGNOSE_RUN_TIME_LIMIT);
+    if let Some(core) = core {
+        let names: Vec<&str> = core.items
+            .iter()
+            .map(|&i| problem.item_names[i].as_str())
+            .collect();
+        println!("infeasible core of {} primary item(s): {}", names.len(), names.join(" "));
+        if !core.minimal {
+            println!("some runs timed out, so the core might not be minimal");
+        }
+        println!("options touching the core:");
+        for i in core.options {
+            println!("  {}: {}", i, problem.format_row(&problem.options[i].0));
+        }
+        process::exit(1);
     }
     else {
-        println!("{} {} -", vertex_count, edge_count);
+        println!("no infeasible core found, the problem has a solution or the search timed out");
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let problem = &args[1];
-    if problem == "queens" {
-        let algo = &args[2];
-        if algo == "dlx" {
-            let n: usize = str::parse(&args[3]).unwrap();
-            solve_queens(n, n_queens_dlx_first);
-        }
-        else if algo == "dlx_random" {
-            let n: usize = str::parse(&args[3]).unwrap();
-            solve_queens(n, n_queens_dlx_first_randomized);
+// Prints the reduced problem to stdout and what was removed to stderr.
+fn preprocess_exact_cover(problem: &ExactCoverProblem) {
+    let checker = verify::Problem::new(problem.options.clone(), problem.primary_items(), problem.secondary_items());
+    let reduced = match preprocess::preprocess(&checker) {
+        Ok(reduced) => reduced,
+        Err(unsupported) => input_error(&unsupported.to_string())
+    };
+    let name = |i: usize| problem.item_names[i].as_str();
+
+    for (option, reason) in reduced.removed_options.iter() {
+        let reason = match reason {
+            preprocess::OptionRemoval::Blocking { item } => format!("blocks item {}", name(*item)),
+            reason => reason.to_string()
+        };
+        eprintln!("removed option {} ({}): it {}", option, problem.format_row(&problem.options[*option].0), reason);
+    }
+    for removal in reduced.removed_items.iter() {
+        match *removal {
+            preprocess::ItemRemoval::Primary { item, same_as } =>
+                eprintln!("removed item {}: it always occurs with {}", name(item), name(same_as)),
+            preprocess::ItemRemoval::Secondary { item, within: Some(primary) } =>
+                eprintln!("removed item {}: it only occurs with {}", name(item), name(primary)),
+            preprocess::ItemRemoval::Secondary { item, within: None } =>
+                eprintln!("removed item {}: it is not used", name(item))
         }
-        else if algo == "dlx_mp" {
-            solve_queens_threaded(n_queens_dlx_first);
+    }
+    for &(item, option) in reduced.forced.iter() {
+        eprintln!("item {} is forced to option {} ({})", name(item), option, problem.format_row(&problem.options[option].0));
+    }
+    if reduced.is_infeasible() {
+        let names: Vec<&str> = reduced.uncoverable.iter().map(|&i| name(i)).collect();
+        eprintln!("no solution, these items cannot be covered: {}", names.join(" "));
+        process::exit(1);
+    }
+    eprintln!("{} options and {} items removed in {} rounds",
+              reduced.removed_options.len(), reduced.removed_items.len(), reduced.rounds);
+
+    let kept: Vec<String> = reduced.option_indices.iter().map(|i| i.to_string()).collect();
+    println!("| original options: {}", kept.join(" "));
+    print!("{}", problem.format(reduced.problem.primary_items(), reduced.problem.secondary_items(), reduced.problem.options()));
+}
+
+// Problems saved as .json or .bin are loaded, anything else is read as text.
+fn read_exact_cover(input: Option<&str>) -> ExactCoverProblem {
+    let problem = match input {
+        Some(filename) if filename.ends_with(".json") || filename.ends_with(".bin") => ExactCoverProblem::read(filename),
+        _ => read_input(input).and_then(|text| ExactCoverProblem::parse(&text))
+    };
+    problem.unwrap_or_else(|err| input_error(&err))
+}
+
+// Solutions are printed one option per line, as SavedSolution reads them,
+// with a blank line after each. Only the cheapest solution is printed with min_cost.
+fn solve_exact_cover(options: &Options, problem: ExactCoverProblem, mode: Mode) -> ! {
+    let instance = solve::exact_cover_instance(problem);
+    let limits = Limits::new(options.time_limit(), None);
+    let never_cancelled = AtomicBool::new(false);
+    let mut solutions = Vec::new();
+    let mut found = |solution: Value, cost: Option<usize>| {
+        if mode == Mode::MinCost {
+            solutions.clear();
         }
-        else if algo == "dfs" {
-            let n: usize = str::parse(&args[3]).unwrap();
-            solve_queens(n, n_queens_dfs_first);
+        solutions.push((solution, cost));
+    };
+    let summary = match mode {
+        Mode::MinCost => solve::search_min_cost(&instance, &limits, &never_cancelled, &mut found),
+        mode => solve::search_exact_cover(&instance, mode, &limits, &never_cancelled, &mut found)
+    };
+
+    let mut text = String::new();
+    for (solution, cost) in solutions.iter() {
+        for row in solution["rows"].as_array().unwrap() {
+            text.push_str(row.as_str().unwrap());
+            text.push('\n');
         }
-        else if algo == "dfs_mp" {
-            solve_queens_threaded(n_queens_dfs_first);
+        if let Some(cost) = cost {
+            text.push_str(&format!("#cost {}\n", cost));
         }
+        text.push('\n');
     }
-    else if problem == "vc" {
-        let mode = &args[2];
-        let filename = &args[3];
-        if mode == "pure" {
-            solve_pure_vc(filename);
-        }
-        else if mode == "reduce" {
-            solve_reduce_vc(filename);
-        }
-        
+    if mode == Mode::Count {
+        text.push_str(&format!("{}\n", summary.solutions));
     }
-    else {
-        test_vertex_cover()
+
+    let solutions: Vec<&Value> = solutions.iter().map(|(solution, _)| solution).collect();
+    let result = json!({ "solutions": solutions });
+    finish(options, summary.outcome(false), summary.stats(&limits), &text, result);
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let options = &cli.options;
+    match cli.command {
+        Command::Queens { n, algorithm, mode, unique, trace } =>
+            run_queens(options, n, algorithm, mode, unique, trace.as_deref()),
+        Command::Sudoku { input, puzzle, algorithm, mode } => {
+            let puzzle = puzzle.unwrap_or_else(|| read_input(input.as_deref()).unwrap_or_else(|err| input_error(&err)));
+            run_sudoku(options, &puzzle, algorithm, mode)
+        },
+        Command::Vc(args) => run_vc(options, &args),
+        Command::Xc { input, mode, min_cost, verify, diagnose, preprocess, encode } => {
+            let problem = read_exact_cover(input.as_deref());
+            if let Some(solution_filename) = verify {
+                verify_exact_cover(problem, &solution_filename);
+            }
+            else if diagnose {
+                diagnose_exact_cover(&problem);
+            }
+            else if preprocess {
+                preprocess_exact_cover(&problem);
+            }
+            else if let Some(filename) = encode {
+                problem.save(&filename);
+            }
+            else {
+                let mode = if min_cost { Mode::MinCost } else { mode.mode() };
+                solve_exact_cover(options, problem, mode);
+            }
+        },
+        Command::Bench { config, results } =>
+            bench::run_bench(options, &config, results.as_deref()).unwrap_or_else(|err| input_error(&err)),
+        Command::Report { inputs, legacy, group_by, format, percentiles, compare } =>
+            report::run_report(&inputs, legacy, &group_by, format, &percentiles, compare.as_deref())
+                .unwrap_or_else(|err| input_error(&err)),
+        Command::Generate { model, format, count, out } =>
+            generate::run_generate(&model, format, count, out.as_deref(), options.seed),
+        Command::Serve { socket } => match socket {
+            Some(path) => serve::serve_socket(&path, options.threads),
+            None => serve::serve_stdio(options.threads)
+        }
     }
 }
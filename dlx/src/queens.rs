@@ -4,10 +4,17 @@ pub use dfs::*;
 mod dlx {
     use libdlx::dlxc::dlxc_first_randomized;
 use std::time::Duration;
+    use std::time::Instant;
     use crate::dlxc::dlxc_first;
     use crate::dlxc::dlxc_iter;
     use crate::dlxc::Item;
+    use libdlx::dancing_cells::Backend;
+    use libdlx::dancing_cells::xcc_first;
+    use libdlx::dancing_cells::xcc_count;
     use libdlx::dlx::*;
+    use libdlx::verify::verify_dlx_solution;
+    use libdlx::symmetry::Symmetry;
+    use libdlx::trace::Trace;
 
     #[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
     pub enum Position {
@@ -70,6 +77,49 @@ use std::time::Duration;
         solution
     }
 
+    fn xcc_option(set: Vec<Position>) -> Vec<Item<Position, Position, ()>> {
+        set.into_iter()
+            .map(|position| match position {
+                Position::Row(_) | Position::Column(_) => Item::Primary(position),
+                _ => Item::Secondary(position)
+            })
+            .collect()
+    }
+
+    fn n_queens_xcc_problem(n: usize) -> Vec<Vec<Item<Position, Position, ()>>> {
+        n_queens_problem(n)
+            .into_iter()
+            .map(xcc_option)
+            .collect()
+    }
+
+    fn xcc_to_solution(xcc_solution: &[Vec<Item<Position, Position, ()>>]) -> Vec<(usize, usize)> {
+        let dlx_solution: Vec<Vec<Position>> = xcc_solution
+            .iter()
+            .map(|option| option
+                .iter()
+                .map(|item| match *item {
+                    Item::Primary(position) | Item::Secondary(position) | Item::ColoredSecondary(position, _) |
+                    Item::ColoredPrimary(position, _) | Item::PrimaryColor(position, _) => position
+                })
+                .collect())
+            .collect();
+        dlx_to_solution(&dlx_solution)
+    }
+
+    pub fn n_queens_xcc_first(n: usize, backend: Backend, time_limit: Duration) -> Option<Vec<(usize, usize)>> {
+        let primary_items = make_primary_items(n);
+        let secondary_items = make_secondary_items(n);
+        xcc_first(backend, n_queens_xcc_problem(n), primary_items, secondary_items, vec![], time_limit)
+            .map(|(solution, _)| xcc_to_solution(&solution))
+    }
+
+    pub fn n_queens_xcc_count(n: usize, backend: Backend, time_limit: Duration) -> usize {
+        let primary_items = make_primary_items(n);
+        let secondary_items = make_secondary_items(n);
+        xcc_count(backend, n_queens_xcc_problem(n), primary_items, secondary_items, vec![], time_limit)
+    }
+
     pub fn n_queens_dlx_iter(n: usize) -> Box<dyn Iterator<Item = Vec<(usize, usize)>>> {
         let problem_sets = n_queens_problem(n);
         let primary_items = make_primary_items(n);
@@ -79,11 +129,93 @@ use std::time::Duration;
             .map(|sol| dlx_to_solution(&sol)))
     }
 
+    fn position_name(position: Position) -> String {
+        match position {
+            Position::Row(i) => format!("r{}", i),
+            Position::Column(j) => format!("c{}", j),
+            Position::UpDiagonal(k) => format!("u{}", k),
+            Position::DownDiagonal(k) => format!("d{}", k)
+        }
+    }
+
+    // The problem in the exact cover text format, see exact_cover.rs.
+    // Option i * n + j places a queen on row i and column j.
+    pub fn n_queens_exact_cover(n: usize) -> String {
+        let mut header: Vec<String> = make_primary_items(n).into_iter().map(position_name).collect();
+        header.push(String::from("|"));
+        header.extend(make_secondary_items(n).into_iter().map(position_name));
+
+        let mut text = header.join(" ");
+        text.push('\n');
+        for set in n_queens_problem(n) {
+            let option: Vec<String> = set.into_iter().map(position_name).collect();
+            text.push_str(&option.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    // The rotations and reflections of the board, acting on the options of n_queens_problem.
+    fn board_symmetry(n: usize) -> Symmetry {
+        let option = |i: usize, j: usize| i * n + j;
+        let mut rotation = vec![0; n * n];
+        let mut reflection = vec![0; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                rotation[option(i, j)] = option(j, n - 1 - i);
+                reflection[option(i, j)] = option(i, n - 1 - j);
+            }
+        }
+        Symmetry::from_option_permutations(n * n, vec![rotation, reflection])
+    }
+
+    // The number of solutions that are different up to rotations and reflections,
+    // and the number of all solutions.
+    pub fn n_queens_dlx_count_unique(n: usize, time_limit: Duration) -> Option<(usize, usize)> {
+        let start_time = Instant::now();
+        let problem_sets = n_queens_problem(n);
+        let primary_items = make_primary_items(n);
+        let secondary_items = make_secondary_items(n);
+        let mut iter = dlx_iter(problem_sets, primary_items, secondary_items)
+            .with_symmetry(board_symmetry(n));
+
+        let mut unique = 0;
+        let mut total = 0;
+        while let Some((solution, orbit_size)) = iter.next_representative() {
+            if start_time.elapsed() >= time_limit {
+                return None
+            }
+            debug_check_solution(n, &Some(solution));
+            unique += 1;
+            total += orbit_size;
+        }
+        Some((unique, total))
+    }
+
+    // The search tree that n_queens_dlx_first explores.
+    pub fn n_queens_dlx_trace(n: usize) -> Trace {
+        let problem_sets = n_queens_problem(n);
+        let primary_items = make_primary_items(n);
+        let secondary_items = make_secondary_items(n);
+        let mut iter = dlx_iter(problem_sets, primary_items, secondary_items).with_trace();
+        iter.next();
+        iter.take_trace().unwrap()
+    }
+
+    fn debug_check_solution(n: usize, solution: &Option<Vec<Vec<Position>>>) {
+        if let (true, Some(solution)) = (cfg!(debug_assertions), solution) {
+            let report = verify_dlx_solution(&n_queens_problem(n), &make_primary_items(n),
+                                             &make_secondary_items(n), solution);
+            debug_assert!(report.is_valid(), "{}", report);
+        }
+    }
+
     pub fn n_queens_dlx_first(n: usize, time_limit: Duration) -> Option<Vec<(usize, usize)>> {
         let problem_sets = n_queens_problem(n);
         let primary_items = make_primary_items(n);
         let secondary_items = make_secondary_items(n);
         let solution = dlx_first(problem_sets, primary_items, secondary_items, time_limit);
+        debug_check_solution(n, &solution);
 
         solution.map(|sol| dlx_to_solution(&sol))
     }
@@ -93,9 +225,28 @@ use std::time::Duration;
         let primary_items = make_primary_items(n);
         let secondary_items = make_secondary_items(n);
         let solution = dlx_first_randomized(problem_sets, primary_items, secondary_items, time_limit);
+        debug_check_solution(n, &solution);
 
         solution.map(|sol| dlx_to_solution(&sol))
     }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::Duration;
+        use libdlx::dancing_cells::Backend;
+        use super::n_queens_dlx_count_unique;
+        use super::n_queens_xcc_count;
+
+        #[test]
+        fn unique_counts_match_known_values() {
+            let unique = [1, 0, 0, 1, 2, 1, 6, 12, 46, 92];
+            for n in 1..=10 {
+                let (count, total) = n_queens_dlx_count_unique(n, Duration::MAX).unwrap();
+                assert_eq!(count, unique[n - 1], "n = {}", n);
+                assert_eq!(total, n_queens_xcc_count(n, Backend::DancingLinks, Duration::MAX), "n = {}", n);
+            }
+        }
+    }
 }
 
 mod dfs {
@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::bench::Row;
+use crate::bench::csv_field;
+use crate::cli::Field;
+use crate::cli::Legacy;
+use crate::cli::TableFormat;
+
+// Tables of benchmark results, the times of each group of runs and how many of them timed out.
+//
+// Results are either the rows written by bench, as CSV or JSON lines, or the older lines
+// of the other commands, see cli::Legacy, with '-' or 'timeout' instead of the time of runs
+// that timed out. Such lines carry no algorithm, so the algorithm is the label given as
+// LABEL=FILE, or the name of the file. Counts are not kept, only n, m and the time.
+//
+// Only the runs that finished, solved or infeasible, count towards the times.
+// The timeout rate counts all others, so runs that failed with an error are included.
+
+#[derive(Clone,PartialEq,Eq,PartialOrd,Ord,Debug)]
+enum Key {
+    Missing,
+    Number(usize),
+    Text(String)
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Missing => Ok(()),
+            Key::Number(number) => write!(f, "{}", number),
+            Key::Text(text) => write!(f, "{}", text)
+        }
+    }
+}
+
+fn field_name(field: Field) -> &'static str {
+    match field {
+        Field::Set => "set",
+        Field::Algorithm => "algorithm",
+        Field::Instance => "instance",
+        Field::N => "n",
+        Field::M => "m"
+    }
+}
+
+fn field_key(row: &Row, field: Field) -> Key {
+    let text = |text: &str| if text.is_empty() { Key::Missing } else { Key::Text(text.to_string()) };
+    let number = |number: Option<usize>| number.map_or(Key::Missing, Key::Number);
+    match field {
+        Field::Set => text(&row.set),
+        Field::Algorithm => text(&row.algorithm),
+        Field::Instance => text(&row.instance),
+        Field::N => number(row.n),
+        Field::M => number(row.m)
+    }
+}
+
+fn finished(row: &Row) -> bool {
+    row.outcome == "solved" || row.outcome == "infeasible"
+}
+
+fn legacy_row(algorithm: &str, n: Option<usize>, m: Option<usize>, millis: Option<u64>) -> Row {
+    Row {
+        set: String::new(),
+        instance: String::new(),
+        algorithm: algorithm.to_string(),
+        repetition: 0,
+        seed: 0,
+        n,
+        m,
+        outcome: String::from(if millis.is_some() { "solved" } else { "timeout" }),
+        millis: millis.unwrap_or(0),
+        cost: None,
+        message: None
+    }
+}
+
+// Splits a CSV line written by bench::csv_field, where quoted fields can contain commas and doubled quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c)
+        }
+    }
+    fields
+}
+
+// An error in a results file, with the line it is on.
+pub struct ParseError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, message })
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, what: &str, line: usize) -> Result<T, ParseError> {
+    text.parse().or_else(|_| error(line, format!("{} is not a valid {}", text, what)))
+}
+
+fn parse_optional<T: std::str::FromStr>(text: &str, what: &str, line: usize) -> Result<Option<T>, ParseError> {
+    if text.is_empty() { Ok(None) } else { parse_number(text, what, line).map(Some) }
+}
+
+// The lines that are not empty, with their numbers.
+fn content_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Row>, ParseError> {
+    let mut lines = content_lines(text);
+    let (header_line, header) = match lines.next() {
+        Some((line, header)) => (line, split_csv_line(header)),
+        None => return error(1, String::from("there is no header"))
+    };
+    let mut columns = Vec::new();
+    for name in ["set", "instance", "algorithm", "repetition", "seed", "n", "m", "outcome", "millis", "cost"].iter() {
+        match header.iter().position(|column| column == name) {
+            Some(column) => columns.push(column),
+            None => return error(header_line, format!("the header has no {} column", name))
+        }
+    }
+    // results written before bench kept the messages of failed runs have no message column
+    let message = header.iter().position(|column| column == "message");
+
+    lines
+        .map(|(line, text)| {
+            let fields = split_csv_line(text);
+            if fields.len() != header.len() {
+                return error(line, format!("expected {} fields, found {}", header.len(), fields.len()));
+            }
+            let field = |k: usize| fields[columns[k]].as_str();
+            Ok(Row {
+                set: field(0).to_string(),
+                instance: field(1).to_string(),
+                algorithm: field(2).to_string(),
+                repetition: parse_number(field(3), "repetition", line)?,
+                seed: parse_number(field(4), "seed", line)?,
+                n: parse_optional(field(5), "n", line)?,
+                m: parse_optional(field(6), "m", line)?,
+                outcome: field(7).to_string(),
+                millis: parse_number(field(8), "time", line)?,
+                cost: parse_optional(field(9), "cost", line)?,
+                message: message.map(|k| fields[k].clone()).filter(|message| !message.is_empty())
+            })
+        })
+        .collect()
+}
+
+fn parse_json_lines(text: &str) -> Result<Vec<Row>, ParseError> {
+    content_lines(text)
+        .map(|(line, text)| serde_json::from_str(text)
+            .or_else(|err| error(line, format!("invalid row: {}", err))))
+        .collect()
+}
+
+// Runs that timed out only print n and '-', whatever the layout of the others.
+fn parse_legacy(text: &str, algorithm: &str, legacy: Legacy) -> Result<Vec<Row>, ParseError> {
+    content_lines(text)
+        .map(|(line, text)| {
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let n = |word: &str| parse_number(word, "n", line).map(Some);
+            let millis = |word: &str| match word {
+                "-" | "timeout" => Ok(None),
+                word => parse_number(word, "time", line).map(Some)
+            };
+            match (legacy, &words[..]) {
+                (_, [first, "-"]) | (_, [first, "timeout"]) => Ok(legacy_row(algorithm, n(first)?, None, None)),
+                (Legacy::Auto, [first, time]) | (Legacy::Queens, [first, time]) =>
+                    Ok(legacy_row(algorithm, n(first)?, None, millis(time)?)),
+                (Legacy::Vc, [first, m, time]) =>
+                    Ok(legacy_row(algorithm, n(first)?, Some(parse_number(m, "m", line)?), millis(time)?)),
+                (Legacy::Count, [first, count, time]) => {
+                    parse_number::<u128>(count, "count", line)?;
+                    Ok(legacy_row(algorithm, n(first)?, None, millis(time)?))
+                },
+                (Legacy::Auto, [first, unique, total, time]) | (Legacy::Unique, [first, unique, total, time]) => {
+                    parse_number::<u128>(unique, "count", line)?;
+                    parse_number::<u128>(total, "count", line)?;
+                    Ok(legacy_row(algorithm, n(first)?, None, millis(time)?))
+                },
+                (Legacy::Auto, [_, _, _]) =>
+                    error(line, format!("'{}' can be 'n m millis' or 'n count millis', choose one with --legacy vc \
+                                         or --legacy count", text)),
+                (legacy, _) => error(line, format!("expected {}, found '{}'", legacy_layout(legacy), text))
+            }
+        })
+        .collect()
+}
+
+fn legacy_layout(legacy: Legacy) -> &'static str {
+    match legacy {
+        Legacy::Auto => "'n millis' or 'n unique total millis'",
+        Legacy::Queens => "'n millis'",
+        Legacy::Vc => "'n m millis'",
+        Legacy::Count => "'n count millis'",
+        Legacy::Unique => "'n unique total millis'"
+    }
+}
+
+// An input is FILE or LABEL=FILE.
+fn read_rows(input: &str, legacy: Legacy) -> Result<Vec<Row>, String> {
+    let (label, filename) = match input.split_once('=') {
+        Some((label, filename)) => (Some(label), filename),
+        None => (None, input)
+    };
+    let text = fs::read_to_string(filename).map_err(|err| format!("Could not read {}: {}.", filename, err))?;
+    let first_line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+
+    let rows = if first_line.starts_with('{') {
+        parse_json_lines(&text)
+    }
+    else if first_line.starts_with("set,") {
+        parse_csv(&text)
+    }
+    else {
+        let stem = Path::new(filename).file_stem().map_or(filename.into(), |stem| stem.to_string_lossy());
+        parse_legacy(&text, &stem, legacy)
+    };
+    let mut rows = rows.map_err(|err| format!("{}: {}.", filename, err))?;
+    if let Some(label) = label {
+        for row in rows.iter_mut() {
+            row.algorithm = label.to_string();
+        }
+    }
+    Ok(rows)
+}
+
+struct Summary {
+    runs: usize,
+    timeouts: usize,
+    // the times of the finished runs, sorted
+    millis: Vec<u64>
+}
+
+impl Summary {
+    fn new(rows: &[&Row]) -> Self {
+        let mut millis: Vec<u64> = rows
+            .iter()
+            .filter(|row| finished(row))
+            .map(|row| row.millis)
+            .collect();
+        millis.sort_unstable();
+        Summary { runs: rows.len(), timeouts: rows.len() - millis.len(), millis }
+    }
+
+    fn mean(&self) -> Option<f64> {
+        if self.millis.is_empty() {
+            None
+        }
+        else {
+            Some(self.millis.iter().sum::<u64>() as f64 / self.millis.len() as f64)
+        }
+    }
+
+    fn median(&self) -> Option<f64> {
+        let len = self.millis.len();
+        if len == 0 {
+            None
+        }
+        else if len % 2 == 1 {
+            Some(self.millis[len / 2] as f64)
+        }
+        else {
+            Some((self.millis[len / 2 - 1] + self.millis[len / 2]) as f64 / 2.0)
+        }
+    }
+
+    // The nearest-rank percentile.
+    fn percentile(&self, percentile: u32) -> Option<u64> {
+        if self.millis.is_empty() {
+            return None
+        }
+        let rank = (percentile as usize * self.millis.len()).div_ceil(100);
+        Some(self.millis[rank.max(1) - 1])
+    }
+
+    fn timeout_rate(&self) -> String {
+        format!("{:.1}%", 100.0 * self.timeouts as f64 / self.runs as f64)
+    }
+}
+
+fn format_millis(millis: Option<f64>) -> String {
+    millis.map_or(String::from("-"), |millis| format!("{:.1}", millis))
+}
+
+struct Table {
+    header: Vec<String>,
+    // how many of the columns on the left are group keys, the others are right-aligned numbers
+    key_columns: usize,
+    rows: Vec<Vec<String>>
+}
+
+fn escape_latex(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '%' | '_' | '&' | '#' | '$' => format!("\\{}", c),
+            c => c.to_string()
+        })
+        .collect()
+}
+
+impl Table {
+    fn render(&self, format: TableFormat) -> String {
+        let mut output = String::new();
+        match format {
+            TableFormat::Markdown => {
+                output.push_str(&format!("| {} |\n", self.header.join(" | ")));
+                let alignments: Vec<&str> = (0..self.header.len())
+                    .map(|i| if i < self.key_columns { "---" } else { "---:" })
+                    .collect();
+                output.push_str(&format!("| {} |\n", alignments.join(" | ")));
+                for row in self.rows.iter() {
+                    output.push_str(&format!("| {} |\n", row.join(" | ")));
+                }
+            },
+            TableFormat::Csv => {
+                for row in Some(&self.header).into_iter().chain(self.rows.iter()) {
+                    let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                    output.push_str(&format!("{}\n", fields.join(",")));
+                }
+            },
+            TableFormat::Latex => {
+                let alignments: String = (0..self.header.len())
+                    .map(|i| if i < self.key_columns { 'l' } else { 'r' })
+                    .collect();
+                output.push_str(&format!("\\begin{{tabular}}{{{}}}\n\\hline\n", alignments));
+                for (i, row) in Some(&self.header).into_iter().chain(self.rows.iter()).enumerate() {
+                    let fields: Vec<String> = row.iter().map(|field| escape_latex(field)).collect();
+                    output.push_str(&format!("{} \\\\\n", fields.join(" & ")));
+                    if i == 0 {
+                        output.push_str("\\hline\n");
+                    }
+                }
+                output.push_str("\\hline\n\\end{tabular}\n");
+            }
+        }
+        output
+    }
+}
+
+// The rows grouped by the values of the fields, leaving out the fields no row has a value for.
+fn group<'a>(rows: &'a [Row], fields: &[Field]) -> (Vec<Field>, BTreeMap<Vec<Key>, Vec<&'a Row>>) {
+    let fields: Vec<Field> = fields
+        .iter()
+        .copied()
+        .filter(|&field| rows.iter().any(|row| field_key(row, field) != Key::Missing))
+        .collect();
+    let mut groups = BTreeMap::<Vec<Key>, Vec<&Row>>::new();
+    for row in rows {
+        let key = fields.iter().map(|&field| field_key(row, field)).collect();
+        groups.entry(key).or_default().push(row);
+    }
+    (fields, groups)
+}
+
+fn summary_table(rows: &[Row], fields: &[Field], percentiles: &[u32]) -> Table {
+    let (fields, groups) = group(rows, fields);
+    let mut header: Vec<String> = fields.iter().map(|&field| field_name(field).to_string()).collect();
+    header.extend(["runs", "timeouts", "mean", "median"].iter().map(|column| column.to_string()));
+    header.extend(percentiles.iter().map(|percentile| format!("p{}", percentile)));
+
+    let rows = groups
+        .iter()
+        .map(|(key, rows)| {
+            let summary = Summary::new(rows);
+            let mut row: Vec<String> = key.iter().map(|key| key.to_string()).collect();
+            row.push(summary.runs.to_string());
+            row.push(summary.timeout_rate());
+            row.push(format_millis(summary.mean()));
+            row.push(format_millis(summary.median()));
+            for &percentile in percentiles {
+                row.push(summary.percentile(percentile).map_or(String::from("-"), |millis| millis.to_string()));
+            }
+            row
+        })
+        .collect();
+
+    Table { header, key_columns: fields.len(), rows }
+}
+
+// The groups without the algorithm, with the times of a and b next to each other
+// and how many times longer a took than b in the median.
+fn comparison_table(rows: &[Row], fields: &[Field], a: &str, b: &str) -> Result<Table, String> {
+    let fields: Vec<Field> = fields.iter().copied().filter(|&field| field != Field::Algorithm).collect();
+    let rows: Vec<Row> = rows
+        .iter()
+        .filter(|row| row.algorithm == a || row.algorithm == b)
+        .cloned()
+        .collect();
+    for algorithm in [a, b] {
+        if !rows.iter().any(|row| row.algorithm == algorithm) {
+            return Err(format!("There are no results for the algorithm {}.", algorithm));
+        }
+    }
+
+    let (fields, groups) = group(&rows, &fields);
+    let mut header: Vec<String> = fields.iter().map(|&field| field_name(field).to_string()).collect();
+    for algorithm in [a, b] {
+        header.extend(["mean", "median", "timeouts"].iter().map(|column| format!("{} {}", algorithm, column)));
+    }
+    header.push(format!("median {}/{}", a, b));
+
+    let rows = groups
+        .iter()
+        .map(|(key, rows)| {
+            let mut row: Vec<String> = key.iter().map(|key| key.to_string()).collect();
+            let mut medians = Vec::new();
+            for algorithm in [a, b] {
+                let runs: Vec<&Row> = rows.iter().copied().filter(|row| row.algorithm == algorithm).collect();
+                let summary = Summary::new(&runs);
+                row.push(format_millis(summary.mean()));
+                row.push(format_millis(summary.median()));
+                row.push(if runs.is_empty() { String::from("-") } else { summary.timeout_rate() });
+                medians.push(summary.median());
+            }
+            row.push(match (medians[0], medians[1]) {
+                (Some(a), Some(b)) if b > 0.0 => format!("{:.2}", a / b),
+                _ => String::from("-")
+            });
+            row
+        })
+        .collect();
+
+    Ok(Table { header, key_columns: fields.len(), rows })
+}
+
+// Returns an error for results that cannot be read and algorithms to compare that have no results.
+pub fn run_report(inputs: &[String], legacy: Legacy, fields: &[Field], format: TableFormat, percentiles: &[u32],
+                  compare: Option<&[String]>) -> Result<(), String> {
+    let mut rows = Vec::new();
+    for input in inputs {
+        rows.extend(read_rows(input, legacy)?);
+    }
+    let table = match compare {
+        Some([a, b]) => comparison_table(&rows, fields, a, b)?,
+        _ => summary_table(&rows, fields, percentiles)
+    };
+    print!("{}", table.render(format));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_layouts_are_not_guessed() {
+        let rows = parse_legacy("8 12 92 12\n9 -\n", "unique", Legacy::Auto).ok().unwrap();
+        assert_eq!(rows.iter().map(|row| (row.n, row.millis)).collect::<Vec<_>>(), [(Some(8), 12), (Some(9), 0)]);
+        assert_eq!(rows[1].outcome, "timeout");
+
+        let err = parse_legacy("5 3\n8 92 12\n", "count", Legacy::Auto).err().unwrap();
+        assert_eq!(err.line, 2);
+        let rows = parse_legacy("7 40 3\n8 92 12\n", "count", Legacy::Count).ok().unwrap();
+        assert_eq!((rows[1].n, rows[1].m, rows[1].millis), (Some(8), None, 12));
+        let rows = parse_legacy("100 400 7\n100 400 -\n", "vc", Legacy::Vc).ok().unwrap();
+        assert_eq!((rows[0].m, rows[1].outcome.as_str()), (Some(400), "timeout"));
+
+        let err = parse_legacy("\n5 3\n6 x\n", "queens", Legacy::Queens).err().unwrap();
+        assert_eq!(err.line, 3);
+        assert!(parse_legacy("5 3 1 2 3\n", "queens", Legacy::Auto).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::thread::spawn;
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use crate::solve::Mode;
+use crate::solve::Instance;
+use crate::solve::Limits;
+use crate::solve::exact_cover_instance;
+use crate::solve::queens_instance;
+use crate::solve::sudoku_instance;
+use crate::solve::search_exact_cover;
+use crate::solve::search_min_cost;
+use crate::solve::search_vertex_cover;
+use crate::exact_cover::ExactCoverProblem;
+
+// A solver daemon that reads one JSON request per line and writes one JSON message per line.
+//
+// A request names a problem and how to solve it:
+//   {"id": 1, "problem": {"queens": 8}, "mode": "count", "time_limit_ms": 1000}
+// The problem is one of {"exact_cover": text} in the format of exact_cover.rs, {"queens": n},
+// {"sudoku": "81 digits with 0 or . for empty cells"} or {"vc": [[a, b], ...]}, a list of edges.
+// The mode is first, all, count or min_cost. It defaults to min_cost for vertex covers,
+// which only support that mode, and to first otherwise. max_solutions stops all and count early.
+//
+// Every solution is sent as soon as it is found, {"id": 1, "solution": ...}, and min_cost sends
+// every cheaper solution with its "cost". The last message for a request is its outcome:
+//   {"id": 1, "outcome": "solved", "stats": {"solutions": 92, "complete": true, "millis": 3, "cost": null}}
+// where the outcome is solved, infeasible, timeout, cancelled or error, and complete tells
+// whether the whole search tree was explored. {"cancel": 1} stops the request with id 1.
+//
+// Requests run concurrently on a fixed number of worker threads, so replies can come in any order.
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProblemSpec {
+    ExactCover(String),
+    Queens(usize),
+    Sudoku(String),
+    Vc(Vec<(usize, usize)>)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    id: Value,
+    problem: ProblemSpec,
+    mode: Option<Mode>,
+    time_limit_ms: Option<u64>,
+    max_solutions: Option<usize>
+}
+
+type Output = Arc<Mutex<Box<dyn Write + Send>>>;
+type Running = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+type Job = (Request, Arc<AtomicBool>);
+
+// A client that went away cannot be told anything, so write errors are ignored.
+fn send(output: &Output, message: Value) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{}", message);
+    let _ = output.flush();
+}
+
+fn error_message(id: &Value, message: &str) -> Value {
+    json!({ "id": id, "outcome": "error", "message": message })
+}
+
+fn send_error(output: &Output, id: &Value, message: &str) {
+    send(output, error_message(id, message));
+}
+
+// What a request asks for, checked before the search starts.
+enum Task<'a> {
+    VertexCover(&'a [(usize, usize)]),
+    ExactCover(Instance, Mode)
+}
+
+// Bad requests are turned into error messages here, so that the solvers only see problems they can solve.
+fn prepare(request: &Request) -> Result<Task<'_>, String> {
+    match (&request.problem, request.mode) {
+        (ProblemSpec::Vc(edges), None) | (ProblemSpec::Vc(edges), Some(Mode::MinCost)) => {
+            if let Some((v, _)) = edges.iter().find(|(v1, v2)| v1 == v2) {
+                return Err(format!("Vertex {} has an edge to itself.", v));
+            }
+            Ok(Task::VertexCover(edges))
+        },
+        (ProblemSpec::Vc(_), Some(mode)) =>
+            Err(format!("Vertex covers can only be solved with min_cost, not {:?}.", mode)),
+        (problem, mode) => {
+            let instance = match problem {
+                ProblemSpec::ExactCover(text) => exact_cover_instance(ExactCoverProblem::parse(text)?),
+                ProblemSpec::Queens(0) => return Err(String::from("The board needs at least one square.")),
+                ProblemSpec::Queens(n) => queens_instance(*n)?,
+                ProblemSpec::Sudoku(puzzle) => sudoku_instance(puzzle)?,
+                ProblemSpec::Vc(_) => unreachable!()
+            };
+            Ok(Task::ExactCover(instance, mode.unwrap_or(Mode::First)))
+        }
+    }
+}
+
+// Sends the solutions as they are found and returns the outcome.
+fn solve(request: &Request, task: &Task<'_>, cancelled: &AtomicBool, output: &Output) -> Value {
+    let limits = Limits::new(request.time_limit_ms.map_or(Duration::MAX, Duration::from_millis), request.max_solutions);
+    let mut found = |solution: Value, cost: Option<usize>| {
+        let mut message = json!({ "id": request.id, "solution": solution });
+        if let Some(cost) = cost {
+            message["cost"] = json!(cost);
+        }
+        send(output, message);
+    };
+
+    let summary = match task {
+        Task::VertexCover(edges) => search_vertex_cover(edges, &limits, cancelled, &mut found),
+        Task::ExactCover(instance, Mode::MinCost) => search_min_cost(instance, &limits, cancelled, &mut found),
+        Task::ExactCover(instance, mode) => search_exact_cover(instance, *mode, &limits, cancelled, &mut found)
+    };
+
+    let outcome = summary.outcome(cancelled.load(Ordering::Relaxed));
+    let stats = summary.stats(&limits);
+    json!({ "id": request.id, "outcome": outcome, "stats": stats })
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    }
+    else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    }
+    else {
+        String::from("The solver failed.")
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Receiver<Job>>>, running: Running, output: Output) {
+    loop {
+        let job = jobs.lock().unwrap().recv();
+        let (request, cancelled) = match job {
+            Ok(job) => job,
+            Err(_) => break
+        };
+
+        // a panic would be a bug in the solvers, it only fails its own request and keeps the worker alive
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| match prepare(&request) {
+            Ok(task) => solve(&request, &task, &cancelled, &output),
+            Err(message) => error_message(&request.id, &message)
+        }))
+        .unwrap_or_else(|payload| error_message(&request.id, &panic_message(payload.as_ref())));
+        // the id can be reused as soon as the outcome is out
+        running.lock().unwrap().remove(&request.id.to_string());
+        send(&output, outcome);
+    }
+}
+
+// Serves the requests read from input until it ends, then waits for the ones still running.
+pub fn serve_session(input: impl BufRead, output: Box<dyn Write + Send>, threads: usize) {
+    let output: Output = Arc::new(Mutex::new(output));
+    let running: Running = Arc::new(Mutex::new(HashMap::new()));
+    let (tx, rx) = channel::<Job>();
+    let rx = Arc::new(Mutex::new(rx));
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let (rx, running, output) = (rx.clone(), running.clone(), output.clone());
+            spawn(move || worker(rx, running, output))
+        })
+        .collect();
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                send_error(&output, &Value::Null, &format!("Invalid JSON: {}.", err));
+                continue;
+            }
+        };
+        // cancelling a request that already finished does nothing
+        if let Some(id) = message.get("cancel") {
+            if let Some(cancelled) = running.lock().unwrap().get(&id.to_string()) {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            continue;
+        }
+
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        let request: Request = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(err) => {
+                send_error(&output, &id, &format!("Invalid request: {}.", err));
+                continue;
+            }
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        {
+            let mut running = running.lock().unwrap();
+            if running.contains_key(&id.to_string()) {
+                send_error(&output, &id, "A request with this id is still running.");
+                continue;
+            }
+            running.insert(id.to_string(), cancelled.clone());
+        }
+        tx.send((request, cancelled)).unwrap();
+    }
+
+    drop(tx);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+pub fn serve_stdio(threads: usize) {
+    let stdin = std::io::stdin();
+    serve_session(stdin.lock(), Box::new(std::io::stdout()), threads);
+}
+
+// Every connection is a session of its own, so cancelling only reaches the requests of the same connection.
+pub fn serve_socket(path: &str, threads: usize) {
+    let listener = UnixListener::bind(path).unwrap_or_else(|err| panic!("Could not listen on {}: {}", path, err));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let output = stream.try_clone().expect("Could not clone the connection.");
+                spawn(move || serve_session(BufReader::new(stream), Box::new(output), threads));
+            },
+            Err(err) => eprintln!("Could not accept a connection: {}", err)
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_requests_are_rejected_before_solving() {
+        let requests = [
+            r#"{"id": 1, "problem": {"sudoku": "123"}}"#,
+            r#"{"id": 2, "problem": {"sudoku": "x........................................................................."}}"#,
+            r#"{"id": 3, "problem": {"exact_cover": "a b\na c"}}"#,
+            r#"{"id": 4, "problem": {"exact_cover": "a b\na $x"}}"#,
+            r#"{"id": 5, "problem": {"exact_cover": ""}}"#,
+            r#"{"id": 6, "problem": {"vc": [[1, 2]]}, "mode": "count"}"#,
+            r#"{"id": 7, "problem": {"vc": [[1, 1]]}}"#,
+            r#"{"id": 8, "problem": {"queens": 0}}"#
+        ];
+        for text in requests.iter() {
+            let request: Request = serde_json::from_str(text).unwrap();
+            assert!(prepare(&request).is_err(), "{} was accepted.", text);
+        }
+
+        let request: Request = serde_json::from_str(r#"{"id": 9, "problem": {"vc": [[1, 2]]}}"#).unwrap();
+        assert!(prepare(&request).is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use serde::Serialize;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use libdlx::dlxc::dlxc_iter;
+use libdlx::dlxc::State;
+use libdlx::min_cost_dlxc::min_cost_dlxc_iter;
+use libdlx::verify;
+use libdlx::serial::Stats;
+use libdlx::dancing_cells::Backend;
+use crate::cli::QueensAlgorithm;
+use crate::cli::VcAlgorithm;
+use crate::exact_cover::ExactCoverProblem;
+use crate::exact_cover::XCItem;
+use crate::queens::n_queens_exact_cover;
+use crate::queens::n_queens_dlx_first;
+use crate::queens::n_queens_dlx_first_randomized;
+use crate::queens::n_queens_dfs_first;
+use crate::queens::n_queens_xcc_first;
+use crate::sudoku;
+use crate::vertex_cover;
+
+// Searches that report their solutions as they find them and can be stopped from another thread,
+// shared by the xc command and dlx serve.
+
+// How many search steps are taken between looking at the clock and the cancel flag.
+static CHECK_INTERVAL: usize = 1024;
+// How long min_cost searches run between looking for cheaper solutions.
+static MIN_COST_SLICE: Duration = Duration::from_millis(50);
+
+#[derive(Deserialize,Clone,Copy,PartialEq,Eq,Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    First,
+    All,
+    Count,
+    MinCost
+}
+
+#[derive(Serialize,Clone,Copy,PartialEq,Eq,Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Solved,
+    Infeasible,
+    Timeout,
+    Cancelled
+}
+
+impl Outcome {
+    // 2 is left to usage errors, which clap reports with it.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Solved => 0,
+            Outcome::Infeasible => 1,
+            Outcome::Timeout | Outcome::Cancelled => 3
+        }
+    }
+}
+
+pub type QueensSolver = fn(usize, Duration) -> Option<Vec<(usize, usize)>>;
+pub type VcSolver = fn(Graph, Duration) -> Option<Vec<usize>>;
+type Graph = BTreeMap<usize, BTreeSet<usize>>;
+
+pub type Render = Box<dyn Fn(&ExactCoverProblem, &[usize]) -> Value>;
+
+// An exact cover problem with a way to turn the options of a solution into something readable.
+pub struct Instance {
+    pub problem: ExactCoverProblem,
+    pub render: Render
+}
+
+pub struct Limits {
+    pub start_time: Instant,
+    pub time_limit: Duration,
+    pub max_solutions: Option<usize>
+}
+
+impl Limits {
+    pub fn new(time_limit: Duration, max_solutions: Option<usize>) -> Self {
+        Limits { start_time: Instant::now(), time_limit, max_solutions }
+    }
+}
+
+pub struct Summary {
+    pub solutions: usize,
+    // the whole search tree was explored
+    pub complete: bool,
+    // the search stopped at max_solutions
+    pub enough: bool,
+    pub cost: Option<usize>
+}
+
+impl Summary {
+    pub fn outcome(&self, cancelled: bool) -> Outcome {
+        if cancelled && !self.complete {
+            Outcome::Cancelled
+        }
+        else if self.complete || self.enough {
+            if self.solutions > 0 { Outcome::Solved } else { Outcome::Infeasible }
+        }
+        else {
+            Outcome::Timeout
+        }
+    }
+
+    pub fn stats(&self, limits: &Limits) -> Stats {
+        Stats {
+            solutions: self.solutions,
+            complete: self.complete,
+            millis: limits.start_time.elapsed().as_millis() as u64,
+            cost: self.cost
+        }
+    }
+}
+
+// A search that gave up without a solution ran out of time if its time limit has passed,
+// otherwise there is no solution.
+pub fn outcome_of(found: bool, start_time: Instant, time_limit: Duration) -> Outcome {
+    if found {
+        Outcome::Solved
+    }
+    else if start_time.elapsed() >= time_limit {
+        Outcome::Timeout
+    }
+    else {
+        Outcome::Infeasible
+    }
+}
+
+pub fn queens_first_solver(algorithm: QueensAlgorithm) -> QueensSolver {
+    match algorithm {
+        QueensAlgorithm::Dlx => n_queens_dlx_first,
+        QueensAlgorithm::DlxRandom => n_queens_dlx_first_randomized,
+        QueensAlgorithm::Dfs => n_queens_dfs_first,
+        QueensAlgorithm::Links => |n, time_limit| n_queens_xcc_first(n, Backend::DancingLinks, time_limit),
+        QueensAlgorithm::Cells => |n, time_limit| n_queens_xcc_first(n, Backend::DancingCells, time_limit)
+    }
+}
+
+// links and cells find some cover, the others a minimum one.
+pub fn vertex_cover_solver(algorithm: VcAlgorithm) -> VcSolver {
+    match algorithm {
+        VcAlgorithm::Pure => vertex_cover::vc_pure_dlxc,
+        VcAlgorithm::Reduce => vertex_cover::vc_reduce_dlxc,
+        VcAlgorithm::Links => |graph, time_limit| vertex_cover::vc_xcc_first(graph, Backend::DancingLinks, time_limit),
+        VcAlgorithm::Cells => |graph, time_limit| vertex_cover::vc_xcc_first(graph, Backend::DancingCells, time_limit)
+    }
+}
+
+pub fn exact_cover_instance(problem: ExactCoverProblem) -> Instance {
+    Instance {
+        problem,
+        render: Box::new(|problem, options| {
+            let rows: Vec<String> = options
+                .iter()
+                .map(|&i| problem.format_row(&problem.options[i].0))
+                .collect();
+            json!({ "options": options, "rows": rows })
+        })
+    }
+}
+
+pub fn queens_instance(n: usize) -> Result<Instance, String> {
+    Ok(Instance {
+        problem: ExactCoverProblem::parse(&n_queens_exact_cover(n))?,
+        render: Box::new(move |_, options| {
+            let queens: Vec<(usize, usize)> = options.iter().map(|&i| (i / n, i % n)).collect();
+            json!(queens)
+        })
+    })
+}
+
+pub fn sudoku_instance(puzzle: &str) -> Result<Instance, String> {
+    let (text, clues) = sudoku::sudoku_exact_cover(&sudoku::parse_sudoku(puzzle)?);
+    Ok(Instance {
+        problem: ExactCoverProblem::parse(&text)?,
+        render: Box::new(move |_, options| {
+            let solution: Vec<sudoku::Clue> = options.iter().map(|&i| clues[i]).collect();
+            let rows: Vec<String> = sudoku::format_sudoku(&solution).lines().map(String::from).collect();
+            json!(rows)
+        })
+    })
+}
+
+// Rows come back from the solvers rotated and recolored, verify.rs matches them to the options.
+fn resolve_options(checker: &verify::Problem<usize, usize, usize>, rows: &[Vec<XCItem>]) -> Vec<usize> {
+    let (mut options, violations) = checker.resolve_rows(rows);
+    assert!(violations.is_empty(), "The solver returned rows that are not options: {:?}", violations);
+    options.sort_unstable();
+    options
+}
+
+// Solves with dlxc, ignoring the costs of the options.
+pub fn search_exact_cover(instance: &Instance, mode: Mode, limits: &Limits, cancelled: &AtomicBool,
+                          found: &mut dyn FnMut(Value, Option<usize>)) -> Summary {
+    let problem = &instance.problem;
+    let checker = verify::Problem::new(problem.options.clone(), problem.primary_items(), problem.secondary_items());
+    let sets = problem.options
+        .iter()
+        .map(|(option, _)| option.clone())
+        .collect();
+    let mut iter = dlxc_iter(sets, problem.primary_items(), problem.secondary_items(), problem.colors());
+
+    let max_solutions = match mode {
+        Mode::First => Some(1),
+        _ => limits.max_solutions
+    };
+    let mut solutions = 0;
+    let mut enough = false;
+    let mut steps = 0;
+    for (state, solution) in iter.by_ref() {
+        if let (State::FoundSolution, Some((rows, _))) = (state, solution) {
+            solutions += 1;
+            if mode != Mode::Count {
+                found((instance.render)(problem, &resolve_options(&checker, &rows)), None);
+            }
+            if max_solutions == Some(solutions) {
+                enough = true;
+                break;
+            }
+        }
+
+        steps += 1;
+        if steps % CHECK_INTERVAL == 0 &&
+            (cancelled.load(Ordering::Relaxed) || limits.start_time.elapsed() >= limits.time_limit) {
+            break;
+        }
+    }
+
+    Summary { solutions, complete: iter.is_exhausted(), enough, cost: None }
+}
+
+// Passes every cheaper solution to found, so the last one is the cheapest.
+pub fn search_min_cost(instance: &Instance, limits: &Limits, cancelled: &AtomicBool,
+                       found: &mut dyn FnMut(Value, Option<usize>)) -> Summary {
+    let problem = &instance.problem;
+    let checker = verify::Problem::new(problem.options.clone(), problem.primary_items(), problem.secondary_items());
+    let mut iter = min_cost_dlxc_iter(problem.options.clone(), problem.primary_items(),
+                                      problem.secondary_items(), problem.colors());
+
+    let mut solutions = 0;
+    let mut cost = None;
+    let mut complete = false;
+    while !complete && !cancelled.load(Ordering::Relaxed) && limits.start_time.elapsed() < limits.time_limit {
+        let remaining = limits.time_limit.saturating_sub(limits.start_time.elapsed());
+        complete = iter.run(min(MIN_COST_SLICE, remaining));
+        if let Some(solution) = iter.incumbent() {
+            if cost != Some(solution.cost) {
+                solutions += 1;
+                cost = Some(solution.cost);
+                found((instance.render)(problem, &resolve_options(&checker, &solution.rows)), cost);
+            }
+        }
+    }
+
+    Summary { solutions, complete, enough: false, cost }
+}
+
+pub fn search_vertex_cover(edges: &[(usize, usize)], limits: &Limits, cancelled: &AtomicBool,
+                           found: &mut dyn FnMut(Value, Option<usize>)) -> Summary {
+    let graph = vertex_cover::graph_from_edges(edges);
+    let mut solutions = 0;
+    let remaining = limits.time_limit.saturating_sub(limits.start_time.elapsed());
+    let (cover, complete) = vertex_cover::vc_pure_dlxc_sliced(graph, remaining, MIN_COST_SLICE,
+        || cancelled.load(Ordering::Relaxed),
+        |cover| {
+            solutions += 1;
+            found(json!(cover), Some(cover.len()));
+        });
+
+    Summary { solutions, complete, enough: false, cost: cover.map(|cover| cover.len()) }
+}
@@ -1,7 +1,8 @@
 use std::collections::{HashMap};
 use std::ops::{Index, IndexMut};
+use itertools::iproduct;
 
-pub use dlx::{sudoku_dlx, sudoku_dlx_first};
+pub use dlx::{sudoku_dlx, sudoku_dlx_first, sudoku_xcc_first};
 pub use dfs::{sudoku_dfs};
 
 #[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
@@ -48,6 +49,35 @@ impl Clue {
     }
 }
 
+// Reads a puzzle given row by row as 81 digits, with '0' or '.' for empty cells.
+pub fn parse_sudoku(puzzle: &str) -> Result<Vec<Clue>, String> {
+    let cells: Vec<char> = puzzle.chars().filter(|c| !c.is_whitespace()).collect();
+    if cells.len() != 81 {
+        return Err(format!("A sudoku puzzle should have 81 cells, but {} were given.", cells.len()));
+    }
+
+    let mut clues = Vec::new();
+    for (i, cell) in cells.into_iter().enumerate() {
+        if cell != '.' && cell != '0' {
+            let number = cell.to_digit(10).ok_or("Sudoku cells should be digits or '.'.")? as u8;
+            clues.push(Clue::new(i as u8 / 9, i as u8 % 9, number));
+        }
+    }
+    Ok(clues)
+}
+
+pub fn format_sudoku(solution: &[Clue]) -> String {
+    let mut grid = [['.'; 9]; 9];
+    for clue in solution {
+        grid[clue.row as usize][clue.column as usize] = (b'0' + clue.number) as char;
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn get_block_index(row: u8, column: u8) -> u8 {
     3 * (row / 3) + column / 3
 }
@@ -144,11 +174,44 @@ fn init_grid(clues: &[Clue]) -> Grid {
     grid
 }
 
+// The puzzle in the exact cover text format, see exact_cover.rs, with an option for the clues
+// and for every number that the clues allow in an empty cell. The options are listed
+// in the order of the returned clues.
+pub fn sudoku_exact_cover(clues: &[Clue]) -> (String, Vec<Clue>) {
+    let grid = init_grid(clues);
+    let mut options = clues.to_vec();
+    for (row, column, number) in iproduct!(0..9, 0..9, 1..=9) {
+        let is_clue = clues.iter().any(|clue| clue.row == row && clue.column == column);
+        if !is_clue && grid[(row, column, number)] {
+            options.push(Clue { row, column, number });
+        }
+    }
+
+    let mut items = Vec::new();
+    for (i, j) in iproduct!(0..9, 0..9) {
+        items.push(format!("p{}{}", i, j));
+    }
+    for (i, n) in iproduct!(0..9, 1..=9) {
+        items.push(format!("r{}{}", i, n));
+        items.push(format!("c{}{}", i, n));
+        items.push(format!("b{}{}", i, n));
+    }
+
+    let mut text = items.join(" ");
+    text.push('\n');
+    for clue in options.iter() {
+        text.push_str(&format!("p{}{} r{}{} c{}{} b{}{}\n", clue.row, clue.column, clue.row, clue.number,
+                               clue.column, clue.number, get_block_index(clue.row, clue.column), clue.number));
+    }
+    (text, options)
+}
 
 mod dlx {
     use itertools::iproduct;
     use std::time::Duration;
     use libdlx::dlx::{dlx_first, dlx_iter, DLXIter};
+    use libdlx::dlxc::Item as XCCItem;
+    use libdlx::dancing_cells::{Backend, xcc_first};
     use super::{Clue, get_block_index, init_grid};
 
     #[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
@@ -254,16 +317,48 @@ mod dlx {
         clues
     }
 
-    pub fn sudoku_dlx(clues: &[Clue]) -> Box<dyn Iterator<Item = Vec<Clue>>> {
-        let items = make_items();
+    // the clues are options too, since their items still have to be covered
+    fn make_sets(clues: &[Clue]) -> Vec<Vec<Item>> {
         let grid = init_grid(clues);
 
         let mut sets = Vec::new();
+        for clue in clues {
+            sets.push(make_option(clue.row, clue.column, clue.number));
+        }
         for (number, row, column) in iproduct!(1..=9, 0..9, 0..9) {
-            if grid[(row, column, number)] {
+            let is_clue = clues.iter().any(|clue| clue.row == row && clue.column == column);
+            if !is_clue && grid[(row, column, number)] {
                 sets.push(make_option(row, column, number));
             }
         }
+        sets
+    }
+
+    pub fn sudoku_xcc_first(clues: &[Clue], backend: Backend) -> Option<Vec<Clue>> {
+        let sets = make_sets(clues)
+            .into_iter()
+            .map(|set| set.into_iter().map(XCCItem::Primary).collect())
+            .collect();
+        let solution = xcc_first::<Item, Item, ()>(backend, sets, make_items(), vec![], vec![], Duration::MAX);
+
+        solution.map(|(options, _)| {
+            let options: Vec<Vec<Item>> = options
+                .iter()
+                .map(|option| option
+                    .iter()
+                    .filter_map(|item| match *item {
+                        XCCItem::Primary(item) => Some(item),
+                        _ => None
+                    })
+                    .collect())
+                .collect();
+            to_clues(&options)
+        })
+    }
+
+    pub fn sudoku_dlx(clues: &[Clue]) -> Box<dyn Iterator<Item = Vec<Clue>>> {
+        let items = make_items();
+        let sets = make_sets(clues);
 
         Box::new(dlx_iter(sets, items, vec![])
             .map(|solution| to_clues(&solution)))
@@ -271,14 +366,7 @@ mod dlx {
 
     pub fn sudoku_dlx_first(clues: &[Clue]) -> Option<Vec<Clue>> {
         let items = make_items();
-        let grid = init_grid(clues);
-
-        let mut sets = Vec::new();
-        for (number, row, column) in iproduct!(1..=9, 0..9, 0..9) {
-            if grid[(row, column, number)] {
-                sets.push(make_option(row, column, number));
-            }
-        }
+        let sets = make_sets(clues);
     
         dlx_first(sets, items, vec![], Duration::MAX).map(|solution| to_clues(&solution))
     }
@@ -1,16 +1,38 @@
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
 pub use dlx::*;
 
-pub fn check_vertex_cover(graph_edges: &Vec<(usize, usize)>, cover: &BTreeSet<usize>) -> bool {
-    let mut is_covered = true;
-    for (a, b) in graph_edges {
-        if !cover.contains(a) && !cover.contains(b) {
-            is_covered = false;
+pub fn check_vertex_cover(graph_edges: &[(usize, usize)], cover: &BTreeSet<usize>) -> bool {
+    uncovered_edges(graph_edges, cover).is_empty()
+}
+
+pub fn uncovered_edges(graph_edges: &[(usize, usize)], cover: &BTreeSet<usize>) -> Vec<(usize, usize)> {
+    graph_edges
+        .iter()
+        .filter(|(a, b)| !cover.contains(a) && !cover.contains(b))
+        .cloned()
+        .collect()
+}
+
+pub fn graph_edges(graph: &BTreeMap<usize, BTreeSet<usize>>) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for (&a, neighbors) in graph {
+        for &b in neighbors.iter().filter(|&&b| b > a) {
+            edges.push((a, b));
         }
     }
 
-    is_covered
+    edges
+}
+
+pub fn graph_from_edges(edges: &[(usize, usize)]) -> BTreeMap<usize, BTreeSet<usize>> {
+    let mut graph = BTreeMap::<usize, BTreeSet<usize>>::new();
+    for &(v1, v2) in edges {
+        graph.entry(v1).or_default().insert(v2);
+        graph.entry(v2).or_default().insert(v1);
+    }
+    graph
 }
 
 mod dlx {
@@ -24,20 +46,37 @@ mod dlx {
     // use libdlx::dlxc::Item;
     use libdlx::min_cost_dlxc::Item;
     use libdlx::min_cost_dlxc::min_cost_dlxc;
+    use libdlx::min_cost_dlxc::DLXCTable;
+    use libdlx::min_cost_dlxc::DLXCIter;
+    use libdlx::min_cost_dlxc::Solution;
+    use libdlx::checkpoint::Checkpoint;
+    use libdlx::trace::Trace;
+    use std::fs;
+    use libdlx::dlxc::Assignment;
+    use libdlx::dlxc::assignment;
     use std::collections::BTreeMap;
     use std::collections::BTreeSet;
     use std::cmp::min;
     use std::cmp::max;
+    use libdlx::verify::Problem;
+    use libdlx::serial;
+    use libdlx::serial::Instance;
+    use serde::Serialize;
+    use serde::Deserialize;
+    use libdlx::dancing_cells::Backend;
+    use libdlx::dancing_cells::xcc_first;
+    use super::graph_edges;
+    use super::uncovered_edges;
     
     type Graph = BTreeMap<usize, BTreeSet<usize>>;
 
-    #[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+    #[derive(Clone,Copy,PartialEq,Eq,Debug,Hash,Serialize,Deserialize)]
     enum Primary {
         Vertex(usize),
         SizeConstraint(usize)
     }
     
-    #[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+    #[derive(Clone,Copy,PartialEq,Eq,Debug,Hash,Serialize,Deserialize)]
     enum Secondary {
         Vertex(usize),
         SumVar(usize)
@@ -237,7 +276,7 @@ mod dlx {
                     .cloned()
                     .collect::<BTreeSet<usize>>();
                 
-                if let Some(b) = intersection.into_iter().next() {
+                if let Some(b) = intersection.into_iter().find(|&b| b != a && graph[&b].len() == 3) {
                     if graph[&v1].contains(&v2) || graph[&v1].contains(&v3) || graph[&v2].contains(&v3) {
                         reductions.exclusions.insert(a);
                         reductions.exclusions.insert(b);
@@ -431,12 +470,70 @@ mod dlx {
         }
     }
 
-    fn component_cover(graph: &Graph, time_limit: Duration) -> Option<Vec<usize>> {
-        let start_time = Instant::now();
-        if graph.is_empty() {
-            return Some(Vec::new());
+    fn cover_from_colors(assignment: &Assignment<Secondary, usize>) -> Vec<usize> {
+        let cover: BTreeSet<usize> = assignment
+            .items_with(1)
+            .filter_map(|item| match item {
+                Secondary::Vertex(i) => Some(i),
+                Secondary::SumVar(_) => None
+            })
+            .collect();
+        cover.into_iter().collect()
+    }
+
+    // Vertices whose excluding option is gone have to be in the cover.
+    // The edges between the other undecided vertices need one endpoint each,
+    // so a matching among them adds to the bound.
+    fn matching_bound(graph: &Graph, table: &DLXCTable<Primary, Secondary, usize>) -> usize {
+        let mut free = BTreeSet::new();
+        let mut forced = 0;
+        for column in table.uncovered_columns() {
+            if let Item::Primary(Primary::Vertex(v)) = table.column_item(column) {
+                match table.column_options(column).next().map(|node| table.option_cost(node)) {
+                    Some(0) => {
+                        free.insert(v);
+                    },
+                    Some(_) => {
+                        forced += 1;
+                    },
+                    None => return usize::MAX
+                }
+            }
+        }
+
+        let mut matched = BTreeSet::new();
+        for &v in free.iter() {
+            if matched.contains(&v) {
+                continue
+            }
+            if let Some(&u) = graph[&v].iter().find(|u| free.contains(u) && !matched.contains(*u)) {
+                matched.insert(u);
+                matched.insert(v);
+            }
+        }
+
+        forced + matched.len() / 2
+    }
+
+    // Takes a vertex of the largest degree until no edges are left.
+    fn greedy_cover(graph: &Graph) -> BTreeSet<usize> {
+        let mut graph = graph.clone();
+        let mut cover = BTreeSet::new();
+        while let Some((&vertex, _)) = graph
+            .iter()
+            .filter(|(_, neighbors)| !neighbors.is_empty())
+            .max_by_key(|(_, neighbors)| neighbors.len()) {
+            delete_vertex(&mut graph, vertex);
+            cover.insert(vertex);
         }
+        cover
+    }
 
+    type CoverSearch = (DLXCIter<Primary, Secondary, usize>, Option<Problem<Primary, Secondary, usize>>);
+
+    // The search for a smallest cover, together with a copy of the problem
+    // to check its solution against in debug builds.
+    fn cover_search(graph: &Graph) -> CoverSearch {
         let primaries = make_primaries(&graph);
         let secondaries = make_secondaries(&graph);
         let sizes: Vec<usize> = (0..=graph.len()).into_iter().collect();
@@ -444,26 +541,50 @@ mod dlx {
         let mut sets = Vec::new();
         add_edge_options(&mut sets, &graph);
 
-        let iter = min_cost_dlxc_iter(sets, primaries, secondaries, sizes);
-        if let Some(solution) = iter.best_solution(time_limit.saturating_sub(start_time.elapsed())) {
-            let mut cover = BTreeSet::new();
-            for (item, color) in solution.colors {
-                if let Secondary::Vertex(i) = item {
-                    if let Some(1) = color {
-                        cover.insert(i);
-                    }
-                }
-            }
-            Some(cover.into_iter().collect())
+        let problem = if cfg!(debug_assertions) {
+            Some(Problem::new(sets.clone(), primaries.clone(), secondaries.clone()))
         }
         else {
             None
+        };
+
+        let bound_graph = graph.clone();
+        let iter = min_cost_dlxc_iter(sets, primaries, secondaries, sizes)
+            .with_upper_bound(greedy_cover(graph).len())
+            .with_lower_bound(move |table| matching_bound(&bound_graph, table));
+        (iter, problem)
+    }
+
+    fn solution_cover(solution: &Solution<Primary, Secondary, usize>, problem: Option<Problem<Primary, Secondary, usize>>) -> Vec<usize> {
+        if let Some(problem) = problem {
+            let report = problem.verify_rows(&solution.rows, Some(&solution.colors), Some(solution.cost));
+            debug_assert!(report.is_valid(), "{}", report);
+        }
+
+        cover_from_colors(&solution.assignment())
+    }
+
+    fn component_cover(graph: &Graph, time_limit: Duration) -> Option<Vec<usize>> {
+        let start_time = Instant::now();
+        if graph.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let (iter, problem) = cover_search(graph);
+        iter.best_solution(time_limit.saturating_sub(start_time.elapsed()))
+            .map(|solution| solution_cover(&solution, problem))
+    }
+
+    fn debug_check_cover(graph: &Graph, cover: &BTreeSet<usize>) {
+        if cfg!(debug_assertions) {
+            let uncovered = uncovered_edges(&graph_edges(graph), cover);
+            debug_assert!(uncovered.is_empty(), "edges {:?} are not covered", uncovered);
         }
-        
     }
 
     pub fn vc_reduce_dlxc(mut graph: Graph, time_limit: Duration) -> Option<Vec<usize>> {
         let start_time = Instant::now();
+        let original_graph = if cfg!(debug_assertions) { Some(graph.clone()) } else { None };
         let mut full_cover = BTreeSet::<usize>::new();
         let reductions = reduce_graph(&mut graph);
         let components = get_connected_components(&graph);
@@ -481,10 +602,135 @@ mod dlx {
             }
         }
         unreduce_cover(&mut full_cover, &reductions);
+        if let Some(graph) = &original_graph {
+            debug_check_cover(graph, &full_cover);
+        }
         Some(full_cover.into_iter().collect())
     }
 
+    // Finds some vertex cover, not necessarily a minimum one,
+    // with the costs dropped from the encoding.
+    pub fn vc_xcc_first(graph: Graph, backend: Backend, time_limit: Duration) -> Option<Vec<usize>> {
+        if graph.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let primaries = make_primaries(&graph);
+        let secondaries = make_secondaries(&graph);
+        let colors = vec![0, 1];
+
+        let mut sets = Vec::new();
+        add_edge_options(&mut sets, &graph);
+        let sets = sets
+            .into_iter()
+            .map(|(set, _)| set)
+            .collect();
+
+        xcc_first(backend, sets, primaries, secondaries, colors, time_limit)
+            .map(|solution| cover_from_colors(&assignment(&solution)))
+    }
+
     pub fn vc_pure_dlxc(graph: Graph, time_limit: Duration) -> Option<Vec<usize>> {
-        component_cover(&graph, time_limit)
+        let cover = component_cover(&graph, time_limit);
+        if let Some(cover) = &cover {
+            debug_check_cover(&graph, &cover.iter().cloned().collect());
+        }
+        cover
+    }
+
+    // The search tree that vc_pure_dlxc explores.
+    pub fn vc_pure_dlxc_trace(graph: Graph, time_limit: Duration) -> Trace {
+        if graph.is_empty() {
+            return Trace::new();
+        }
+
+        let (iter, _) = cover_search(&graph);
+        let mut iter = iter.with_trace();
+        iter.run(time_limit);
+        iter.take_trace().unwrap()
+    }
+
+    // Saves the encoding that vc_pure_dlxc searches, as JSON or in the binary encoding of serial.rs,
+    // so it can be shared with other tools without building it again.
+    pub fn vc_save_instance(graph: Graph, filename: &str) {
+        let mut sets = Vec::new();
+        add_edge_options(&mut sets, &graph);
+        let sizes = (0..=graph.len()).collect();
+        let instance = Instance::new(sets, make_primaries(&graph), make_secondaries(&graph), sizes);
+        serial::save(&instance, filename);
+    }
+
+    // Like vc_pure_dlxc, but searches in slices of interval and passes every cheaper cover
+    // to improved as it is found. The search ends early once stop returns true.
+    // Returns the smallest cover found and whether it is known to be a minimum one.
+    pub fn vc_pure_dlxc_sliced(graph: Graph, time_limit: Duration, interval: Duration,
+                               stop: impl Fn() -> bool, mut improved: impl FnMut(&[usize])) -> (Option<Vec<usize>>, bool) {
+        let start_time = Instant::now();
+        if graph.is_empty() {
+            improved(&[]);
+            return (Some(Vec::new()), true);
+        }
+
+        let (mut iter, problem) = cover_search(&graph);
+        let mut best_cost = None;
+        let mut exhausted = false;
+        while !exhausted && !stop() && start_time.elapsed() < time_limit {
+            let remaining = time_limit.saturating_sub(start_time.elapsed());
+            exhausted = iter.run(min(interval, remaining));
+            if let Some(solution) = iter.incumbent() {
+                if best_cost != Some(solution.cost) {
+                    best_cost = Some(solution.cost);
+                    improved(&cover_from_colors(&solution.assignment()));
+                }
+            }
+        }
+
+        let cover = iter.incumbent().map(|solution| solution_cover(solution, problem));
+        if let Some(cover) = &cover {
+            debug_check_cover(&graph, &cover.iter().cloned().collect());
+        }
+        (cover, exhausted)
+    }
+
+    // Like vc_pure_dlxc, but saves the search to checkpoint_file every interval
+    // and continues from that file if it exists, so a run that was killed can be resumed.
+    // The file is removed once the search is done.
+    pub fn vc_pure_dlxc_resumable(graph: Graph, time_limit: Duration, interval: Duration, 
+                                  checkpoint_file: &str) -> Option<Vec<usize>> {
+        let start_time = Instant::now();
+        if graph.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let (mut iter, problem) = cover_search(&graph);
+        if let Ok(text) = fs::read_to_string(checkpoint_file) {
+            // a checkpoint that does not fit is only reported, the search starts over
+            iter = match Checkpoint::parse(&text).and_then(|checkpoint| iter.restore(&checkpoint)) {
+                Ok(restored) => restored,
+                Err(err) => {
+                    eprintln!("Could not resume from {}: {}. Starting a fresh search.", checkpoint_file, err);
+                    cover_search(&graph).0
+                }
+            };
+        }
+
+        loop {
+            let remaining = time_limit.saturating_sub(start_time.elapsed());
+            if iter.run(min(interval, remaining)) {
+                break
+            }
+            fs::write(checkpoint_file, iter.checkpoint().to_string())
+                .unwrap_or_else(|err| panic!("Could not write the checkpoint to {}: {}", checkpoint_file, err));
+            if start_time.elapsed() >= time_limit {
+                return None
+            }
+        }
+        let _ = fs::remove_file(checkpoint_file);
+
+        let cover = iter.incumbent().map(|solution| solution_cover(solution, problem));
+        if let Some(cover) = &cover {
+            debug_check_cover(&graph, &cover.iter().cloned().collect());
+        }
+        cover
     }
 }
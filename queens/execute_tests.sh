@@ -1,4 +1,3 @@
 #!/bin/bash
-for algo in "${@:1}"; do
-    python3 run_queens.py ./run_$algo.sh > ./results/queens_${algo}_results.txt
-done
\ No newline at end of file
+# $1: config file, bench.json by default
+./dlx bench ${1:-bench.json} --results ./results/queens_results.csv
@@ -1,2 +0,0 @@
-#!/bin/bash
-timeout 60s ./dlx queens dfs $1
@@ -1,2 +0,0 @@
-#!/bin/bash
-timeout 60s ./dlx queens dlx $1
\ No newline at end of file
@@ -1,2 +0,0 @@
-#!/bin/bash
-timeout 60s ./dlx queens dlx_random $1
\ No newline at end of file
@@ -1,2 +1,2 @@
 #!/bin/bash
-timeout 60s ./dlx vc reduce $1
\ No newline at end of file
+timeout 60s ./dlx vc $1 --algorithm reduce
\ No newline at end of file